@@ -0,0 +1,89 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+// Compare the SIMD batch functions against scalar loops.  Run with
+// `cargo bench`.
+
+extern crate ami;
+
+use ami::*;
+use std::hint::black_box;
+use std::time::Instant;
+
+const COUNT: usize = 100_000;
+const ROUNDS: u32 = 100;
+
+/// Time `f` over `ROUNDS`, printing nanoseconds per item.
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+	f();
+
+	let start = Instant::now();
+	for _ in 0..ROUNDS {
+		f();
+	}
+	let elapsed = start.elapsed();
+	let ns = elapsed.as_secs() as f64 * 1.0e9
+		+ f64::from(elapsed.subsec_nanos());
+
+	println!("{:<24} {:>8.3} ns/item", name,
+		ns / f64::from(ROUNDS) / COUNT as f64);
+}
+
+fn main() {
+	let mut seed = 7u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % 10_000) as f32 / 100.0 - 50.0
+	};
+	let bboxes: Vec<BBox> = (0..COUNT).map(|_| {
+		let min = vector!(random(), random(), random());
+		BBox::new(min, min + vector!(random().abs() / 4.0))
+	}).collect();
+	let batch = BBoxBatch::from(&bboxes[..]);
+	let mut out = vec![false; COUNT];
+
+	let q = BBox::new(vector!(-10.0, -20.0, 0.0), vector!(15.0, 5.0, 30.0));
+	bench("collide_bbox scalar", || {
+		for (o, bbox) in out.iter_mut().zip(bboxes.iter()) {
+			*o = black_box(*bbox).collide(q);
+		}
+		black_box(&out);
+	});
+	bench("collide_bbox batch", || {
+		batch_collide_bbox(black_box(&batch), q, &mut out);
+		black_box(&out);
+	});
+
+	let frustum = Frustum::new(vector!(0.0, 0.0, -40.0), 60.0, 0.3, -0.2,
+		1.2, 1.0);
+	let planes = frustum.planes();
+	bench("frustum_cull scalar", || {
+		for (o, bbox) in out.iter_mut().zip(bboxes.iter()) {
+			let bbox = black_box(*bbox);
+			*o = planes.iter()
+				.all(|p| p.classify_bbox(bbox) != Side::Back);
+		}
+		black_box(&out);
+	});
+	bench("frustum_cull batch", || {
+		batch_frustum_cull(black_box(&batch), &frustum, &mut out);
+		black_box(&out);
+	});
+
+	let m = Matrix::default().srt(vector!(2.0, 1.0, 0.5),
+		Rotation::euler(vector!(0.3, 1.0, -0.5)),
+		vector!(5.0, -1.0, 2.0));
+	let mut points: Vec<Vector> = bboxes.iter().map(|b| b.center())
+		.collect();
+	bench("transform_points scalar", || {
+		for p in points.iter_mut() {
+			*p = m.transform_point(black_box(*p));
+		}
+	});
+	bench("transform_points batch", || {
+		batch_transform_points(m, black_box(&mut points));
+	});
+}
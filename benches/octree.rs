@@ -0,0 +1,74 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+// Compare queries on an `Octree` against its `FrozenOctree`.  Run with
+// `cargo bench`.
+
+extern crate ami;
+
+use ami::*;
+use std::hint::black_box;
+use std::time::Instant;
+
+const COUNT: usize = 50_000;
+const QUERIES: usize = 2_000;
+
+struct Item(BBox);
+
+impl Collider for Item {
+	fn bbox(&self) -> BBox {
+		self.0
+	}
+}
+
+/// Time `f`, printing microseconds per query.
+fn bench<F: FnMut() -> usize>(name: &str, mut f: F) {
+	black_box(f());
+
+	let start = Instant::now();
+	let found = f();
+	let elapsed = start.elapsed();
+	let us = elapsed.as_secs() as f64 * 1.0e6
+		+ f64::from(elapsed.subsec_nanos()) / 1.0e3;
+
+	println!("{:<24} {:>8.3} us/query ({} found)", name,
+		us / QUERIES as f64, found);
+}
+
+fn main() {
+	let mut seed = 7u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % 100_000) as f32 / 100.0 - 500.0
+	};
+
+	let mut octree = Octree::new();
+	for i in 0..COUNT {
+		let min = vector!(random(), random(), random());
+		let size = if i % 8 == 0 { 20.0 } else { 1.0 };
+		octree.add(Item(BBox::new(min, min + vector!(size))));
+	}
+	let frozen = octree.freeze();
+	let queries: Vec<BBox> = (0..QUERIES).map(|_| {
+		let min = vector!(random(), random(), random());
+		BBox::new(min, min + vector!(25.0))
+	}).collect();
+
+	bench("query_bbox octree", || {
+		queries.iter().map(|q| octree.query_bbox(*q).len()).sum()
+	});
+	bench("query_bbox frozen", || {
+		queries.iter().map(|q| frozen.query_bbox(*q).len()).sum()
+	});
+	bench("query_ray octree", || {
+		queries.iter().map(|q| octree.query_ray(q.center(),
+			vector!(1.0, 0.5, 0.25)).len()).sum()
+	});
+	bench("query_ray frozen", || {
+		queries.iter().map(|q| frozen.query_ray(q.center(),
+			vector!(1.0, 0.5, 0.25)).len()).sum()
+	});
+}
@@ -0,0 +1,150 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::ops;
+use std::sync::Arc;
+
+/// Number of items in each chunk that can be shared.
+pub(crate) const CHUNK: usize = 256;
+
+/// A `Vec` that can cheaply publish immutable copies of itself (`Shared`),
+/// which share the chunks that haven't changed since the last copy.
+pub(crate) struct Arena<T> {
+	items: Vec<T>,
+	// Which chunks changed since the last `publish()`.
+	dirty: Vec<bool>,
+	published: Vec<Arc<Vec<T>>>,
+}
+
+/// An immutable copy of an `Arena`, which is cheap to clone.
+pub(crate) struct Shared<T> {
+	pub(crate) chunks: Arc<Vec<Arc<Vec<T>>>>,
+	len: usize,
+}
+
+impl<T> Arena<T> {
+	/// Create a new empty arena.
+	pub(crate) fn new() -> Arena<T> {
+		Arena { items: vec![], dirty: vec![], published: vec![] }
+	}
+
+	/// Create a new empty arena with room for `capacity` items.
+	pub(crate) fn with_capacity(capacity: usize) -> Arena<T> {
+		Arena {
+			items: Vec::with_capacity(capacity),
+			dirty: vec![],
+			published: vec![],
+		}
+	}
+
+	/// Get the number of items.
+	pub(crate) fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	/// Reserve room for at least `additional` more items.
+	pub(crate) fn reserve(&mut self, additional: usize) {
+		self.items.reserve(additional);
+	}
+
+	/// Give back unused memory.
+	pub(crate) fn shrink_to_fit(&mut self) {
+		self.items.shrink_to_fit();
+		self.dirty.shrink_to_fit();
+		self.published.shrink_to_fit();
+	}
+
+	/// Add an item to the end.
+	pub(crate) fn push(&mut self, item: T) {
+		let i = self.items.len();
+
+		self.mark(i);
+		self.items.push(item);
+	}
+
+	/// Remove all of the items.
+	pub(crate) fn clear(&mut self) {
+		self.items.clear();
+		self.dirty.clear();
+		self.published.clear();
+	}
+
+	/// Mark the chunk containing item `i` as changed.
+	fn mark(&mut self, i: usize) {
+		let chunk = i / CHUNK;
+
+		if chunk >= self.dirty.len() {
+			self.dirty.resize(chunk + 1, true);
+		}
+		self.dirty[chunk] = true;
+	}
+
+	/// Get an immutable copy, copying only the chunks that changed.
+	pub(crate) fn publish(&mut self) -> Shared<T> where T: Clone {
+		let len = self.items.len();
+		let n_chunks = len.div_ceil(CHUNK);
+
+		self.published.truncate(n_chunks);
+		self.dirty.resize(n_chunks, true);
+
+		for chunk in 0..n_chunks {
+			if chunk < self.published.len() && !self.dirty[chunk] {
+				continue;
+			}
+
+			let start = chunk * CHUNK;
+			let end = len.min(start + CHUNK);
+			let copy = Arc::new(self.items[start..end].to_vec());
+
+			if chunk < self.published.len() {
+				self.published[chunk] = copy;
+			} else {
+				self.published.push(copy);
+			}
+			self.dirty[chunk] = false;
+		}
+
+		Shared { chunks: Arc::new(self.published.clone()), len }
+	}
+}
+
+impl<T> ops::Index<usize> for Arena<T> {
+	type Output = T;
+
+	fn index(&self, index: usize) -> &T {
+		&self.items[index]
+	}
+}
+
+impl<T> ops::IndexMut<usize> for Arena<T> {
+	fn index_mut(&mut self, index: usize) -> &mut T {
+		self.mark(index);
+		&mut self.items[index]
+	}
+}
+
+impl<T> Shared<T> {
+	/// Get the number of items.
+	pub(crate) fn len(&self) -> usize {
+		self.len
+	}
+}
+
+impl<T> Clone for Shared<T> {
+	fn clone(&self) -> Self {
+		Shared { chunks: self.chunks.clone(), len: self.len }
+	}
+}
+
+impl<T> ops::Index<usize> for Shared<T> {
+	type Output = T;
+
+	fn index(&self, index: usize) -> &T {
+		assert!(index < self.len, "index out of bounds");
+
+		&self.chunks[index / CHUNK][index % CHUNK]
+	}
+}
@@ -0,0 +1,470 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::iter::FromIterator;
+use *;
+
+/// Single-precision `BBox`es stored as a structure of arrays (each coordinate
+/// in its own `Vec`), so that batch functions can test 4 or 8 at once with
+/// SIMD.
+#[derive(Clone, Debug, Default)]
+pub struct BBoxBatch {
+	min_x: Vec<f32>,
+	min_y: Vec<f32>,
+	min_z: Vec<f32>,
+	max_x: Vec<f32>,
+	max_y: Vec<f32>,
+	max_z: Vec<f32>,
+}
+
+impl BBoxBatch {
+	/// Create a new empty `BBoxBatch`.
+	pub fn new() -> BBoxBatch {
+		BBoxBatch::default()
+	}
+
+	/// Create a new empty `BBoxBatch` with room for `capacity` `BBox`es.
+	pub fn with_capacity(capacity: usize) -> BBoxBatch {
+		BBoxBatch {
+			min_x: Vec::with_capacity(capacity),
+			min_y: Vec::with_capacity(capacity),
+			min_z: Vec::with_capacity(capacity),
+			max_x: Vec::with_capacity(capacity),
+			max_y: Vec::with_capacity(capacity),
+			max_z: Vec::with_capacity(capacity),
+		}
+	}
+
+	/// Get the number of `BBox`es.
+	pub fn len(&self) -> usize {
+		self.min_x.len()
+	}
+
+	/// Check if there are no `BBox`es.
+	pub fn is_empty(&self) -> bool {
+		self.min_x.is_empty()
+	}
+
+	/// Remove all of the `BBox`es.
+	pub fn clear(&mut self) {
+		self.min_x.clear();
+		self.min_y.clear();
+		self.min_z.clear();
+		self.max_x.clear();
+		self.max_y.clear();
+		self.max_z.clear();
+	}
+
+	/// Add a `BBox` to the end.
+	pub fn push(&mut self, bbox: BBox) {
+		self.min_x.push(bbox.min.x);
+		self.min_y.push(bbox.min.y);
+		self.min_z.push(bbox.min.z);
+		self.max_x.push(bbox.max.x);
+		self.max_y.push(bbox.max.y);
+		self.max_z.push(bbox.max.z);
+	}
+
+	/// Get `BBox` `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is out of bounds.
+	pub fn get(&self, i: usize) -> BBox {
+		BBox::new(
+			vector!(self.min_x[i], self.min_y[i], self.min_z[i]),
+			vector!(self.max_x[i], self.max_y[i], self.max_z[i]),
+		)
+	}
+
+	/// Replace `BBox` `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is out of bounds.
+	pub fn set(&mut self, i: usize, bbox: BBox) {
+		self.min_x[i] = bbox.min.x;
+		self.min_y[i] = bbox.min.y;
+		self.min_z[i] = bbox.min.z;
+		self.max_x[i] = bbox.max.x;
+		self.max_y[i] = bbox.max.y;
+		self.max_z[i] = bbox.max.z;
+	}
+}
+
+impl<'a> From<&'a [BBox]> for BBoxBatch {
+	fn from(bboxes: &'a [BBox]) -> BBoxBatch {
+		bboxes.iter().cloned().collect()
+	}
+}
+
+impl FromIterator<BBox> for BBoxBatch {
+	fn from_iter<I: IntoIterator<Item = BBox>>(iter: I) -> BBoxBatch {
+		let iter = iter.into_iter();
+		let mut batch = BBoxBatch::with_capacity(iter.size_hint().0);
+
+		for bbox in iter {
+			batch.push(bbox);
+		}
+
+		batch
+	}
+}
+
+/// For each `BBox` in `batch`, set `out` to whether it collides with `bbox`
+/// (the same as `BBox::collide()`).
+///
+/// ## Panics
+///
+/// Panics if `out` isn't the same length as `batch`.
+pub fn batch_collide_bbox(batch: &BBoxBatch, bbox: BBox, out: &mut [bool]) {
+	assert_eq!(out.len(), batch.len());
+
+	let done = simd_collide_bbox(batch, bbox, out);
+
+	for (i, o) in out.iter_mut().enumerate().skip(done) {
+		*o = bbox.max.x >= batch.min_x[i]
+			&& batch.max_x[i] >= bbox.min.x
+			&& bbox.max.y >= batch.min_y[i]
+			&& batch.max_y[i] >= bbox.min.y
+			&& bbox.max.z >= batch.min_z[i]
+			&& batch.max_z[i] >= bbox.min.z;
+	}
+}
+
+/// For each `BBox` in `batch`, set `out` to whether it might be visible in
+/// `frustum`; false if it's entirely behind one of `Frustum::planes()` (the
+/// same as `Plane::classify_bbox()` returning `Side::Back`).
+///
+/// ## Panics
+///
+/// Panics if `out` isn't the same length as `batch`.
+pub fn batch_frustum_cull(batch: &BBoxBatch, frustum: &Frustum,
+	out: &mut [bool])
+{
+	assert_eq!(out.len(), batch.len());
+
+	let planes = frustum.planes();
+	let done = simd_frustum_cull(batch, &planes, out);
+
+	for (i, o) in out.iter_mut().enumerate().skip(done) {
+		let (min, max) = (batch.get(i).min, batch.get(i).max);
+
+		// The farthest point in front of each plane must be in front.
+		*o = planes.iter().all(|plane| {
+			let n = plane.facing;
+			let d = (n.x * min.x).max(n.x * max.x)
+				+ (n.y * min.y).max(n.y * max.y)
+				+ (n.z * min.z).max(n.z * max.z);
+
+			d - plane.offset > 0.0
+		});
+	}
+}
+
+/// Transform each point in `points` by `matrix`, in place (the same as
+/// `Matrix::transform_point()`).
+pub fn batch_transform_points(matrix: Matrix, points: &mut [Vector]) {
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		x86::transform_points_sse2(&matrix, points);
+	}
+
+	#[cfg(not(target_arch = "x86_64"))]
+	for p in points.iter_mut() {
+		*p = matrix.transform_point(*p);
+	}
+}
+
+/// Do as many as possible with SIMD, returning how many were done.
+#[cfg(target_arch = "x86_64")]
+fn simd_collide_bbox(batch: &BBoxBatch, bbox: BBox, out: &mut [bool])
+	-> usize
+{
+	unsafe {
+		if is_x86_feature_detected!("avx2") {
+			x86::collide_bbox_avx2(batch, bbox, out)
+		} else {
+			x86::collide_bbox_sse2(batch, bbox, out)
+		}
+	}
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn simd_collide_bbox(_: &BBoxBatch, _: BBox, _: &mut [bool]) -> usize {
+	0
+}
+
+/// Do as many as possible with SIMD, returning how many were done.
+#[cfg(target_arch = "x86_64")]
+fn simd_frustum_cull(batch: &BBoxBatch, planes: &[Plane; 6],
+	out: &mut [bool]) -> usize
+{
+	unsafe {
+		if is_x86_feature_detected!("avx2") {
+			x86::frustum_cull_avx2(batch, planes, out)
+		} else {
+			x86::frustum_cull_sse2(batch, planes, out)
+		}
+	}
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn simd_frustum_cull(_: &BBoxBatch, _: &[Plane; 6], _: &mut [bool])
+	-> usize
+{
+	0
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+	use std::arch::x86_64::*;
+	use super::BBoxBatch;
+	use *;
+
+	/// Set `out` from the bits of a SIMD comparison mask.
+	fn set_mask(out: &mut [bool], mask: i32) {
+		for (k, o) in out.iter_mut().enumerate() {
+			*o = mask & (1 << k) != 0;
+		}
+	}
+
+	/// Returns how many were done (a multiple of 8).
+	#[target_feature(enable = "avx2")]
+	pub(super) unsafe fn collide_bbox_avx2(batch: &BBoxBatch, bbox: BBox,
+		out: &mut [bool]) -> usize
+	{
+		let (q_min_x, q_min_y, q_min_z) = (_mm256_set1_ps(bbox.min.x),
+			_mm256_set1_ps(bbox.min.y), _mm256_set1_ps(bbox.min.z));
+		let (q_max_x, q_max_y, q_max_z) = (_mm256_set1_ps(bbox.max.x),
+			_mm256_set1_ps(bbox.max.y), _mm256_set1_ps(bbox.max.z));
+		let end = batch.len() / 8 * 8;
+		let load = |v: &[f32], i: usize| {
+			_mm256_loadu_ps(v[i..].as_ptr())
+		};
+
+		for i in (0..end).step_by(8) {
+			let x = _mm256_and_ps(
+				_mm256_cmp_ps(q_max_x, load(&batch.min_x, i),
+					_CMP_GE_OQ),
+				_mm256_cmp_ps(load(&batch.max_x, i), q_min_x,
+					_CMP_GE_OQ));
+			let y = _mm256_and_ps(
+				_mm256_cmp_ps(q_max_y, load(&batch.min_y, i),
+					_CMP_GE_OQ),
+				_mm256_cmp_ps(load(&batch.max_y, i), q_min_y,
+					_CMP_GE_OQ));
+			let z = _mm256_and_ps(
+				_mm256_cmp_ps(q_max_z, load(&batch.min_z, i),
+					_CMP_GE_OQ),
+				_mm256_cmp_ps(load(&batch.max_z, i), q_min_z,
+					_CMP_GE_OQ));
+			let mask = _mm256_movemask_ps(_mm256_and_ps(x,
+				_mm256_and_ps(y, z)));
+
+			set_mask(&mut out[i..i + 8], mask);
+		}
+
+		end
+	}
+
+	/// Returns how many were done (a multiple of 4).
+	pub(super) unsafe fn collide_bbox_sse2(batch: &BBoxBatch, bbox: BBox,
+		out: &mut [bool]) -> usize
+	{
+		let (q_min_x, q_min_y, q_min_z) = (_mm_set1_ps(bbox.min.x),
+			_mm_set1_ps(bbox.min.y), _mm_set1_ps(bbox.min.z));
+		let (q_max_x, q_max_y, q_max_z) = (_mm_set1_ps(bbox.max.x),
+			_mm_set1_ps(bbox.max.y), _mm_set1_ps(bbox.max.z));
+		let end = batch.len() / 4 * 4;
+		let load = |v: &[f32], i: usize| _mm_loadu_ps(v[i..].as_ptr());
+
+		for i in (0..end).step_by(4) {
+			let x = _mm_and_ps(
+				_mm_cmpge_ps(q_max_x, load(&batch.min_x, i)),
+				_mm_cmpge_ps(load(&batch.max_x, i), q_min_x));
+			let y = _mm_and_ps(
+				_mm_cmpge_ps(q_max_y, load(&batch.min_y, i)),
+				_mm_cmpge_ps(load(&batch.max_y, i), q_min_y));
+			let z = _mm_and_ps(
+				_mm_cmpge_ps(q_max_z, load(&batch.min_z, i)),
+				_mm_cmpge_ps(load(&batch.max_z, i), q_min_z));
+			let mask = _mm_movemask_ps(_mm_and_ps(x,
+				_mm_and_ps(y, z)));
+
+			set_mask(&mut out[i..i + 4], mask);
+		}
+
+		end
+	}
+
+	/// Returns how many were done (a multiple of 8).
+	#[target_feature(enable = "avx2")]
+	pub(super) unsafe fn frustum_cull_avx2(batch: &BBoxBatch,
+		planes: &[Plane; 6], out: &mut [bool]) -> usize
+	{
+		let end = batch.len() / 8 * 8;
+		let load = |v: &[f32], i: usize| {
+			_mm256_loadu_ps(v[i..].as_ptr())
+		};
+		// Farthest distance along `n` of the box from `min` to `max`.
+		let far = |n: __m256, min: __m256, max: __m256| {
+			_mm256_max_ps(_mm256_mul_ps(n, min),
+				_mm256_mul_ps(n, max))
+		};
+
+		for i in (0..end).step_by(8) {
+			let (min_x, min_y, min_z) = (load(&batch.min_x, i),
+				load(&batch.min_y, i), load(&batch.min_z, i));
+			let (max_x, max_y, max_z) = (load(&batch.max_x, i),
+				load(&batch.max_y, i), load(&batch.max_z, i));
+			let mut visible = _mm256_castsi256_ps(
+				_mm256_set1_epi32(-1));
+
+			for plane in planes.iter() {
+				let n = plane.facing;
+				let d = _mm256_add_ps(_mm256_add_ps(
+					far(_mm256_set1_ps(n.x), min_x, max_x),
+					far(_mm256_set1_ps(n.y), min_y, max_y)),
+					far(_mm256_set1_ps(n.z), min_z, max_z));
+				let d = _mm256_sub_ps(d,
+					_mm256_set1_ps(plane.offset));
+				let front = _mm256_cmp_ps(d,
+					_mm256_setzero_ps(), _CMP_GT_OQ);
+
+				visible = _mm256_and_ps(visible, front);
+			}
+
+			let mask = _mm256_movemask_ps(visible);
+			set_mask(&mut out[i..i + 8], mask);
+		}
+
+		end
+	}
+
+	/// Returns how many were done (a multiple of 4).
+	pub(super) unsafe fn frustum_cull_sse2(batch: &BBoxBatch,
+		planes: &[Plane; 6], out: &mut [bool]) -> usize
+	{
+		let end = batch.len() / 4 * 4;
+		let load = |v: &[f32], i: usize| _mm_loadu_ps(v[i..].as_ptr());
+		// Farthest distance along `n` of the box from `min` to `max`.
+		let far = |n: __m128, min: __m128, max: __m128| {
+			_mm_max_ps(_mm_mul_ps(n, min), _mm_mul_ps(n, max))
+		};
+
+		for i in (0..end).step_by(4) {
+			let (min_x, min_y, min_z) = (load(&batch.min_x, i),
+				load(&batch.min_y, i), load(&batch.min_z, i));
+			let (max_x, max_y, max_z) = (load(&batch.max_x, i),
+				load(&batch.max_y, i), load(&batch.max_z, i));
+			let mut visible = _mm_castsi128_ps(_mm_set1_epi32(-1));
+
+			for plane in planes.iter() {
+				let n = plane.facing;
+				let d = _mm_add_ps(_mm_add_ps(
+					far(_mm_set1_ps(n.x), min_x, max_x),
+					far(_mm_set1_ps(n.y), min_y, max_y)),
+					far(_mm_set1_ps(n.z), min_z, max_z));
+				let d = _mm_sub_ps(d,
+					_mm_set1_ps(plane.offset));
+
+				visible = _mm_and_ps(visible,
+					_mm_cmpgt_ps(d, _mm_setzero_ps()));
+			}
+
+			set_mask(&mut out[i..i + 4], _mm_movemask_ps(visible));
+		}
+
+		end
+	}
+
+	pub(super) unsafe fn transform_points_sse2(matrix: &Matrix,
+		points: &mut [Vector])
+	{
+		let m: &[[f32; 4]; 4] = matrix.as_ref();
+		let c0 = _mm_loadu_ps(m[0].as_ptr());
+		let c1 = _mm_loadu_ps(m[1].as_ptr());
+		let c2 = _mm_loadu_ps(m[2].as_ptr());
+		let c3 = _mm_loadu_ps(m[3].as_ptr());
+		let mut result = [0.0f32; 4];
+
+		for p in points.iter_mut() {
+			let v = _mm_add_ps(_mm_add_ps(_mm_add_ps(
+				_mm_mul_ps(c0, _mm_set1_ps(p.x)),
+				_mm_mul_ps(c1, _mm_set1_ps(p.y))),
+				_mm_mul_ps(c2, _mm_set1_ps(p.z))),
+				c3);
+
+			_mm_storeu_ps(result.as_mut_ptr(), v);
+			*p = vector!(result[0], result[1], result[2]);
+		}
+	}
+}
+
+#[test]
+fn test_batch() {
+	// Deterministic pseudo-random boxes.
+	let mut seed = 11u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % 10_000) as f32 / 100.0 - 50.0
+	};
+
+	// Not a multiple of 8, so the scalar path is used for the end.
+	let bboxes: Vec<BBox> = (0..1003).map(|_| {
+		let min = vector!(random(), random(), random());
+		BBox::new(min, min + vector!(random().abs() / 4.0))
+	}).collect();
+	let batch = BBoxBatch::from(&bboxes[..]);
+	let mut out = vec![false; batch.len()];
+	assert_eq!(batch.get(10).all_points(), bboxes[10].all_points());
+
+	let q = BBox::new(vector!(-10.0, -20.0, 0.0), vector!(15.0, 5.0, 30.0));
+	batch_collide_bbox(&batch, q, &mut out);
+	for (bbox, hit) in bboxes.iter().zip(out.iter()) {
+		assert_eq!(bbox.collide(q), *hit);
+	}
+	assert!(out.iter().any(|hit| *hit) && !out.iter().all(|hit| *hit));
+
+	// The SSE2 path is only used without AVX2, so check it directly.
+	#[cfg(target_arch = "x86_64")]
+	{
+		let mut sse2 = vec![false; batch.len()];
+		let done = unsafe {
+			x86::collide_bbox_sse2(&batch, q, &mut sse2)
+		};
+		assert_eq!(&sse2[..done], &out[..done]);
+	}
+
+	let frustum = Frustum::new(vector!(0.0, 0.0, -40.0), 60.0, 0.3, -0.2,
+		1.2, 1.0);
+	batch_frustum_cull(&batch, &frustum, &mut out);
+	for (bbox, visible) in bboxes.iter().zip(out.iter()) {
+		let expect = frustum.planes().iter()
+			.all(|plane| plane.classify_bbox(*bbox) != Side::Back);
+		assert_eq!(expect, *visible);
+	}
+	assert!(out.iter().any(|hit| *hit) && !out.iter().all(|hit| *hit));
+	#[cfg(target_arch = "x86_64")]
+	{
+		let planes = frustum.planes();
+		let mut sse2 = vec![false; batch.len()];
+		let done = unsafe {
+			x86::frustum_cull_sse2(&batch, &planes, &mut sse2)
+		};
+		assert_eq!(&sse2[..done], &out[..done]);
+	}
+
+	let m = Matrix::default().srt(vector!(2.0, 1.0, 0.5),
+		Rotation::euler(vector!(0.3, 1.0, -0.5)),
+		vector!(5.0, -1.0, 2.0));
+	let mut points: Vec<Vector> = bboxes.iter().map(|b| b.min).collect();
+	batch_transform_points(m, &mut points);
+	for (bbox, p) in bboxes.iter().zip(points.iter()) {
+		assert_eq!(m.transform_point(bbox.min), *p);
+	}
+}
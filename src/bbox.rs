@@ -7,49 +7,56 @@
 use std::{ fmt, ops };
 use *;
 
-/// Single-precision bounding box
+/// Bounding box, single-precision unless `S` is `f64`.
 #[derive(Clone, Copy)]
-pub struct BBox {
-	pub(crate) min: Vector,
-	pub(crate) max: Vector,
+pub struct BBox<S = f32> {
+	pub(crate) min: Vec3<S>,
+	pub(crate) max: Vec3<S>,
 }
 
-impl fmt::Debug for BBox {
+impl<S: Scalar> fmt::Debug for BBox<S> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(f, "{:?} → {:?}", self.min, self.max)
 	}
 }
 
-impl ops::Sub<Vector> for BBox {
-	type Output = BBox;
+impl<S: Scalar> ops::Sub<Vec3<S>> for BBox<S> {
+	type Output = BBox<S>;
 
-	fn sub(self, other: Vector) -> Self::Output {
+	fn sub(self, other: Vec3<S>) -> Self::Output {
 		BBox::new(self.min - other, self.max - other)
 	}
 }
 
-impl ops::Add<Vector> for BBox {
-	type Output = BBox;
+impl<S: Scalar> ops::Add<Vec3<S>> for BBox<S> {
+	type Output = BBox<S>;
 
-	fn add(self, other: Vector) -> Self::Output {
+	fn add(self, other: Vec3<S>) -> Self::Output {
 		BBox::new(self.min + other, self.max + other)
 	}
 }
 
-impl Into<BCube> for BBox {
-	fn into(self) -> BCube {
-		let span = self.max - self.min;
+impl<S: Scalar> From<BBox<S>> for BCube<S> {
+	fn from(bbox: BBox<S>) -> BCube<S> {
+		let span = bbox.max - bbox.min;
 
 		BCube {
-			center: self.center(),
-			half_len: span.x.max(span.y).max(span.z) / 2.0,
+			center: bbox.center(),
+			half_len: span.x.max(span.y).max(span.z)
+				/ S::from_f64(2.0),
 		}
 	}
 }
 
-impl BBox {
+impl From<BBox> for BBox<f64> {
+	fn from(bbox: BBox) -> BBox<f64> {
+		bbox.cast()
+	}
+}
+
+impl<S: Scalar> BBox<S> {
 	/// Create an new `BBox` at position `p`.
-	pub fn new(min: Vector, max: Vector) -> BBox {
+	pub fn new(min: Vec3<S>, max: Vec3<S>) -> BBox<S> {
 		assert!(min.x <= max.x);
 		assert!(min.y <= max.y);
 		assert!(min.z <= max.z);
@@ -57,8 +64,14 @@ impl BBox {
 		BBox { min, max }
 	}
 
+	/// Convert to another precision, rounding if it is less precise.
+	/// Rounding may make a narrower `BBox` slightly smaller.
+	pub fn cast<T: Scalar>(self) -> BBox<T> {
+		BBox { min: self.min.cast(), max: self.max.cast() }
+	}
+
 	/// Check if `BBox` collides with `other` `BBox`.
-	pub fn collide(&self, other: BBox) -> bool {
+	pub fn collide(&self, other: BBox<S>) -> bool {
 		   other.max.x >= self.min.x
 		&& self.max.x >= other.min.x
 		&& other.max.y >= self.min.y
@@ -68,13 +81,65 @@ impl BBox {
 	}
 
 	/// Check if `BBox` collides with `BCube`.
-	pub fn collide_bcube(&self, bcube: BCube) -> bool {
+	pub fn collide_bcube(&self, bcube: BCube<S>) -> bool {
 		let (max, min) = bcube.to_point_pair();
 		self.collide(BBox::new(min, max))
 	}
 
+	/// Check if `BBox` is entirely within `BCube`.
+	pub(crate) fn within_bcube(&self, bcube: BCube<S>) -> bool {
+		let (max, min) = bcube.to_point_pair();
+
+		   self.min.x >= min.x && self.max.x <= max.x
+		&& self.min.y >= min.y && self.max.y <= max.y
+		&& self.min.z >= min.z && self.max.z <= max.z
+	}
+
+	/// Get the smallest `BBox` containing both `self` and `other`.
+	pub(crate) fn union(&self, other: BBox<S>) -> BBox<S> {
+		BBox {
+			min: self.min.min(other.min),
+			max: self.max.max(other.max),
+		}
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// first hit at `origin + direction * t`, or `None` if it misses.  If
+	/// the origin is inside the `BBox`, `t` is `0.0`.
+	pub fn intersect_ray(&self, origin: Vec3<S>, direction: Vec3<S>)
+		-> Option<S>
+	{
+		let mut tmin = S::zero();
+		let mut tmax = S::infinity();
+		let o: [S; 3] = origin.into();
+		let d: [S; 3] = direction.into();
+		let min: [S; 3] = self.min.into();
+		let max: [S; 3] = self.max.into();
+
+		for i in 0..3 {
+			if d[i] == S::zero() {
+				// Parallel to slab, must be within it.
+				if o[i] < min[i] || o[i] > max[i] {
+					return None;
+				}
+			} else {
+				let a = (min[i] - o[i]) / d[i];
+				let b = (max[i] - o[i]) / d[i];
+
+				tmin = tmin.max(a.min(b));
+				tmax = tmax.min(a.max(b));
+
+				if tmin > tmax {
+					return None;
+				}
+			}
+		}
+
+		Some(tmin)
+	}
+
 	/// Get which sides are the farthest away from the bbox (to extend).
-	pub(crate) fn bcube_sides(&self, bcube: BCube) -> (bool, bool, bool) {
+	pub(crate) fn bcube_sides(&self, bcube: BCube<S>) -> (bool, bool, bool) {
 		let (max, min) = bcube.to_point_pair();
 		let cube = BBox::new(min, max);
 
@@ -89,7 +154,7 @@ impl BBox {
 	}
 
 	/// Check if `BBox` collides with point `p`.
-	pub fn collide_vec3(&self, p: Vector) -> bool {
+	pub fn collide_vec3(&self, p: Vec3<S>) -> bool {
 		(p.x >= self.min.x) &&
 		(p.x <= self.max.x) &&
 		(p.y >= self.min.y) &&
@@ -99,39 +164,46 @@ impl BBox {
 	}
 
 	/// Get all 8 points of the `BBox`.
-	pub fn all_points(&self) -> [Vector; 8] {
+	pub fn all_points(&self) -> [Vec3<S>; 8] {
 		[
-			Vector::new(self.min.x, self.min.y, self.min.z),
-			Vector::new(self.min.x, self.min.y, self.max.z),
-			Vector::new(self.min.x, self.max.y, self.min.z),
-			Vector::new(self.min.x, self.max.y, self.max.z),
-			Vector::new(self.max.x, self.min.y, self.min.z),
-			Vector::new(self.max.x, self.min.y, self.max.z),
-			Vector::new(self.max.x, self.max.y, self.min.z),
-			Vector::new(self.max.x, self.max.y, self.max.z),
+			Vec3::new(self.min.x, self.min.y, self.min.z),
+			Vec3::new(self.min.x, self.min.y, self.max.z),
+			Vec3::new(self.min.x, self.max.y, self.min.z),
+			Vec3::new(self.min.x, self.max.y, self.max.z),
+			Vec3::new(self.max.x, self.min.y, self.min.z),
+			Vec3::new(self.max.x, self.min.y, self.max.z),
+			Vec3::new(self.max.x, self.max.y, self.min.z),
+			Vec3::new(self.max.x, self.max.y, self.max.z),
 		]
 	}
 
 	/// Get all 6 sides of the `BBox` as points.
-	pub fn side_points(&self) -> [Vector; 6] {
+	pub fn side_points(&self) -> [Vec3<S>; 6] {
 		let center = self.center();
 
 		[
-			Vector::new(self.min.x, center.y, center.z),
-			Vector::new(center.x, self.min.y, center.z),
-			Vector::new(center.x, center.y, self.min.z),
-			Vector::new(self.max.x, center.y, center.z),
-			Vector::new(center.x, self.max.y, center.z),
-			Vector::new(center.x, center.y, self.max.z),
+			Vec3::new(self.min.x, center.y, center.z),
+			Vec3::new(center.x, self.min.y, center.z),
+			Vec3::new(center.x, center.y, self.min.z),
+			Vec3::new(self.max.x, center.y, center.z),
+			Vec3::new(center.x, self.max.y, center.z),
+			Vec3::new(center.x, center.y, self.max.z),
 		]
 	}
 
 	/// Get the center of the `BBox`.
-	pub fn center(&self) -> Vector {
-		Vector::new(
-			(self.min.x + self.max.x) / 2.0,
-			(self.min.y + self.max.y) / 2.0,
-			(self.min.z + self.max.z) / 2.0,
-		)
+	pub fn center(&self) -> Vec3<S> {
+		(self.min + self.max) / S::from_f64(2.0)
+	}
+
+	/// Get the `BBox` around this one after it's transformed by `matrix`.
+	pub fn transform(&self, matrix: &Mat4<S>) -> BBox<S> {
+		let first = matrix.transform_point(self.min);
+
+		let (min, max) = self.all_points().iter()
+			.map(|p| matrix.transform_point(*p))
+			.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+
+		BBox::new(min, max)
 	}
 }
@@ -7,62 +7,73 @@
 use std::fmt;
 use *;
 
-/// Single-precision bounding cube
+/// Bounding cube, single-precision unless `S` is `f64`.
 #[derive(Clone, Copy, PartialEq)]
-pub struct BCube {
-	pub(crate) center: Vector,
-	pub(crate) half_len: f32,
+pub struct BCube<S = f32> {
+	pub(crate) center: Vec3<S>,
+	pub(crate) half_len: S,
 }
 
-impl fmt::Debug for BCube {
+impl<S: Scalar> fmt::Debug for BCube<S> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(f, "{:?}±{:?}", self.center, self.half_len)
 	}
 }
 
-impl BCube {
+impl From<BCube> for BCube<f64> {
+	fn from(bcube: BCube) -> BCube<f64> {
+		bcube.cast()
+	}
+}
+
+impl<S: Scalar> BCube<S> {
 	/// Create an new empty `BCube`.
-	pub fn empty() -> BCube {
-		let z = 0.0;
+	pub fn empty() -> BCube<S> {
+		let z = S::zero();
 
-		BCube { center: Vector::new(z, z, z), half_len: -1.0 }
+		BCube { center: Vec3::new(z, z, z), half_len: -S::one() }
 	}
 
 	/// Create an new `BCube` at position `p`.
-	pub fn new(p: Vector) -> BCube {
-		BCube { center: p, half_len: 1.0 }
+	pub fn new(p: Vec3<S>) -> BCube<S> {
+		BCube { center: p, half_len: S::one() }
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> BCube<T> {
+		BCube { center: self.center.cast(), half_len: self.half_len.cast() }
 	}
 
 	/// Extend the `BCube` to accommodate for `BBox`
-	pub fn extend(&mut self, p: BBox) {
+	pub fn extend(&mut self, p: BBox<S>) {
 //		println!("Extend: {:?}", p);
 		self.center = self.move_center(p);
-		self.half_len *= 2.0;
+		self.half_len *= S::from_f64(2.0);
 	}
 
-	fn move_center(&self, p: BBox) -> Vector {
+	fn move_center(&self, p: BBox<S>) -> Vec3<S> {
 		let (maxx, maxy, maxz) = p.bcube_sides(*self);
 
 //		println!("MAX: {} {} {}", maxx, maxy, maxz);
 
-		let min = self.center - vector!(self.half_len);
-		let max = self.center + vector!(self.half_len);
+		let min = self.center - Vec3::from(self.half_len);
+		let max = self.center + Vec3::from(self.half_len);
 
 		match (maxx, maxy, maxz) {
-			(false, false, false) => Vector::new(min.x, min.y, min.z),
-			(false, false, true) => Vector::new(min.x, min.y, max.z),
-			(false, true, false) => Vector::new(min.x, max.y, min.z),
-			(false, true, true) => Vector::new(min.x, max.y, max.z),
-			(true, false, false) => Vector::new(max.x, min.y, min.z),
-			(true, false, true) => Vector::new(max.x, min.y, max.z),
-			(true, true, false) => Vector::new(max.x, max.y, min.z),
-			(true, true, true) => Vector::new(max.x, max.y, max.z),
+			(false, false, false) => Vec3::new(min.x, min.y, min.z),
+			(false, false, true) => Vec3::new(min.x, min.y, max.z),
+			(false, true, false) => Vec3::new(min.x, max.y, min.z),
+			(false, true, true) => Vec3::new(min.x, max.y, max.z),
+			(true, false, false) => Vec3::new(max.x, min.y, min.z),
+			(true, false, true) => Vec3::new(max.x, min.y, max.z),
+			(true, true, false) => Vec3::new(max.x, max.y, min.z),
+			(true, true, true) => Vec3::new(max.x, max.y, max.z),
 		}
 	}
 
 	/// Check if `BCube` contains point `p`.
-	pub fn contains(&self, p: Vector) -> bool {
-		let Vector { x, y, z } = self.center;
+	pub fn contains(&self, p: Vec3<S>) -> bool {
+		let Vec3 { x, y, z } = self.center;
 		let hl = self.half_len;
 		(p.x >= x - hl) &&
 		(p.x < x + hl) &&
@@ -73,43 +84,42 @@ impl BCube {
 	}
 
 	/// Get two opposite points that are the bounds of the BCube.
-	pub fn to_point_pair(&self) -> (Vector, Vector) {
-		let half_cube = Vector::new(self.half_len, self.half_len,
-			self.half_len);
+	pub fn to_point_pair(&self) -> (Vec3<S>, Vec3<S>) {
+		let half_cube = Vec3::from(self.half_len);
 
 		(self.center + half_cube, self.center - half_cube)
 	}
 
 	/// Turn into a bbox.
-	pub fn to_bbox(&self) -> BBox {
+	pub fn to_bbox(&self) -> BBox<S> {
 		let (max, min) = self.to_point_pair();
 		BBox::new(min, max)
 	}
 
 	/// Get all 6 points or the `BCube`.
-	pub fn all_points(&self) -> [Vector; 7] {
-		let z = 0.0;
+	pub fn all_points(&self) -> [Vec3<S>; 7] {
+		let z = S::zero();
 
 		[
 			self.center,
-			self.center + Vector::new(self.half_len, z, z),
-			self.center + Vector::new(z, self.half_len, z),
-			self.center + Vector::new(z, z, self.half_len),
-			self.center + Vector::new(-self.half_len, z, z),
-			self.center + Vector::new(z, -self.half_len, z),
-			self.center + Vector::new(z, z, -self.half_len),
+			self.center + Vec3::new(self.half_len, z, z),
+			self.center + Vec3::new(z, self.half_len, z),
+			self.center + Vec3::new(z, z, self.half_len),
+			self.center + Vec3::new(-self.half_len, z, z),
+			self.center + Vec3::new(z, -self.half_len, z),
+			self.center + Vec3::new(z, z, -self.half_len),
 		]
 	}
 
 	/// Get a positive and negative pair of opposite points that are the
 	/// bounds of the BCube, based around a normal.
-	pub fn pn_pair_from_normal(&self, normal: Vector)
-		-> (Vector, Vector)
+	pub fn pn_pair_from_normal(&self, normal: Vec3<S>)
+		-> (Vec3<S>, Vec3<S>)
 	{
 		let mut pvertex = self.center;
 		let mut nvertex = self.center;
 
-		if normal.x >= 0.0 {
+		if normal.x >= S::zero() {
 			pvertex.x += self.half_len;
 			nvertex.x -= self.half_len;
 		} else {
@@ -117,7 +127,7 @@ impl BCube {
 			pvertex.x -= self.half_len;
 		}
 
-		if normal.y >= 0.0 {
+		if normal.y >= S::zero() {
 			pvertex.y += self.half_len;
 			nvertex.y -= self.half_len;
 		} else {
@@ -125,7 +135,7 @@ impl BCube {
 			pvertex.y -= self.half_len;
 		}
 
-		if normal.z >= 0.0 {
+		if normal.z >= S::zero() {
 			pvertex.z += self.half_len;
 			nvertex.z -= self.half_len;
 		} else {
@@ -0,0 +1,180 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::{ fmt, ops };
+use *;
+
+/// 2D bounding rectangle, single-precision unless `S` is `f64`.
+#[derive(Clone, Copy)]
+pub struct BRect<S = f32> {
+	pub(crate) min: Vec2<S>,
+	pub(crate) max: Vec2<S>,
+}
+
+impl<S: Scalar> fmt::Debug for BRect<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?} → {:?}", self.min, self.max)
+	}
+}
+
+impl<S: Scalar> ops::Sub<Vec2<S>> for BRect<S> {
+	type Output = BRect<S>;
+
+	fn sub(self, other: Vec2<S>) -> Self::Output {
+		BRect::new(self.min - other, self.max - other)
+	}
+}
+
+impl<S: Scalar> ops::Add<Vec2<S>> for BRect<S> {
+	type Output = BRect<S>;
+
+	fn add(self, other: Vec2<S>) -> Self::Output {
+		BRect::new(self.min + other, self.max + other)
+	}
+}
+
+impl<S: Scalar> From<BRect<S>> for BSquare<S> {
+	fn from(brect: BRect<S>) -> BSquare<S> {
+		let span = brect.max - brect.min;
+
+		BSquare {
+			center: brect.center(),
+			half_len: span.x.max(span.y) / S::from_f64(2.0),
+		}
+	}
+}
+
+impl From<BRect> for BRect<f64> {
+	fn from(brect: BRect) -> BRect<f64> {
+		brect.cast()
+	}
+}
+
+impl<S: Scalar> BRect<S> {
+	/// Create an new `BRect` from its corners.
+	pub fn new(min: Vec2<S>, max: Vec2<S>) -> BRect<S> {
+		assert!(min.x <= max.x);
+		assert!(min.y <= max.y);
+
+		BRect { min, max }
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> BRect<T> {
+		BRect { min: self.min.cast(), max: self.max.cast() }
+	}
+
+	/// Check if `BRect` collides with `other` `BRect`.
+	pub fn collide(&self, other: BRect<S>) -> bool {
+		   other.max.x >= self.min.x
+		&& self.max.x >= other.min.x
+		&& other.max.y >= self.min.y
+		&& self.max.y >= other.min.y
+	}
+
+	/// Check if `BRect` collides with `BSquare`.
+	pub fn collide_bsquare(&self, bsquare: BSquare<S>) -> bool {
+		self.collide(bsquare.to_brect())
+	}
+
+	/// Check if `BRect` is entirely within `BSquare`.
+	pub(crate) fn within_bsquare(&self, bsquare: BSquare<S>) -> bool {
+		let (max, min) = bsquare.to_point_pair();
+
+		   self.min.x >= min.x && self.max.x <= max.x
+		&& self.min.y >= min.y && self.max.y <= max.y
+	}
+
+	/// Get the smallest `BRect` containing both `self` and `other`.
+	pub(crate) fn union(&self, other: BRect<S>) -> BRect<S> {
+		BRect {
+			min: self.min.min(other.min),
+			max: self.max.max(other.max),
+		}
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// first hit at `origin + direction * t`, or `None` if it misses.  If
+	/// the origin is inside the `BRect`, `t` is `0.0`.
+	pub fn intersect_ray(&self, origin: Vec2<S>, direction: Vec2<S>)
+		-> Option<S>
+	{
+		let mut tmin = S::zero();
+		let mut tmax = S::infinity();
+		let o: [S; 2] = origin.into();
+		let d: [S; 2] = direction.into();
+		let min: [S; 2] = self.min.into();
+		let max: [S; 2] = self.max.into();
+
+		for i in 0..2 {
+			if d[i] == S::zero() {
+				// Parallel to slab, must be within it.
+				if o[i] < min[i] || o[i] > max[i] {
+					return None;
+				}
+			} else {
+				let a = (min[i] - o[i]) / d[i];
+				let b = (max[i] - o[i]) / d[i];
+
+				tmin = tmin.max(a.min(b));
+				tmax = tmax.min(a.max(b));
+
+				if tmin > tmax {
+					return None;
+				}
+			}
+		}
+
+		Some(tmin)
+	}
+
+	/// Get which sides are the farthest away from the brect (to extend).
+	pub(crate) fn bsquare_sides(&self, bsquare: BSquare<S>) -> (bool, bool) {
+		let square = bsquare.to_brect();
+
+		let lt_dist = self.max.x - square.max.x;
+		let rt_dist = square.min.x - self.min.x;
+		let up_dist = self.max.y - square.max.y;
+		let dn_dist = square.min.y - self.min.y;
+
+		(rt_dist <= lt_dist, dn_dist <= up_dist)
+	}
+
+	/// Check if `BRect` collides with point `p`.
+	pub fn collide_vec2(&self, p: Vec2<S>) -> bool {
+		(p.x >= self.min.x) &&
+		(p.x <= self.max.x) &&
+		(p.y >= self.min.y) &&
+		(p.y <= self.max.y)
+	}
+
+	/// Get all 4 points of the `BRect`.
+	pub fn all_points(&self) -> [Vec2<S>; 4] {
+		[
+			Vec2::new(self.min.x, self.min.y),
+			Vec2::new(self.min.x, self.max.y),
+			Vec2::new(self.max.x, self.min.y),
+			Vec2::new(self.max.x, self.max.y),
+		]
+	}
+
+	/// Get all 4 sides of the `BRect` as points.
+	pub fn side_points(&self) -> [Vec2<S>; 4] {
+		let center = self.center();
+
+		[
+			Vec2::new(self.min.x, center.y),
+			Vec2::new(center.x, self.min.y),
+			Vec2::new(self.max.x, center.y),
+			Vec2::new(center.x, self.max.y),
+		]
+	}
+
+	/// Get the center of the `BRect`.
+	pub fn center(&self) -> Vec2<S> {
+		(self.min + self.max) / S::from_f64(2.0)
+	}
+}
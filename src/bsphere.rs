@@ -4,124 +4,112 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-// TODO: this thing.
-
 use std::fmt;
+use *;
 
-use Vec3;
-
-/// Bounding sphere
-#[derive(Clone, Copy)]
+/// Single-precision bounding sphere
+#[derive(Clone, Copy, PartialEq)]
 pub struct BSphere {
-	pub(crate) center: Vec3,
-	pub(crate) half_d: Vec3,
+	pub(crate) center: Vector,
+	pub(crate) radius: f32,
 }
 
-impl fmt::Debug for BBox {
+impl fmt::Debug for BSphere {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "{:?}±{:?}", self.center, self.half_d)
+		write!(f, "{:?}±{:?}", self.center, self.radius)
 	}
 }
 
-impl BBox {
-	/// Create an new `BBox` at position `p`.
-	pub fn new(p: Vec3) -> BBox {
-		BBox { center: p, half_len: 1.0 }
+impl BSphere {
+	/// Create a new `BSphere` at position `center`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `radius` is negative.
+	pub fn new(center: Vector, radius: f32) -> BSphere {
+		assert!(radius >= 0.0);
+
+		BSphere { center, radius }
 	}
 
-	fn min_p(&self) -> f32 {
-		if self.half_len > 0.0 {
-			self.center.min_p() - self.half_len
-		} else {
-			self.center.min_p()
-		}
+	/// Get the center of the `BSphere`.
+	pub fn center(&self) -> Vector {
+		self.center
 	}
 
-	fn max_p(&self) -> f32 {
-		if self.half_len > 0.0 {
-			self.center.max_p() + self.half_len
-		} else {
-			self.center.max_p()
-		}
+	/// Get the radius of the `BSphere`.
+	pub fn radius(&self) -> f32 {
+		self.radius
 	}
 
-	fn move_center(&self, p: Vec3) -> Vec3 {
-		let min_p = self.min_p();
-		if p.min_p() < min_p {
-			return Vec3::new(min_p, min_p, min_p);
-		} else {
-			let max_p = self.max_p();
-			return Vec3::new(max_p, max_p, max_p);
-		}
+	/// Check if `BSphere` collides with point `p`.
+	pub fn collide_point(&self, p: Vector) -> bool {
+		(p - self.center).squared_length() <= self.radius * self.radius
 	}
 
-	/// Check if `BBox` contains point `p`.
-	pub fn contains(&self, p: Vec3) -> bool {
-		let Vec3 { x, y, z } = self.center;
-		let hl = self.half_len;
-		(p.x >= x - hl) &&
-		(p.x <  x + hl) &&
-		(p.y >= y - hl) &&
-		(p.y <  y + hl) &&
-		(p.z >= z - hl) &&
-		(p.z <  z + hl)
+	/// Check if `BSphere` collides with `other` `BSphere`.
+	pub fn collide_bsphere(&self, other: BSphere) -> bool {
+		let r = self.radius + other.radius;
+
+		(other.center - self.center).squared_length() <= r * r
 	}
 
-	/// Get two opposite points that are the bounds of the BBox.
-	pub fn to_point_pair(&self) -> (Vec3, Vec3) {
-		let half_box = Vec3::new(self.half_len, self.half_len,
-			self.half_len);
+	/// Check if `BSphere` collides with `BBox`.
+	pub fn collide_bbox(&self, bbox: BBox) -> bool {
+		let c = self.center;
+		let closest = Vector::new(
+			c.x.max(bbox.min.x).min(bbox.max.x),
+			c.y.max(bbox.min.y).min(bbox.max.y),
+			c.z.max(bbox.min.z).min(bbox.max.z),
+		);
 
-		(self.center + half_box, self.center - half_box)
+		self.collide_point(closest)
 	}
 
-	/// Get all 6 points or the `BBox`.
-	pub fn all_points(&self) -> [Vec3; 7] {
-		let z = 0.0;
-
-		[
-			self.center,
-			self.center + Vec3::new(self.half_len, z, z),
-			self.center + Vec3::new(z, self.half_len, z),
-			self.center + Vec3::new(z, z, self.half_len),
-			self.center + Vec3::new(-self.half_len, z, z),
-			self.center + Vec3::new(z, -self.half_len, z),
-			self.center + Vec3::new(z, z, -self.half_len),
-		]
+	/// Check if `BSphere` collides with `BCube`.
+	pub fn collide_bcube(&self, bcube: BCube) -> bool {
+		self.collide_bbox(bcube.to_bbox())
 	}
 
-	/// Get a positive and negative pair of opposite points that are the
-	/// bounds of the BBox, based around a normal.
-	pub fn pn_pair_from_normal(&self, normal: Vec3)
-		-> (Vec3, Vec3)
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// first hit at `origin + direction * t`, or `None` if it misses.  If
+	/// the origin is inside the `BSphere`, `t` is `0.0`.
+	pub fn intersect_ray(&self, origin: Vector, direction: Vector)
+		-> Option<f32>
 	{
-		let mut pvertex = self.center;
-		let mut nvertex = self.center;
-
-		if normal.x >= 0.0 {
-			pvertex.x += self.half_len;
-			nvertex.x -= self.half_len;
-		} else {
-			nvertex.x += self.half_len;
-			pvertex.x -= self.half_len;
-		}
+		let m = origin - self.center;
+		let b = m.dot(direction);
+		let c = m.squared_length() - self.radius * self.radius;
 
-		if normal.y >= 0.0 {
-			pvertex.y += self.half_len;
-			nvertex.y -= self.half_len;
-		} else {
-			nvertex.y += self.half_len;
-			pvertex.y -= self.half_len;
+		// Origin is inside.
+		if c <= 0.0 {
+			return Some(0.0);
 		}
+		// Origin is outside and pointing away.
+		if b > 0.0 {
+			return None;
+		}
+
+		let a = direction.squared_length();
+		let discr = b * b - a * c;
 
-		if normal.z >= 0.0 {
-			pvertex.z += self.half_len;
-			nvertex.z -= self.half_len;
-		} else {
-			nvertex.z += self.half_len;
-			pvertex.z -= self.half_len;
+		if a == 0.0 || discr < 0.0 {
+			return None;
 		}
 
-		(nvertex, pvertex)
+		Some((-b - discr.sqrt()) / a)
+	}
+
+	/// Get the `BBox` that contains this `BSphere`.
+	pub fn bbox(&self) -> BBox {
+		let r = vector!(self.radius);
+
+		BBox::new(self.center - r, self.center + r)
+	}
+}
+
+impl Collider for BSphere {
+	fn bbox(&self) -> BBox {
+		BSphere::bbox(self)
 	}
 }
@@ -0,0 +1,103 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::fmt;
+use *;
+
+/// 2D bounding square, single-precision unless `S` is `f64`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BSquare<S = f32> {
+	pub(crate) center: Vec2<S>,
+	pub(crate) half_len: S,
+}
+
+impl<S: Scalar> fmt::Debug for BSquare<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?}±{:?}", self.center, self.half_len)
+	}
+}
+
+impl From<BSquare> for BSquare<f64> {
+	fn from(bsquare: BSquare) -> BSquare<f64> {
+		bsquare.cast()
+	}
+}
+
+impl<S: Scalar> BSquare<S> {
+	/// Create an new empty `BSquare`.
+	pub fn empty() -> BSquare<S> {
+		BSquare { center: Vec2::zero(), half_len: -S::one() }
+	}
+
+	/// Create an new `BSquare` at position `p`.
+	pub fn new(p: Vec2<S>) -> BSquare<S> {
+		BSquare { center: p, half_len: S::one() }
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> BSquare<T> {
+		BSquare {
+			center: self.center.cast(),
+			half_len: self.half_len.cast(),
+		}
+	}
+
+	/// Extend the `BSquare` to accommodate for `BRect`
+	pub fn extend(&mut self, p: BRect<S>) {
+		self.center = self.move_center(p);
+		self.half_len *= S::from_f64(2.0);
+	}
+
+	fn move_center(&self, p: BRect<S>) -> Vec2<S> {
+		let (maxx, maxy) = p.bsquare_sides(*self);
+
+		let min = self.center - Vec2::from(self.half_len);
+		let max = self.center + Vec2::from(self.half_len);
+
+		match (maxx, maxy) {
+			(false, false) => Vec2::new(min.x, min.y),
+			(false, true) => Vec2::new(min.x, max.y),
+			(true, false) => Vec2::new(max.x, min.y),
+			(true, true) => Vec2::new(max.x, max.y),
+		}
+	}
+
+	/// Check if `BSquare` contains point `p`.
+	pub fn contains(&self, p: Vec2<S>) -> bool {
+		let Vec2 { x, y } = self.center;
+		let hl = self.half_len;
+		(p.x >= x - hl) &&
+		(p.x < x + hl) &&
+		(p.y >= y - hl) &&
+		(p.y < y + hl)
+	}
+
+	/// Get two opposite points that are the bounds of the BSquare.
+	pub fn to_point_pair(&self) -> (Vec2<S>, Vec2<S>) {
+		let half_square = Vec2::from(self.half_len);
+
+		(self.center + half_square, self.center - half_square)
+	}
+
+	/// Turn into a brect.
+	pub fn to_brect(&self) -> BRect<S> {
+		let (max, min) = self.to_point_pair();
+		BRect::new(min, max)
+	}
+
+	/// Get the center and the 4 side points of the `BSquare`.
+	pub fn all_points(&self) -> [Vec2<S>; 5] {
+		let z = S::zero();
+
+		[
+			self.center,
+			self.center + Vec2::new(self.half_len, z),
+			self.center + Vec2::new(z, self.half_len),
+			self.center + Vec2::new(-self.half_len, z),
+			self.center + Vec2::new(z, -self.half_len),
+		]
+	}
+}
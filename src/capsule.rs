@@ -0,0 +1,226 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use gjk;
+use *;
+
+/// Single-precision capsule (a line segment swept by a sphere)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule {
+	/// The center of the first hemisphere.
+	pub a: Vector,
+	/// The center of the second hemisphere.
+	pub b: Vector,
+	/// The radius of the capsule.
+	pub radius: f32,
+}
+
+impl Capsule {
+	/// Create a new capsule around the segment from `a` to `b`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `radius` is negative.
+	pub fn new(a: Vector, b: Vector, radius: f32) -> Capsule {
+		assert!(radius >= 0.0);
+
+		Capsule { a, b, radius }
+	}
+
+	/// Get the inner line segment of the capsule.
+	pub fn segment(&self) -> Segment {
+		Segment::new(self.a, self.b)
+	}
+
+	/// Get the point on the inner line segment closest to `p`.
+	pub fn closest_point_on_segment(&self, p: Vector) -> Vector {
+		self.segment().closest_point(p)
+	}
+
+	/// Check if `Capsule` collides with point `p`.
+	pub fn collide_point(&self, p: Vector) -> bool {
+		self.segment().squared_distance(p) <= self.radius * self.radius
+	}
+
+	/// Check if `Capsule` collides with `BSphere`.
+	pub fn collide_bsphere(&self, sphere: BSphere) -> bool {
+		let r = self.radius + sphere.radius;
+
+		self.segment().squared_distance(sphere.center) <= r * r
+	}
+
+	/// Check if `Capsule` collides with `other` `Capsule`.
+	pub fn collide_capsule(&self, other: Capsule) -> bool {
+		let r = self.radius + other.radius;
+
+		self.segment().squared_distance_segment(other.segment()) <= r * r
+	}
+
+	/// Check if `Capsule` collides with `Cylinder`.
+	pub fn collide_cylinder(&self, cylinder: Cylinder) -> bool {
+		gjk::collide(self, &cylinder)
+	}
+
+	/// Check if `Capsule` collides with `BBox`.
+	pub fn collide_bbox(&self, bbox: BBox) -> bool {
+		self.bbox().collide(bbox) && gjk::collide(self, &bbox)
+	}
+
+	/// Check if `Capsule` collides with `BCube`.
+	pub fn collide_bcube(&self, bcube: BCube) -> bool {
+		self.collide_bbox(bcube.to_bbox())
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// first hit at `origin + direction * t`, or `None` if it misses.  If
+	/// the origin is inside the `Capsule`, `t` is `0.0`.
+	pub fn intersect_ray(&self, origin: Vector, direction: Vector)
+		-> Option<f32>
+	{
+		if self.collide_point(origin) {
+			return Some(0.0);
+		}
+
+		// Hit the hemispheres.
+		let mut hit = min_hit(
+			BSphere::new(self.a, self.radius)
+				.intersect_ray(origin, direction),
+			BSphere::new(self.b, self.radius)
+				.intersect_ray(origin, direction),
+		);
+
+		// Hit the side of the cylinder between the hemispheres.
+		if let Some(t) = ray_tube(self.a, self.b, self.radius, origin,
+			direction)
+		{
+			hit = min_hit(hit, Some(t));
+		}
+
+		hit
+	}
+
+	/// Get the `BBox` that contains this `Capsule`.
+	pub fn bbox(&self) -> BBox {
+		let r = vector!(self.radius);
+		let min = Vector::new(
+			self.a.x.min(self.b.x),
+			self.a.y.min(self.b.y),
+			self.a.z.min(self.b.z),
+		);
+		let max = Vector::new(
+			self.a.x.max(self.b.x),
+			self.a.y.max(self.b.y),
+			self.a.z.max(self.b.z),
+		);
+
+		BBox::new(min - r, max + r)
+	}
+}
+
+impl Collider for Capsule {
+	fn bbox(&self) -> BBox {
+		Capsule::bbox(self)
+	}
+}
+
+/// Get the nearest of two optional ray hits.
+pub(crate) fn min_hit(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+	match (a, b) {
+		(Some(a), Some(b)) => Some(a.min(b)),
+		(a, None) => a,
+		(None, b) => b,
+	}
+}
+
+/// Cast a ray (from outside) at the side of the tube of `radius` around the
+/// segment `a` to `b`, ignoring the ends.
+pub(crate) fn ray_tube(a: Vector, b: Vector, radius: f32, origin: Vector,
+	direction: Vector) -> Option<f32>
+{
+	let ab = b - a;
+	let ab2 = ab.squared_length();
+
+	if ab2 <= f32::EPSILON {
+		return None;
+	}
+
+	// Remove the components along the axis.
+	let ao = origin - a;
+	let d = direction - ab * (direction.dot(ab) / ab2);
+	let m = ao - ab * (ao.dot(ab) / ab2);
+
+	let qa = d.squared_length();
+	let qb = m.dot(d);
+	let qc = m.squared_length() - radius * radius;
+	let discr = qb * qb - qa * qc;
+
+	if qa <= f32::EPSILON || discr < 0.0 {
+		return None;
+	}
+
+	let t = (-qb - discr.sqrt()) / qa;
+
+	if t < 0.0 {
+		return None;
+	}
+
+	// Must hit between the ends.
+	let s = (origin + direction * t - a).dot(ab) / ab2;
+
+	if (0.0..=1.0).contains(&s) {
+		Some(t)
+	} else {
+		None
+	}
+}
+
+#[test]
+fn test_capsule_collide() {
+	let c = Capsule::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 4.0, 0.0),
+		1.0);
+
+	assert!(c.collide_point(vector!(0.5, 2.0, 0.0)));
+	assert!(c.collide_point(vector!(0.0, 4.9, 0.0)));
+	assert!(!c.collide_point(vector!(0.8, 4.8, 0.0)));
+
+	assert!(c.collide_bsphere(BSphere::new(vector!(2.5, 2.0, 0.0), 1.5)));
+	assert!(!c.collide_bsphere(BSphere::new(vector!(2.5, 6.0, 0.0), 1.5)));
+
+	let d = Capsule::new(vector!(1.5, 2.0, -3.0), vector!(1.5, 2.0, 3.0),
+		0.6);
+	assert!(c.collide_capsule(d));
+	assert!(!c.collide_capsule(Capsule { radius: 0.4, ..d }));
+
+	let bbox = BBox::new(vector!(1.5, 1.5, 1.5), vector!(3.0, 3.0, 3.0));
+	assert!(!c.collide_bbox(bbox));
+	assert!(c.collide_bbox(bbox - vector!(0.6, 0.0, 1.2)));
+	assert!(c.collide_bcube(BCube::new(vector!(-1.5, 5.5, 0.0))));
+	assert!(!c.collide_bcube(BCube::new(vector!(-2.5, 6.5, 0.0))));
+
+	let cyl = Cylinder::new(vector!(1.8, 2.0, -2.0), vector!(1.8, 2.0, 2.0),
+		1.0);
+	assert!(c.collide_cylinder(cyl));
+	assert!(!c.collide_cylinder(Cylinder { radius: 0.5, ..cyl }));
+}
+
+#[test]
+fn test_capsule_ray() {
+	let c = Capsule::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 4.0, 0.0),
+		1.0);
+
+	// Hit the side.
+	let t = c.intersect_ray(vector!(-5.0, 2.0, 0.0), vector!(1.0, 0.0, 0.0));
+	assert!((t.unwrap() - 4.0).abs() < 0.0001);
+	// Hit the top hemisphere.
+	let t = c.intersect_ray(vector!(0.0, 10.0, 0.0), vector!(0.0, -2.0, 0.0));
+	assert!((t.unwrap() - 2.5).abs() < 0.0001);
+	// Miss.
+	assert!(c.intersect_ray(vector!(-5.0, 2.0, 0.0), vector!(0.0, 1.0, 0.0))
+		.is_none());
+	// Start inside.
+	assert_eq!(c.intersect_ray(vector!(0.0, 1.0, 0.0), vector!(1.0, 0.0,
+		0.0)), Some(0.0));
+}
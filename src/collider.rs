@@ -4,10 +4,16 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-use BBox;
+use {BBox, BRect, Scalar};
 
 /// `Collider` has bounding box and id.
-pub trait Collider {
+pub trait Collider<S: Scalar = f32> {
 	/// Get the `BBox` for this collider.
-	fn bbox(&self) -> BBox;
+	fn bbox(&self) -> BBox<S>;
+}
+
+/// `Collider2` has bounding rectangle and id.
+pub trait Collider2<S: Scalar = f32> {
+	/// Get the `BRect` for this collider.
+	fn brect(&self) -> BRect<S>;
 }
@@ -0,0 +1,215 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use capsule::{min_hit, ray_tube};
+use gjk;
+use *;
+
+/// Single-precision cylinder with flat ends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cylinder {
+	/// The center of the first end.
+	pub a: Vector,
+	/// The center of the second end.
+	pub b: Vector,
+	/// The radius of the cylinder.
+	pub radius: f32,
+}
+
+impl Cylinder {
+	/// Create a new cylinder around the segment from `a` to `b`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `radius` is negative.
+	pub fn new(a: Vector, b: Vector, radius: f32) -> Cylinder {
+		assert!(radius >= 0.0);
+
+		Cylinder { a, b, radius }
+	}
+
+	/// Get the axis line segment of the cylinder.
+	pub fn segment(&self) -> Segment {
+		Segment::new(self.a, self.b)
+	}
+
+	/// Get the point on the axis line segment closest to `p`.
+	pub fn closest_point_on_segment(&self, p: Vector) -> Vector {
+		self.segment().closest_point(p)
+	}
+
+	/// Get the point in (or on) the cylinder closest to `p`.
+	pub fn closest_point(&self, p: Vector) -> Vector {
+		let ab = self.b - self.a;
+		let ab2 = ab.squared_length();
+		let ap = p - self.a;
+
+		// Degenerate cylinder is a disc with no facing; treat as point.
+		if ab2 <= f32::EPSILON {
+			return self.a;
+		}
+
+		// Clamp along the axis and across the radius independently.
+		let along = ap.dot(ab) / ab2;
+		let mut radial = ap - ab * along;
+		let len2 = radial.squared_length();
+
+		if len2 > self.radius * self.radius {
+			radial *= self.radius / len2.sqrt();
+		}
+
+		self.a + ab * along.clamp(0.0, 1.0) + radial
+	}
+
+	/// Check if `Cylinder` collides with point `p`.
+	pub fn collide_point(&self, p: Vector) -> bool {
+		let ab = self.b - self.a;
+		let ab2 = ab.squared_length();
+		let ap = p - self.a;
+		let along = ap.dot(ab);
+
+		if along < 0.0 || along > ab2 {
+			return false;
+		}
+
+		// Distance from axis squared.
+		ap.squared_length() - along * along / ab2
+			<= self.radius * self.radius
+	}
+
+	/// Check if `Cylinder` collides with `BSphere`.
+	pub fn collide_bsphere(&self, sphere: BSphere) -> bool {
+		sphere.collide_point(self.closest_point(sphere.center))
+	}
+
+	/// Check if `Cylinder` collides with `Capsule`.
+	pub fn collide_capsule(&self, capsule: Capsule) -> bool {
+		capsule.collide_cylinder(*self)
+	}
+
+	/// Check if `Cylinder` collides with `other` `Cylinder`.
+	pub fn collide_cylinder(&self, other: Cylinder) -> bool {
+		self.bbox().collide(other.bbox()) && gjk::collide(self, &other)
+	}
+
+	/// Check if `Cylinder` collides with `BBox`.
+	pub fn collide_bbox(&self, bbox: BBox) -> bool {
+		self.bbox().collide(bbox) && gjk::collide(self, &bbox)
+	}
+
+	/// Check if `Cylinder` collides with `BCube`.
+	pub fn collide_bcube(&self, bcube: BCube) -> bool {
+		self.collide_bbox(bcube.to_bbox())
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// first hit at `origin + direction * t`, or `None` if it misses.  If
+	/// the origin is inside the `Cylinder`, `t` is `0.0`.
+	pub fn intersect_ray(&self, origin: Vector, direction: Vector)
+		-> Option<f32>
+	{
+		if self.collide_point(origin) {
+			return Some(0.0);
+		}
+
+		let ab = self.b - self.a;
+		let mut hit = ray_tube(self.a, self.b, self.radius, origin,
+			direction);
+
+		// Hit the flat ends.
+		let facing = direction.dot(ab);
+		if facing != 0.0 {
+			for end in [self.a, self.b].iter() {
+				let t = (*end - origin).dot(ab) / facing;
+				let p = origin + direction * t;
+
+				if t >= 0.0 && (p - *end).squared_length()
+					<= self.radius * self.radius
+				{
+					hit = min_hit(hit, Some(t));
+				}
+			}
+		}
+
+		hit
+	}
+
+	/// Get the `BBox` that contains this `Cylinder`.
+	pub fn bbox(&self) -> BBox {
+		let axis = self.b - self.a;
+		let len2 = axis.squared_length();
+
+		// How far the end discs reach along each axis.
+		let reach = if len2 <= f32::EPSILON {
+			vector!(self.radius)
+		} else {
+			let e = |d: f32| self.radius * (1.0 - d * d / len2).max(0.0)
+				.sqrt();
+			Vector::new(e(axis.x), e(axis.y), e(axis.z))
+		};
+		let min = Vector::new(
+			self.a.x.min(self.b.x),
+			self.a.y.min(self.b.y),
+			self.a.z.min(self.b.z),
+		);
+		let max = Vector::new(
+			self.a.x.max(self.b.x),
+			self.a.y.max(self.b.y),
+			self.a.z.max(self.b.z),
+		);
+
+		BBox::new(min - reach, max + reach)
+	}
+}
+
+impl Collider for Cylinder {
+	fn bbox(&self) -> BBox {
+		Cylinder::bbox(self)
+	}
+}
+
+#[test]
+fn test_cylinder_collide() {
+	let c = Cylinder::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 4.0, 0.0),
+		1.0);
+
+	assert!(c.collide_point(vector!(0.9, 3.9, 0.0)));
+	assert!(!c.collide_point(vector!(0.0, 4.1, 0.0)));
+	assert!(!c.collide_point(vector!(0.8, 2.0, 0.8)));
+
+	// Corner of the end is square, unlike a capsule.
+	assert!(c.collide_bsphere(BSphere::new(vector!(1.5, 4.5, 0.0), 0.75)));
+	assert!(!c.collide_bsphere(BSphere::new(vector!(1.5, 4.5, 0.0), 0.7)));
+
+	let bbox = BBox::new(vector!(0.9, 3.9, -0.1), vector!(2.0, 5.0, 0.1));
+	assert!(c.collide_bbox(bbox));
+	assert!(!c.collide_bbox(bbox + vector!(0.8, 0.0, 0.8)));
+
+	let d = Cylinder::new(vector!(2.5, 2.0, -2.0), vector!(2.5, 2.0, 2.0),
+		1.0);
+	assert!(!c.collide_cylinder(d));
+	assert!(c.collide_cylinder(Cylinder { radius: 1.5, ..d }));
+
+	let bbox = c.bbox();
+	assert_eq!(bbox.min, vector!(-1.0, 0.0, -1.0));
+	assert_eq!(bbox.max, vector!(1.0, 4.0, 1.0));
+}
+
+#[test]
+fn test_cylinder_ray() {
+	let c = Cylinder::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 4.0, 0.0),
+		1.0);
+
+	// Hit the side.
+	let t = c.intersect_ray(vector!(-5.0, 2.0, 0.0), vector!(1.0, 0.0, 0.0));
+	assert!((t.unwrap() - 4.0).abs() < 0.0001);
+	// Hit the flat top.
+	let t = c.intersect_ray(vector!(0.5, 10.0, 0.0), vector!(0.0, -2.0, 0.0));
+	assert!((t.unwrap() - 3.0).abs() < 0.0001);
+	// Miss past the top edge.
+	assert!(c.intersect_ray(vector!(-5.0, 4.5, 0.0), vector!(1.0, 0.0, 0.0))
+		.is_none());
+}
@@ -0,0 +1,197 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// A read-only copy of an `Octree`, made with `Octree::freeze()`, laid out to
+/// answer queries faster.
+///
+/// The nodes are stored breadth-first, and each level is in Morton order of
+/// the cell centers, so the children of each node are next to each other.
+/// Each node has its enlarged bounds, and its colliders stored together as
+/// their `Id` and the `BBox` they had when they were added.
+#[derive(Clone, Debug)]
+pub struct FrozenOctree<S: Scalar = f32> {
+	pub(crate) nodes: Vec<FrozenNode<S>>,
+	pub(crate) colliders: Vec<(BBox<S>, Id)>,
+}
+
+/// A node of a `FrozenOctree`.
+#[derive(Clone, Debug)]
+pub(crate) struct FrozenNode<S: Scalar> {
+	// Bounds containing all colliders in it and its descendants.
+	pub(crate) bounds: BBox<S>,
+	// Children are at `first_child..first_child + n_children`.
+	pub(crate) first_child: u32,
+	pub(crate) n_children: u32,
+	// Colliders are at `first_collider..first_collider + n_colliders`.
+	pub(crate) first_collider: u32,
+	pub(crate) n_colliders: u32,
+}
+
+impl<S: Scalar> FrozenOctree<S> {
+	/// Get the number of colliders.
+	pub fn len(&self) -> usize {
+		self.colliders.len()
+	}
+
+	/// Check if there are no colliders.
+	pub fn is_empty(&self) -> bool {
+		self.colliders.is_empty()
+	}
+
+	/// Get the `Id`s of all colliders whose `BBox` collides with `bbox`.
+	pub fn query_bbox(&self, bbox: BBox<S>) -> Vec<Id> {
+		self.query(
+			|bounds| bbox.collide(bounds),
+			|other| bbox.collide(other),
+		)
+	}
+
+	/// Get the `Id`s of all colliders whose `BBox` is hit by a ray from
+	/// `origin` along `direction`.
+	pub fn query_ray(&self, origin: Vec3<S>, direction: Vec3<S>)
+		-> Vec<Id>
+	{
+		self.query(
+			|bounds| bounds.intersect_ray(origin, direction)
+				.is_some(),
+			|other| other.intersect_ray(origin, direction)
+				.is_some(),
+		)
+	}
+
+	/// Get each pair of colliders whose `BBox`es collide, with the lesser
+	/// `Id` first.
+	pub fn collision_pairs(&self) -> Vec<(Id, Id)> {
+		self.colliders.iter()
+			.flat_map(|&(bbox, id)| {
+				self.query_bbox(bbox).into_iter()
+					.filter(move |other| id.0 < other.0)
+					.map(move |other| (id, other))
+			})
+			.collect()
+	}
+
+	/// Walk the nodes whose bounds pass `node_test`, collecting the
+	/// colliders whose `BBox` passes `bbox_test`.
+	fn query<F, G>(&self, node_test: F, bbox_test: G) -> Vec<Id>
+		where F: Fn(BBox<S>) -> bool, G: Fn(BBox<S>) -> bool
+	{
+		let mut found = vec![];
+
+		if self.nodes.is_empty() {
+			return found;
+		}
+
+		let mut stack = vec![0];
+
+		while let Some(i) = stack.pop() {
+			let node = &self.nodes[i as usize];
+
+			if !node_test(node.bounds) {
+				continue;
+			}
+
+			let first = node.first_collider as usize;
+			let colliders = &self.colliders[first..first
+				+ node.n_colliders as usize];
+
+			for &(bbox, id) in colliders {
+				if bbox_test(bbox) {
+					found.push(id);
+				}
+			}
+
+			stack.extend(node.first_child
+				..node.first_child + node.n_children);
+		}
+
+		found
+	}
+}
+
+#[test]
+fn test_frozen_octree() {
+	use octree::add_test_boxes;
+
+	let sorted = |mut ids: Vec<Id>| {
+		ids.sort_by_key(|id| id.0);
+		ids
+	};
+
+	for &looseness in &[1.0, 2.0] {
+		let mut octree = Octree::loose(looseness);
+		let boxes = add_test_boxes(&mut octree);
+		for (id, _) in boxes.iter().step_by(3) {
+			octree.remove(*id);
+		}
+		let frozen = octree.freeze();
+		assert_eq!(frozen.len(), octree.len());
+
+		for i in 0..20 {
+			let c = vector!(i as f32 * 5.0 - 50.0);
+			let q = BBox::new(c, c + vector!(10.0, 20.0, 15.0));
+			assert_eq!(sorted(frozen.query_bbox(q)),
+				sorted(octree.query_bbox(q)));
+
+			let origin = vector!(-100.0, c.y, c.z + 0.5);
+			let direction = vector!(1.0, 0.1, 0.0);
+			assert_eq!(sorted(frozen.query_ray(origin, direction)),
+				sorted(octree.query_ray(origin, direction)));
+		}
+
+		let mut pairs = frozen.collision_pairs();
+		let mut expect = octree.collision_pairs();
+		pairs.sort_by_key(|(a, b)| (a.0, b.0));
+		expect.sort_by_key(|(a, b)| (a.0, b.0));
+		assert_eq!(pairs, expect);
+	}
+
+	let empty: Octree<::octree::TestCollider> = Octree::new();
+	assert!(empty.freeze().query_bbox(BBox::new(vector!(0.0),
+		vector!(1.0))).is_empty());
+}
+
+#[test]
+fn test_frozen_octree_morton() {
+	let mut octree = Octree::new();
+	::octree::add_test_boxes(&mut octree);
+	let frozen = octree.freeze();
+	let root = frozen.nodes[0].bounds;
+	let size = root.max.x - root.min.x;
+
+	// Interleave the bits of the cell position on each axis, x highest.
+	let morton = |bounds: BBox, depth: u32| {
+		let cells = (1u64 << depth) as f32;
+		let p = (bounds.center() - root.min) * (cells / size);
+		let (x, y, z) = (p.x as u64, p.y as u64, p.z as u64);
+		(0..depth).rev().fold(0u64, |code, bit| {
+			code << 3 | (x >> bit & 1) << 2 | (y >> bit & 1) << 1
+				| (z >> bit & 1)
+		})
+	};
+
+	// Each level follows the last, in increasing Morton order.
+	let mut level = vec![0];
+	let mut depth = 0;
+	let mut last = 0;
+	while !level.is_empty() {
+		assert_eq!(level[0], last);
+		let codes: Vec<u64> = level.iter()
+			.map(|i| morton(frozen.nodes[*i as usize].bounds, depth))
+			.collect();
+		assert!(codes.windows(2).all(|w| w[0] < w[1]));
+		last = level[level.len() - 1] + 1;
+		level = level.iter().flat_map(|i| {
+			let node = &frozen.nodes[*i as usize];
+			node.first_child..node.first_child + node.n_children
+		}).collect();
+		depth += 1;
+	}
+	assert_eq!(last as usize, frozen.nodes.len());
+	assert!(depth > 3);
+}
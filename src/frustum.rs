@@ -66,7 +66,7 @@ impl Frustum {
 		Frustum { center, radius, xrot, yrot, wfov, hfov }
 	}
 
-	/// 
+	/// If viewing frustum collides with the bounding box.
 	pub fn collide_bbox(&self, bbox: BBox) -> bool {
 		for i in bbox.all_points().iter() {
 			if (*i - self.center).length() <= self.radius {
@@ -122,4 +122,28 @@ impl Frustum {
 			&& self.top.isdistpos_point(point)
 			&& self.bottom.isdistpos_point(point)*/
 	}
+
+	/// Get the 6 planes bounding the frustum, facing inward: near, far,
+	/// left, right, top and bottom.  Unrotated, the frustum looks down +Z
+	/// from `center` out to `radius`; `xrot` turns it around the Y axis and
+	/// `yrot` around the X axis.
+	pub fn planes(&self) -> [Plane; 6] {
+		let rotation = Rotation::euler(vector!(self.yrot, self.xrot, 0.0));
+		let (ws, wc) = (self.wfov / 2.0).sin_cos();
+		let (hs, hc) = (self.hfov / 2.0).sin_cos();
+		let forward = rotation.rotate(vector!(0.0, 0.0, 1.0));
+		let plane = |facing: Vector| {
+			Plane::from_point_normal(self.center, rotation.rotate(facing))
+		};
+
+		[
+			Plane::from_point_normal(self.center, forward),
+			Plane::from_point_normal(self.center + forward * self.radius,
+				-forward),
+			plane(vector!(wc, 0.0, ws)),
+			plane(vector!(-wc, 0.0, ws)),
+			plane(vector!(0.0, -hc, hs)),
+			plane(vector!(0.0, hc, hs)),
+		]
+	}
 }
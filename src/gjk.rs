@@ -0,0 +1,235 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Gilbert–Johnson–Keerthi overlap test for convex shapes.
+
+use *;
+
+/// Maximum number of refinement steps before giving up (touching shapes).
+const MAX_ITERATIONS: usize = 64;
+
+/// A convex shape that can report its farthest point in a direction.
+pub(crate) trait Support {
+	/// Get the point of the shape farthest along direction `d`.
+	fn support(&self, d: Vector) -> Vector;
+}
+
+impl Support for BBox {
+	fn support(&self, d: Vector) -> Vector {
+		Vector::new(
+			if d.x >= 0.0 { self.max.x } else { self.min.x },
+			if d.y >= 0.0 { self.max.y } else { self.min.y },
+			if d.z >= 0.0 { self.max.z } else { self.min.z },
+		)
+	}
+}
+
+impl Support for BCube {
+	fn support(&self, d: Vector) -> Vector {
+		self.to_bbox().support(d)
+	}
+}
+
+impl Support for BSphere {
+	fn support(&self, d: Vector) -> Vector {
+		self.center + unit_or_zero(d) * self.radius
+	}
+}
+
+impl Support for Capsule {
+	fn support(&self, d: Vector) -> Vector {
+		let end = if d.dot(self.b - self.a) >= 0.0 {
+			self.b
+		} else {
+			self.a
+		};
+
+		end + unit_or_zero(d) * self.radius
+	}
+}
+
+impl Support for Cylinder {
+	fn support(&self, d: Vector) -> Vector {
+		let axis = unit_or_zero(self.b - self.a);
+		let along = d.dot(axis);
+		let end = if along >= 0.0 { self.b } else { self.a };
+
+		end + unit_or_zero(d - axis * along) * self.radius
+	}
+}
+
+/// Normalize `v`, or return zero if it has no length.
+fn unit_or_zero(v: Vector) -> Vector {
+	let len = v.length();
+
+	if len <= f32::EPSILON {
+		Vector::zero()
+	} else {
+		v / len
+	}
+}
+
+/// Check if two convex shapes overlap (touching counts as overlapping).
+///
+/// If it hasn't converged after `MAX_ITERATIONS` (which can happen when the
+/// shapes are just touching), the shapes are reported as overlapping.  This
+/// is deliberately conservative, so a real contact is never missed.
+pub(crate) fn collide<A: Support, B: Support>(a: &A, b: &B) -> bool {
+	// Support of the Minkowski difference `a - b`.
+	let support = |d: Vector| a.support(d) - b.support(-d);
+
+	let mut simplex = [Vector::zero(); 4];
+	simplex[0] = support(Vector::new(1.0, 0.0, 0.0));
+	let mut len = 1;
+	let mut d = -simplex[0];
+
+	for _ in 0..MAX_ITERATIONS {
+		// Origin lies on the current simplex.
+		if d.squared_length() <= f32::EPSILON * f32::EPSILON {
+			return true;
+		}
+
+		let p = support(d);
+
+		// Couldn't pass the origin: separated.
+		if p.dot(d) < 0.0 {
+			return false;
+		}
+
+		simplex[len] = p;
+		len += 1;
+
+		if let Some(new_d) = do_simplex(&mut simplex, &mut len) {
+			d = new_d;
+		} else {
+			return true;
+		}
+	}
+
+	// Didn't converge, so assume they touch.
+	true
+}
+
+/// Reduce the simplex to the feature closest to the origin.  Returns the
+/// next search direction, or `None` if the simplex contains the origin.
+fn do_simplex(s: &mut [Vector; 4], len: &mut usize) -> Option<Vector> {
+	match *len {
+		2 => Some(line(s, len)),
+		3 => triangle(s, len),
+		4 => tetrahedron(s, len),
+		_ => unreachable!(),
+	}
+}
+
+/// Line case: `s[1]` is the newest point.
+fn line(s: &mut [Vector; 4], len: &mut usize) -> Vector {
+	let a = s[1];
+	let b = s[0];
+	let ab = b - a;
+	let ao = -a;
+
+	if ab.dot(ao) > 0.0 {
+		ab.cross(ao).cross(ab)
+	} else {
+		s[0] = a;
+		*len = 1;
+		ao
+	}
+}
+
+/// Triangle case: `s[2]` is the newest point.
+fn triangle(s: &mut [Vector; 4], len: &mut usize) -> Option<Vector> {
+	let a = s[2];
+	let b = s[1];
+	let c = s[0];
+	let ab = b - a;
+	let ac = c - a;
+	let ao = -a;
+	let abc = ab.cross(ac);
+
+	// Degenerate (collinear) triangle: drop the oldest point.
+	if abc.squared_length()
+		<= f32::EPSILON * ab.squared_length() * ac.squared_length()
+	{
+		s[0] = b;
+		s[1] = a;
+		*len = 2;
+		return Some(line(s, len));
+	}
+
+	if abc.cross(ac).dot(ao) > 0.0 {
+		if ac.dot(ao) > 0.0 {
+			s[0] = c;
+			s[1] = a;
+			*len = 2;
+			return Some(ac.cross(ao).cross(ac));
+		}
+		s[0] = b;
+		s[1] = a;
+		*len = 2;
+		return Some(line(s, len));
+	}
+
+	if ab.cross(abc).dot(ao) > 0.0 {
+		s[0] = b;
+		s[1] = a;
+		*len = 2;
+		return Some(line(s, len));
+	}
+
+	let side = abc.dot(ao);
+
+	if side > 0.0 {
+		Some(abc)
+	} else if side < 0.0 {
+		s[0] = b;
+		s[1] = c;
+		Some(-abc)
+	} else {
+		// Origin lies on the triangle.
+		None
+	}
+}
+
+/// Tetrahedron case: `s[3]` is the newest point.
+fn tetrahedron(s: &mut [Vector; 4], len: &mut usize) -> Option<Vector> {
+	let a = s[3];
+	let ao = -a;
+
+	// Degenerate (flat) tetrahedron: drop the oldest point.
+	let volume = (s[2] - a).dot((s[1] - a).cross(s[0] - a));
+	if volume.abs() <= f32::EPSILON * (s[0] - a).length()
+		* (s[1] - a).length() * (s[2] - a).length()
+	{
+		s[0] = s[1];
+		s[1] = s[2];
+		s[2] = a;
+		*len = 3;
+		return triangle(s, len);
+	}
+
+	// Each face touching `a`, and the vertex opposite of it.
+	let faces = [(s[2], s[1], s[0]), (s[1], s[0], s[2]), (s[0], s[2], s[1])];
+
+	for &(b, c, opposite) in faces.iter() {
+		let mut normal = (b - a).cross(c - a);
+
+		// Point the face normal away from the opposite vertex.
+		if normal.dot(opposite - a) > 0.0 {
+			normal = -normal;
+		}
+
+		if normal.dot(ao) > 0.0 {
+			s[0] = c;
+			s[1] = b;
+			s[2] = a;
+			*len = 3;
+			return triangle(s, len);
+		}
+	}
+
+	None
+}
@@ -0,0 +1,454 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Conversions to and from other math libraries, each behind the feature of
+//! the same name.  Matrices are by column in all of them.
+
+#[allow(unused_imports)]
+use *;
+
+#[cfg(feature = "cgmath")]
+mod cgmath_interop {
+	use cgmath;
+	use *;
+
+	impl<S> From<Vec2<S>> for cgmath::Vector2<S> {
+		fn from(v: Vec2<S>) -> Self {
+			cgmath::Vector2 { x: v.x, y: v.y }
+		}
+	}
+
+	impl<S> From<cgmath::Vector2<S>> for Vec2<S> {
+		fn from(v: cgmath::Vector2<S>) -> Self {
+			Vec2 { x: v.x, y: v.y }
+		}
+	}
+
+	impl<S> From<Vec3<S>> for cgmath::Vector3<S> {
+		fn from(v: Vec3<S>) -> Self {
+			cgmath::Vector3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<cgmath::Vector3<S>> for Vec3<S> {
+		fn from(v: cgmath::Vector3<S>) -> Self {
+			Vec3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<Vec3<S>> for cgmath::Point3<S> {
+		fn from(v: Vec3<S>) -> Self {
+			cgmath::Point3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<cgmath::Point3<S>> for Vec3<S> {
+		fn from(p: cgmath::Point3<S>) -> Self {
+			Vec3 { x: p.x, y: p.y, z: p.z }
+		}
+	}
+
+	impl<S> From<Vec4<S>> for cgmath::Vector4<S> {
+		fn from(v: Vec4<S>) -> Self {
+			cgmath::Vector4 { x: v.x, y: v.y, z: v.z, w: v.w }
+		}
+	}
+
+	impl<S> From<cgmath::Vector4<S>> for Vec4<S> {
+		fn from(v: cgmath::Vector4<S>) -> Self {
+			Vec4 { x: v.x, y: v.y, z: v.z, w: v.w }
+		}
+	}
+
+	impl<S> From<Quat<S>> for cgmath::Quaternion<S> {
+		fn from(q: Quat<S>) -> Self {
+			cgmath::Quaternion {
+				v: cgmath::Vector3 { x: q.x, y: q.y, z: q.z },
+				s: q.s,
+			}
+		}
+	}
+
+	impl<S> From<cgmath::Quaternion<S>> for Quat<S> {
+		fn from(q: cgmath::Quaternion<S>) -> Self {
+			Quat { x: q.v.x, y: q.v.y, z: q.v.z, s: q.s }
+		}
+	}
+
+	impl<S: Copy> From<Mat4<S>> for cgmath::Matrix4<S> {
+		fn from(m: Mat4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+
+	impl<S: Copy> From<cgmath::Matrix4<S>> for Mat4<S> {
+		fn from(m: cgmath::Matrix4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+}
+
+#[cfg(feature = "mint")]
+mod mint_interop {
+	use mint;
+	use *;
+
+	impl<S> From<Vec2<S>> for mint::Vector2<S> {
+		fn from(v: Vec2<S>) -> Self {
+			mint::Vector2 { x: v.x, y: v.y }
+		}
+	}
+
+	impl<S> From<mint::Vector2<S>> for Vec2<S> {
+		fn from(v: mint::Vector2<S>) -> Self {
+			Vec2 { x: v.x, y: v.y }
+		}
+	}
+
+	impl<S> From<Vec3<S>> for mint::Vector3<S> {
+		fn from(v: Vec3<S>) -> Self {
+			mint::Vector3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<mint::Vector3<S>> for Vec3<S> {
+		fn from(v: mint::Vector3<S>) -> Self {
+			Vec3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<Vec3<S>> for mint::Point3<S> {
+		fn from(v: Vec3<S>) -> Self {
+			mint::Point3 { x: v.x, y: v.y, z: v.z }
+		}
+	}
+
+	impl<S> From<mint::Point3<S>> for Vec3<S> {
+		fn from(p: mint::Point3<S>) -> Self {
+			Vec3 { x: p.x, y: p.y, z: p.z }
+		}
+	}
+
+	impl<S> From<Vec4<S>> for mint::Vector4<S> {
+		fn from(v: Vec4<S>) -> Self {
+			mint::Vector4 { x: v.x, y: v.y, z: v.z, w: v.w }
+		}
+	}
+
+	impl<S> From<mint::Vector4<S>> for Vec4<S> {
+		fn from(v: mint::Vector4<S>) -> Self {
+			Vec4 { x: v.x, y: v.y, z: v.z, w: v.w }
+		}
+	}
+
+	impl<S> From<Quat<S>> for mint::Quaternion<S> {
+		fn from(q: Quat<S>) -> Self {
+			mint::Quaternion {
+				v: mint::Vector3 { x: q.x, y: q.y, z: q.z },
+				s: q.s,
+			}
+		}
+	}
+
+	impl<S> From<mint::Quaternion<S>> for Quat<S> {
+		fn from(q: mint::Quaternion<S>) -> Self {
+			Quat { x: q.v.x, y: q.v.y, z: q.v.z, s: q.s }
+		}
+	}
+
+	impl<S: Copy> From<Mat4<S>> for mint::ColumnMatrix4<S> {
+		fn from(m: Mat4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+
+	impl<S: Copy> From<mint::ColumnMatrix4<S>> for Mat4<S> {
+		fn from(m: mint::ColumnMatrix4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+}
+
+#[cfg(feature = "glam")]
+mod glam_interop {
+	use glam;
+	use *;
+
+	// glam isn't generic, so implement for each precision.
+	macro_rules! glam {
+		($s:ty, $vec2:ident, $vec3:ident, $vec4:ident, $quat:ident,
+			$mat4:ident) =>
+		{
+			impl From<Vec2<$s>> for glam::$vec2 {
+				fn from(v: Vec2<$s>) -> Self {
+					glam::$vec2::new(v.x, v.y)
+				}
+			}
+
+			impl From<glam::$vec2> for Vec2<$s> {
+				fn from(v: glam::$vec2) -> Self {
+					Vec2::new(v.x, v.y)
+				}
+			}
+
+			impl From<Vec3<$s>> for glam::$vec3 {
+				fn from(v: Vec3<$s>) -> Self {
+					glam::$vec3::new(v.x, v.y, v.z)
+				}
+			}
+
+			impl From<glam::$vec3> for Vec3<$s> {
+				fn from(v: glam::$vec3) -> Self {
+					Vec3::new(v.x, v.y, v.z)
+				}
+			}
+
+			impl From<Vec4<$s>> for glam::$vec4 {
+				fn from(v: Vec4<$s>) -> Self {
+					glam::$vec4::new(v.x, v.y, v.z, v.w)
+				}
+			}
+
+			impl From<glam::$vec4> for Vec4<$s> {
+				fn from(v: glam::$vec4) -> Self {
+					v.to_array().into()
+				}
+			}
+
+			impl From<Quat<$s>> for glam::$quat {
+				fn from(q: Quat<$s>) -> Self {
+					glam::$quat::from_xyzw(q.x, q.y, q.z,
+						q.s)
+				}
+			}
+
+			impl From<glam::$quat> for Quat<$s> {
+				fn from(q: glam::$quat) -> Self {
+					let [x, y, z, s] = q.to_array();
+
+					Quat { x, y, z, s }
+				}
+			}
+
+			impl From<Mat4<$s>> for glam::$mat4 {
+				fn from(m: Mat4<$s>) -> Self {
+					glam::$mat4::from_cols_array_2d(
+						m.as_ref())
+				}
+			}
+
+			impl From<glam::$mat4> for Mat4<$s> {
+				fn from(m: glam::$mat4) -> Self {
+					m.to_cols_array_2d().into()
+				}
+			}
+		}
+	}
+
+	glam!(f32, Vec2, Vec3, Vec4, Quat, Mat4);
+	glam!(f64, DVec2, DVec3, DVec4, DQuat, DMat4);
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop {
+	use nalgebra;
+	use *;
+
+	impl<S> From<Vec2<S>> for nalgebra::Vector2<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: Vec2<S>) -> Self {
+			nalgebra::Vector2::new(v.x, v.y)
+		}
+	}
+
+	impl<S> From<nalgebra::Vector2<S>> for Vec2<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: nalgebra::Vector2<S>) -> Self {
+			Vec2::new(v.x, v.y)
+		}
+	}
+
+	impl<S> From<Vec3<S>> for nalgebra::Vector3<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: Vec3<S>) -> Self {
+			nalgebra::Vector3::new(v.x, v.y, v.z)
+		}
+	}
+
+	impl<S> From<nalgebra::Vector3<S>> for Vec3<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: nalgebra::Vector3<S>) -> Self {
+			Vec3::new(v.x, v.y, v.z)
+		}
+	}
+
+	impl<S> From<Vec3<S>> for nalgebra::Point3<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: Vec3<S>) -> Self {
+			nalgebra::Point3::new(v.x, v.y, v.z)
+		}
+	}
+
+	impl<S> From<nalgebra::Point3<S>> for Vec3<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(p: nalgebra::Point3<S>) -> Self {
+			Vec3::new(p.x, p.y, p.z)
+		}
+	}
+
+	impl<S> From<Vec4<S>> for nalgebra::Vector4<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: Vec4<S>) -> Self {
+			nalgebra::Vector4::new(v.x, v.y, v.z, v.w)
+		}
+	}
+
+	impl<S> From<nalgebra::Vector4<S>> for Vec4<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(v: nalgebra::Vector4<S>) -> Self {
+			Vec4::new(v.x, v.y, v.z, v.w)
+		}
+	}
+
+	impl<S> From<Quat<S>> for nalgebra::Quaternion<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(q: Quat<S>) -> Self {
+			nalgebra::Quaternion::new(q.s, q.x, q.y, q.z)
+		}
+	}
+
+	impl<S> From<nalgebra::Quaternion<S>> for Quat<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(q: nalgebra::Quaternion<S>) -> Self {
+			// Stored as i, j, k, w.
+			let c = q.coords;
+
+			Quat { x: c.x, y: c.y, z: c.z, s: c.w }
+		}
+	}
+
+	// Doesn't normalize, so `Quat` must already be a unit quaternion.
+	impl<S> From<Quat<S>> for nalgebra::UnitQuaternion<S>
+		where S: Scalar + nalgebra::RealField
+	{
+		fn from(q: Quat<S>) -> Self {
+			nalgebra::UnitQuaternion::new_unchecked(q.into())
+		}
+	}
+
+	impl<S> From<nalgebra::UnitQuaternion<S>> for Quat<S>
+		where S: Scalar + nalgebra::RealField
+	{
+		fn from(q: nalgebra::UnitQuaternion<S>) -> Self {
+			q.into_inner().into()
+		}
+	}
+
+	impl<S> From<Mat4<S>> for nalgebra::Matrix4<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(m: Mat4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+
+	impl<S> From<nalgebra::Matrix4<S>> for Mat4<S>
+		where S: Scalar + nalgebra::Scalar
+	{
+		fn from(m: nalgebra::Matrix4<S>) -> Self {
+			let m: [[S; 4]; 4] = m.into();
+
+			m.into()
+		}
+	}
+}
+
+/// Check that converting to and from another library keeps the vectors,
+/// rotation and matrix the same, and that they transform the same way.
+#[cfg(test)]
+#[allow(dead_code)]
+fn check_interop<V, Q, M>(transform: fn(&M, V) -> V, rotate: fn(&Q, V) -> V)
+	where V: From<Vector> + Into<Vector>,
+		Q: From<Rotation> + Into<Rotation>,
+		M: From<Matrix> + Into<Matrix>
+{
+	let p = vector!(1.0, -2.0, 3.0);
+	let r = Rotation::new(vector!(1.0, 2.0, 0.5), 0.9);
+	let m = Matrix::default().srt(vector!(2.0, 1.0, 0.5), r,
+		vector!(4.0, 5.0, 6.0));
+
+	assert_eq!(V::from(p).into(), p);
+	assert_eq!(Q::from(r).into(), r);
+	assert_eq!(M::from(m).into(), m);
+	let moved: Vector = transform(&M::from(m), V::from(p)).into();
+	assert!(moved.approx_eq(m.transform_point(p), 0.0001));
+	let turned: Vector = rotate(&Q::from(r), V::from(p)).into();
+	assert!(turned.approx_eq(r.rotate(p), 0.0001));
+}
+
+#[cfg(feature = "cgmath")]
+#[test]
+fn test_interop_cgmath() {
+	use cgmath::{Rotation, Transform};
+
+	check_interop::<cgmath::Point3<f32>, cgmath::Quaternion<f32>,
+		cgmath::Matrix4<f32>>(|m, p| m.transform_point(p),
+		|q, p| q.rotate_point(p));
+	let v: cgmath::Vector4<f64> = DVector4::new(1.0, 2.0, 3.0, 4.0).into();
+	assert_eq!(DVector4::from(v), DVector4::new(1.0, 2.0, 3.0, 4.0));
+}
+
+#[cfg(feature = "mint")]
+#[test]
+fn test_interop_mint() {
+	let p: mint::Vector3<f32> = vector!(1.0, 2.0, 3.0).into();
+	assert_eq!(Vector::from(p), vector!(1.0, 2.0, 3.0));
+	let q: mint::Quaternion<f64> = DRotation::identity().into();
+	assert_eq!(q.s, 1.0);
+	let m: mint::ColumnMatrix4<f32> = Matrix::default()
+		.t(vector!(4.0, 5.0, 6.0)).into();
+	assert_eq!(m.w.x, 4.0);
+	assert_eq!(Matrix::from(m).translation(), vector!(4.0, 5.0, 6.0));
+}
+
+#[cfg(feature = "glam")]
+#[test]
+fn test_interop_glam() {
+	check_interop::<glam::Vec3, glam::Quat, glam::Mat4>(
+		|m, p| m.transform_point3(p), |q, p| *q * p);
+	let v: glam::DVec2 = DVector2::new(1.0, 2.0).into();
+	assert_eq!(DVector2::from(v), DVector2::new(1.0, 2.0));
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn test_interop_nalgebra() {
+	check_interop::<nalgebra::Point3<f32>, nalgebra::UnitQuaternion<f32>,
+		nalgebra::Matrix4<f32>>(|m, p| m.transform_point(&p),
+		|q, p| q * p);
+	let v: nalgebra::Vector2<f64> = DVector2::new(1.0, 2.0).into();
+	assert_eq!(DVector2::from(v), DVector2::new(1.0, 2.0));
+}
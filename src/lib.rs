@@ -11,7 +11,12 @@
 //! ## Features
 //! **ami**'s current features:
 //! * Provide geometrical data structs, and do math with them
-//! * Automatic-size-adjusting octree
+//! * Automatic-size-adjusting octree, and quadtree for 2D
+//! * Batch `BBox` collision, frustum culling and point transforms with SIMD
+//! * Scene graph of transforms, with world-space bounds in an octree
+//! * Conversions to and from `cgmath`, `glam`, `mint` and `nalgebra` types,
+//!   with the features of the same names
+//! * Parallel octree queries with the `rayon` feature
 //!
 //! ## Getting started
 //! ```
@@ -26,26 +31,80 @@
 	html_root_url = "http://plopgrizzly.com/ami/"
 )]
 
+#[cfg(feature = "cgmath")]
 extern crate cgmath;
+#[cfg(feature = "glam")]
+extern crate glam;
+#[cfg(feature = "mint")]
+extern crate mint;
+#[cfg(feature = "nalgebra")]
+extern crate nalgebra;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
 #[macro_use]
 mod macros;
+mod arena;
+mod batch;
 mod bbox;
+mod brect;
 mod bcube;
+mod bsphere;
+mod bsquare;
+mod capsule;
+mod cylinder;
+mod frozen;
 mod frustum;
+mod gjk;
+mod interop;
+mod node;
 mod octree;
 mod plane;
+mod polygon;
+mod quadtree;
 mod collider;
+mod scene;
+mod segment;
+mod snapshot;
+mod transform;
+mod tree;
+mod triangle;
+mod trimesh;
 mod vector;
+mod vector2;
+mod vector4;
 mod matrix;
+mod matrix3;
 mod rotation;
+mod scalar;
 
+pub use batch::{BBoxBatch, batch_collide_bbox, batch_frustum_cull,
+	batch_transform_points};
 pub use bcube::BCube;
 pub use bbox::BBox;
+pub use brect::BRect;
+pub use bsphere::BSphere;
+pub use bsquare::BSquare;
+pub use capsule::Capsule;
+pub use cylinder::Cylinder;
+pub use frozen::FrozenOctree;
 pub use frustum::Frustum;
-pub use octree::{Octree, Id};
-pub use plane::Plane;
-pub use collider::Collider;
-pub use vector::Vector;
-pub use matrix::Matrix;
-pub use rotation::Rotation;
+pub use node::Id;
+pub use octree::{ColliderMut, Octree, OctreeError};
+pub use plane::{Plane, Side};
+pub use polygon::Polygon;
+pub use quadtree::Quadtree;
+pub use collider::{Collider, Collider2};
+pub use scene::Scene;
+pub use segment::Segment;
+pub use snapshot::OctreeSnapshot;
+pub use transform::Transform;
+pub use triangle::Triangle;
+pub use trimesh::TriMesh;
+pub use vector::{Vec3, Vector, DVector};
+pub use vector2::{Vec2, Vector2, DVector2};
+pub use vector4::{Vec4, Vector4, DVector4};
+pub use matrix::{Mat4, Matrix, DMatrix};
+pub use matrix3::{Mat3, Matrix3, DMatrix3};
+pub use rotation::{Quat, Rotation, DRotation};
+pub use scalar::Scalar;
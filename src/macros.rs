@@ -31,19 +31,24 @@
 /// 
 /// Array:
 /// ```
-/// matrix!([
-/// 	[1.0, 0.0, 0.0, 0.0],
-/// 	[0.0, 1.0, 0.0, 0.0],
-/// 	[0.0, 0.0, 1.0, 0.0],
-/// 	[0.0, 0.0, 0.0, 1.0],
-/// ])
+/// # #[macro_use] extern crate ami;
+/// # fn main() {
+/// let a = matrix!([
+///     [1.0, 0.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0, 0.0],
+///     [0.0, 0.0, 1.0, 0.0],
+///     [0.0, 0.0, 0.0, 1.0],
+/// ]);
 ///
-/// matrix!(
-/// 	1.0, 0.0, 0.0, 0.0,
-/// 	0.0, 1.0, 0.0, 0.0,
-/// 	0.0, 0.0, 1.0, 0.0,
-/// 	0.0, 0.0, 0.0, 1.0,
-/// )
+/// let b = matrix!(
+///     1.0, 0.0, 0.0, 0.0,
+///     0.0, 1.0, 0.0, 0.0,
+///     0.0, 0.0, 1.0, 0.0,
+///     0.0, 0.0, 0.0, 1.0,
+/// );
+/// # assert_eq!(a, b);
+/// # }
+/// ```
 #[macro_export] macro_rules! matrix {
 	() => {
 		$crate::Matrix {
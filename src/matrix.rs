@@ -4,22 +4,27 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-use cgmath;
-use std::{fmt, mem, ops};
+use std::{fmt, ops};
 use *;
 
-/// 4x4 Matrix.
+/// 4x4 Matrix, generic over the `Scalar` type.
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[allow(missing_docs)]
 #[repr(C)]
-pub struct Matrix {
-	pub m00: f32, pub m01: f32, pub m02: f32, pub m03: f32,
-	pub m10: f32, pub m11: f32, pub m12: f32, pub m13: f32,
-	pub m20: f32, pub m21: f32, pub m22: f32, pub m23: f32,
-	pub m30: f32, pub m31: f32, pub m32: f32, pub m33: f32,
+pub struct Mat4<S> {
+	pub m00: S, pub m01: S, pub m02: S, pub m03: S,
+	pub m10: S, pub m11: S, pub m12: S, pub m13: S,
+	pub m20: S, pub m21: S, pub m22: S, pub m23: S,
+	pub m30: S, pub m31: S, pub m32: S, pub m33: S,
 }
 
-impl fmt::Display for Matrix {
+/// Single-precision 4x4 Matrix.
+pub type Matrix = Mat4<f32>;
+
+/// Double-precision 4x4 Matrix.
+pub type DMatrix = Mat4<f64>;
+
+impl<S: Scalar> fmt::Display for Mat4<S> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(
 			f,
@@ -32,10 +37,10 @@ impl fmt::Display for Matrix {
 	}
 }
 
-impl Matrix {
+impl<S: Scalar> Mat4<S> {
 	/// Scale, then rotate Quaternion (axis, angle), then translate.
 	#[inline(always)]
-	pub fn srt(self, scale: Vector, rotate: Rotation, translate: Vector)
+	pub fn srt(self, scale: Vec3<S>, rotate: Quat<S>, translate: Vec3<S>)
 		-> Self
 	{
 		self.s(scale).r(rotate).t(translate)
@@ -43,41 +48,46 @@ impl Matrix {
 
 	/// Rotate Quaternion (axis, angle), then translate.
 	#[inline(always)]
-	pub fn rt(self, rotate: Rotation, translate: Vector) -> Self {
+	pub fn rt(self, rotate: Quat<S>, translate: Vec3<S>) -> Self {
 		self.r(rotate).t(translate)
 	}
 
 	/// Scale, then translate.
 	#[inline(always)]
-	pub fn st(self, scale: Vector, translate: Vector) -> Self {
+	pub fn st(self, scale: Vec3<S>, translate: Vec3<S>) -> Self {
 		self.s(scale).t(translate)
 	}
 
 	/// Translate.
 	#[inline(always)]
-	pub fn t(self, translate: Vector) -> Self {
-		self.m(Matrix::new(
-			1.0, 0.0, 0.0, 0.0,
-			0.0, 1.0, 0.0, 0.0,
-			0.0, 0.0, 1.0, 0.0,
-			translate.x, translate.y, translate.z, 1.0,
+	pub fn t(self, translate: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+
+		self.m(Mat4::new(
+			o, z, z, z,
+			z, o, z, z,
+			z, z, o, z,
+			translate.x, translate.y, translate.z, o,
 		))
 	}
 
 	/// Scale.
 	#[inline(always)]
-	pub fn s(self, scale: Vector) -> Self {
-		self.m(Matrix::new(
-			scale.x, 0.0, 0.0, 0.0,
-			0.0, scale.y, 0.0, 0.0,
-			0.0, 0.0, scale.z, 0.0,
-			0.0, 0.0, 0.0, 1.0,
+	pub fn s(self, scale: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+
+		self.m(Mat4::new(
+			scale.x, z, z, z,
+			z, scale.y, z, z,
+			z, z, scale.z, z,
+			z, z, z, o,
 		))
 	}
 
 	/// Rotate Quaternion (axis, angle).
 	#[inline(always)]
-	pub fn r(self, rotation: Rotation) -> Self {
+	pub fn r(self, rotation: Quat<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
 		let x2 = rotation.x + rotation.x;
 		let y2 = rotation.y + rotation.y;
 		let z2 = rotation.z + rotation.z;
@@ -94,11 +104,11 @@ impl Matrix {
 		let sz2 = z2 * rotation.s;
 		let sx2 = x2 * rotation.s;
 
-		self.m(Matrix::new(
-			1.0 - yy2 - zz2, xy2 + sz2, xz2 - sy2, 0.0,
-			xy2 - sz2, 1.0 - xx2 - zz2, yz2 + sx2, 0.0,
-			xz2 + sy2, yz2 - sx2, 1.0 - xx2 - yy2, 0.0,
-			0.0, 0.0, 0.0, 1.0,
+		self.m(Mat4::new(
+			o - yy2 - zz2, xy2 + sz2, xz2 - sy2, z,
+			xy2 - sz2, o - xx2 - zz2, yz2 + sx2, z,
+			xz2 + sy2, yz2 - sx2, o - xx2 - yy2, z,
+			z, z, z, o,
 		))
 	}
 
@@ -109,13 +119,14 @@ impl Matrix {
 	}
 
 	/// Full constructor.
-	pub fn new(
-		m00: f32, m01: f32, m02: f32, m03: f32,
-		m10: f32, m11: f32, m12: f32, m13: f32,
-		m20: f32, m21: f32, m22: f32, m23: f32,
-		m30: f32, m31: f32, m32: f32, m33: f32,
+	#[allow(clippy::too_many_arguments)]
+	pub const fn new(
+		m00: S, m01: S, m02: S, m03: S,
+		m10: S, m11: S, m12: S, m13: S,
+		m20: S, m21: S, m22: S, m23: S,
+		m30: S, m31: S, m32: S, m33: S,
 	) -> Self {
-		Matrix {
+		Mat4 {
 			m00, m01, m02, m03,
 			m10, m11, m12, m13,
 			m20, m21, m22, m23,
@@ -124,60 +135,281 @@ impl Matrix {
 	}
 
 	/// Diagonal constructor.
-	pub fn diagonal(di: f32) -> Self {
-		Matrix::tridiagonal(0.0, di, 0.0)
+	pub fn diagonal(di: S) -> Self {
+		Mat4::tridiagonal(S::zero(), di, S::zero())
 	}
 
 	/// Tri-diagonal constructor.
-	pub fn tridiagonal(lo: f32, di: f32, up: f32) -> Self {
-		Matrix::new(
-			di, up, 0., 0.,
-			lo, di, up, 0.,
-			0., lo, di, up,
-			0., 0., lo, di,
+	pub fn tridiagonal(lo: S, di: S, up: S) -> Self {
+		let z = S::zero();
+
+		Mat4::new(
+			di, up, z, z,
+			lo, di, up, z,
+			z, lo, di, up,
+			z, z, lo, di,
+		)
+	}
+
+	/// Translation matrix constructor.
+	pub fn from_translation(translate: Vec3<S>) -> Self {
+		Mat4::default().t(translate)
+	}
+
+	/// Scale matrix constructor.
+	pub fn from_scale(scale: Vec3<S>) -> Self {
+		Mat4::default().s(scale)
+	}
+
+	/// Rotation matrix constructor.
+	pub fn from_rotation(rotate: Quat<S>) -> Self {
+		Mat4::default().r(rotate)
+	}
+
+	/// View matrix constructor for a camera at `eye` looking at `target`
+	/// (down -Z in view space, with `up` as close to +Y as possible).
+	pub fn look_at(eye: Vec3<S>, target: Vec3<S>, up: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+		let f = (target - eye).normalize();
+		let s = f.cross(up).normalize();
+		let u = s.cross(f);
+
+		Mat4::new(
+			s.x, u.x, -f.x, z,
+			s.y, u.y, -f.y, z,
+			s.z, u.z, -f.z, z,
+			-s.dot(eye), -u.dot(eye), f.dot(eye), o,
 		)
 	}
 
 	/// Orthographic projection matrix constructor.
 	pub fn orthographic_projection(
-		left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32
+		left: S, right: S, bottom: S, top: S, near: S, far: S
 	) -> Self {
-		let ortho = cgmath::ortho(left, right, bottom, top, near, far);
-
-		Matrix::new(
-			ortho.x.x, ortho.x.y, ortho.x.z, ortho.x.w,
-			ortho.y.x, ortho.y.y, ortho.y.z, ortho.y.w,
-			ortho.z.x, ortho.z.y, ortho.z.z, ortho.z.w,
-			ortho.w.x, ortho.w.y, ortho.w.z, ortho.w.w,
+		let (o, z) = (S::one(), S::zero());
+		let two = S::from_f64(2.0);
+		let (width, height, depth) = (right - left, top - bottom,
+			far - near);
+
+		Mat4::new(
+			two / width, z, z, z,
+			z, two / height, z, z,
+			z, z, -two / depth, z,
+			-(right + left) / width, -(top + bottom) / height,
+				-(far + near) / depth, o,
 		)
 	}
 
 	/// Finite perspective projection matrix constructor.
 	pub fn finite_perspective_projection(
-		fovy: f32, aspect: f32, near: f32, far: f32
+		fovy: S, aspect: S, near: S, far: S
+	) -> Self {
+		let z = S::zero();
+		let two = S::from_f64(2.0);
+		let f = (fovy / two).tan().recip();
+		let depth = near - far;
+
+		Mat4::new(
+			f / aspect, z, z, z,
+			z, f, z, z,
+			z, z, (far + near) / depth, -S::one(),
+			z, z, two * far * near / depth, z,
+		)
+	}
+
+	/// Infinite perspective projection matrix constructor (no far plane).
+	pub fn infinite_perspective_projection(fovy: S, aspect: S, near: S)
+		-> Self
+	{
+		let z = S::zero();
+		let two = S::from_f64(2.0);
+		let f = (fovy / two).tan().recip();
+
+		Mat4::new(
+			f / aspect, z, z, z,
+			z, f, z, z,
+			z, z, -S::one(), -S::one(),
+			z, z, -two * near, z,
+		)
+	}
+
+	/// Reversed-Z perspective projection matrix constructor.  Depth goes
+	/// from 1 at `near` to 0 at `far`, which spreads floating point
+	/// precision evenly over the distance (use with a 0 to 1 depth range).
+	pub fn reversed_perspective_projection(
+		fovy: S, aspect: S, near: S, far: S
 	) -> Self {
-		let pp = cgmath::perspective(cgmath::Rad(fovy), aspect, near, far);
+		let z = S::zero();
+		let f = (fovy / S::from_f64(2.0)).tan().recip();
+		let range = far - near;
+
+		Mat4::new(
+			f / aspect, z, z, z,
+			z, f, z, z,
+			z, z, near / range, -S::one(),
+			z, z, near * far / range, z,
+		)
+	}
+
+	/// Reversed-Z perspective projection matrix constructor with no far
+	/// plane.  Depth goes from 1 at `near` towards 0 at infinity.
+	pub fn infinite_reversed_perspective_projection(
+		fovy: S, aspect: S, near: S
+	) -> Self {
+		let z = S::zero();
+		let f = (fovy / S::from_f64(2.0)).tan().recip();
+
+		Mat4::new(
+			f / aspect, z, z, z,
+			z, f, z, z,
+			z, z, z, -S::one(),
+			z, z, near, z,
+		)
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Mat4<T> {
+		let m: [[S; 4]; 4] = self.into();
+		let c = |v: [S; 4]| [v[0].cast(), v[1].cast(), v[2].cast(),
+			v[3].cast()];
 
-		Matrix::new(
-			pp.x.x, pp.x.y, pp.x.z, pp.x.w,
-			pp.y.x, pp.y.y, pp.y.z, pp.y.w,
-			pp.z.x, pp.z.y, pp.z.z, pp.z.w,
-			pp.w.x, pp.w.y, pp.w.z, pp.w.w,
+		[c(m[0]), c(m[1]), c(m[2]), c(m[3])].into()
+	}
+
+	/// Get column `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is not 0, 1, 2 or 3.
+	pub fn column(&self, i: usize) -> Vec4<S> {
+		match i {
+			0 => Vec4::new(self.m00, self.m01, self.m02, self.m03),
+			1 => Vec4::new(self.m10, self.m11, self.m12, self.m13),
+			2 => Vec4::new(self.m20, self.m21, self.m22, self.m23),
+			3 => Vec4::new(self.m30, self.m31, self.m32, self.m33),
+			_ => panic!("Mat4 column {} out of range", i),
+		}
+	}
+
+	/// Get row `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is not 0, 1, 2 or 3.
+	pub fn row(&self, i: usize) -> Vec4<S> {
+		match i {
+			0 => Vec4::new(self.m00, self.m10, self.m20, self.m30),
+			1 => Vec4::new(self.m01, self.m11, self.m21, self.m31),
+			2 => Vec4::new(self.m02, self.m12, self.m22, self.m32),
+			3 => Vec4::new(self.m03, self.m13, self.m23, self.m33),
+			_ => panic!("Mat4 row {} out of range", i),
+		}
+	}
+
+	/// Get the translation.
+	pub fn translation(&self) -> Vec3<S> {
+		Vec3::new(self.m30, self.m31, self.m32)
+	}
+
+	/// Drop the last row and column (translation and projection).
+	pub fn truncate(self) -> Mat3<S> {
+		Mat3::new(
+			self.m00, self.m01, self.m02,
+			self.m10, self.m11, self.m12,
+			self.m20, self.m21, self.m22,
 		)
 	}
 
+	/// Split into scale, rotation and translation, so that
+	/// `Matrix::default().srt(s, r, t)` rebuilds the matrix.  A mirror
+	/// shows up as a negative X scale.  `None` if the matrix has a
+	/// projection, shear or zero scale.
+	pub fn decompose(self) -> Option<(Vec3<S>, Quat<S>, Vec3<S>)> {
+		let (o, z) = (S::one(), S::zero());
+		let epsilon = S::from_f64(0.000_1);
+
+		if self.row(3) != Vec4::new(z, z, z, o) {
+			return None;
+		}
+
+		let m = self.truncate();
+		let mut scale = Vec3::new(m.column(0).length(),
+			m.column(1).length(), m.column(2).length());
+
+		if m.determinant() < z {
+			scale.x = -scale.x;
+		}
+
+		if scale.x.abs() <= epsilon || scale.y.abs() <= epsilon
+			|| scale.z.abs() <= epsilon
+		{
+			return None;
+		}
+
+		let x = m.column(0) / scale.x;
+		let y = m.column(1) / scale.y;
+		let w = m.column(2) / scale.z;
+
+		if x.dot(y).abs() > epsilon || y.dot(w).abs() > epsilon
+			|| w.dot(x).abs() > epsilon
+		{
+			return None;
+		}
+
+		let rotation = Quat::from_matrix(Mat3::new(
+			x.x, x.y, x.z,
+			y.x, y.y, y.z,
+			w.x, w.y, w.z,
+		).extend());
+
+		Some((scale, rotation, self.translation()))
+	}
+
+	/// Computes the inverse of an affine matrix (one without projection,
+	/// such as from `srt()`), which is faster than `inverse()`.
+	///
+	/// ## Panics
+	///
+	/// Panics if the matrix has no inverse (i.e. has zero determinant).
+	pub fn inverse_affine(self) -> Self {
+		let m = self.truncate().inverse();
+		let t = -(m * self.translation());
+
+		Mat4::new(
+			m.m00, m.m01, m.m02, S::zero(),
+			m.m10, m.m11, m.m12, S::zero(),
+			m.m20, m.m21, m.m22, S::zero(),
+			t.x, t.y, t.z, S::one(),
+		)
+	}
+
+	/// Transform point `p` (`w` is 1), including translation.  For a
+	/// projection matrix, use `project_point()` instead.
+	pub fn transform_point(&self, p: Vec3<S>) -> Vec3<S> {
+		(self * Vec4::point(p)).truncate()
+	}
+
+	/// Transform direction `v` (`w` is 0), ignoring translation.
+	pub fn transform_vector(&self, v: Vec3<S>) -> Vec3<S> {
+		(self * Vec4::direction(v)).truncate()
+	}
+
+	/// Transform point `p` (`w` is 1), and then do the perspective divide.
+	pub fn project_point(&self, p: Vec3<S>) -> Vec3<S> {
+		(self * Vec4::point(p)).to_cartesian()
+	}
+
 	/// Computes the matrix determinant.
-	pub fn determinant(self) -> f32 {
-		use cgmath::SquareMatrix;
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		a.determinant()
+	pub fn determinant(self) -> S {
+		let (s, c) = self.minors();
+
+		s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2]
+			- s[4] * c[1] + s[5] * c[0]
 	}
 
 	/// Computes the matrix trace.
-	pub fn trace(self) -> f32 {
-		use cgmath::SquareMatrix;
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		a.trace()
+	pub fn trace(self) -> S {
+		self.m00 + self.m11 + self.m22 + self.m33
 	}
 
 	/// Computes the matrix inverse.
@@ -185,82 +417,146 @@ impl Matrix {
 	/// ## Panics
 	///
 	/// Panics if the matrix has no inverse (i.e. has zero determinant).
-	pub fn inverse(self) -> Matrix {
+	pub fn inverse(self) -> Self {
 		self.try_invert().unwrap()
 	}
 
 	/// Returns the matrix transpose.
-	pub fn transpose(self) -> Matrix {
-		use cgmath::SquareMatrix;
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let mut b = *a;
-		b.transpose_self();
-		let m: [[f32; 4]; 4] = b.into();
-		m.into()
+	pub fn transpose(self) -> Self {
+		Mat4::from_columns(self.row(0), self.row(1), self.row(2),
+			self.row(3))
 	}
 
 	/// Attempts to compute the matrix inverse, returning `None` if the matrix is
 	/// non-invertible (i.e. has zero determinant).
-	pub fn try_invert(self) -> Option<Matrix> {
-		use cgmath::SquareMatrix;
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		a.invert().map(|inv| {
-			let b: [[f32; 4]; 4] = inv.into();
-			b.into()
-		})
+	pub fn try_invert(self) -> Option<Self> {
+		let det = self.determinant();
+
+		if det == S::zero() {
+			return None;
+		}
+
+		// Transposed cofactors (the adjugate), over the determinant.
+		let (s, c) = self.minors();
+		let a = self;
+
+		Some(Mat4::new(
+			a.m11 * c[5] - a.m12 * c[4] + a.m13 * c[3],
+			-a.m01 * c[5] + a.m02 * c[4] - a.m03 * c[3],
+			a.m31 * s[5] - a.m32 * s[4] + a.m33 * s[3],
+			-a.m21 * s[5] + a.m22 * s[4] - a.m23 * s[3],
+
+			-a.m10 * c[5] + a.m12 * c[2] - a.m13 * c[1],
+			a.m00 * c[5] - a.m02 * c[2] + a.m03 * c[1],
+			-a.m30 * s[5] + a.m32 * s[2] - a.m33 * s[1],
+			a.m20 * s[5] - a.m22 * s[2] + a.m23 * s[1],
+
+			a.m10 * c[4] - a.m11 * c[2] + a.m13 * c[0],
+			-a.m00 * c[4] + a.m01 * c[2] - a.m03 * c[0],
+			a.m30 * s[4] - a.m31 * s[2] + a.m33 * s[0],
+			-a.m20 * s[4] + a.m21 * s[2] - a.m23 * s[0],
+
+			-a.m10 * c[3] + a.m11 * c[1] - a.m12 * c[0],
+			a.m00 * c[3] - a.m01 * c[1] + a.m02 * c[0],
+			-a.m30 * s[3] + a.m31 * s[1] - a.m32 * s[0],
+			a.m20 * s[3] - a.m21 * s[1] + a.m22 * s[0],
+		) * det.recip())
+	}
+
+	/// The 2x2 determinants of the first two and last two columns, used
+	/// for the determinant and inverse.
+	fn minors(&self) -> ([S; 6], [S; 6]) {
+		let a = self;
+
+		([
+			a.m00 * a.m11 - a.m10 * a.m01,
+			a.m00 * a.m12 - a.m10 * a.m02,
+			a.m00 * a.m13 - a.m10 * a.m03,
+			a.m01 * a.m12 - a.m11 * a.m02,
+			a.m01 * a.m13 - a.m11 * a.m03,
+			a.m02 * a.m13 - a.m12 * a.m03,
+		], [
+			a.m20 * a.m31 - a.m30 * a.m21,
+			a.m20 * a.m32 - a.m30 * a.m22,
+			a.m20 * a.m33 - a.m30 * a.m23,
+			a.m21 * a.m32 - a.m31 * a.m22,
+			a.m21 * a.m33 - a.m31 * a.m23,
+			a.m22 * a.m33 - a.m32 * a.m23,
+		])
+	}
+
+	fn from_columns(a: Vec4<S>, b: Vec4<S>, c: Vec4<S>, d: Vec4<S>) -> Self {
+		Mat4::new(
+			a.x, a.y, a.z, a.w,
+			b.x, b.y, b.z, b.w,
+			c.x, c.y, c.z, c.w,
+			d.x, d.y, d.z, d.w,
+		)
 	}
 }
 
-impl ops::Add<Matrix> for Matrix {
-	type Output = Matrix;
-	fn add(self, rhs: Matrix) -> Self::Output {
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let b: &cgmath::Matrix4<f32> = rhs.as_ref().into();
-		let m: [[f32; 4]; 4] = (a + b).into();
-		m.into()
+impl<S: Scalar> ops::Add<Mat4<S>> for Mat4<S> {
+	type Output = Mat4<S>;
+	fn add(self, rhs: Mat4<S>) -> Self::Output {
+		Mat4::from_columns(self.column(0) + rhs.column(0),
+			self.column(1) + rhs.column(1),
+			self.column(2) + rhs.column(2),
+			self.column(3) + rhs.column(3))
 	}
 }
 
-impl ops::Sub<Matrix> for Matrix {
-	type Output = Matrix;
-	fn sub(self, rhs: Matrix) -> Self::Output {
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let b: &cgmath::Matrix4<f32> = rhs.as_ref().into();
-		let m: [[f32; 4]; 4] = (a - b).into();
-		m.into()
+impl<S: Scalar> ops::Sub<Mat4<S>> for Mat4<S> {
+	type Output = Mat4<S>;
+	fn sub(self, rhs: Mat4<S>) -> Self::Output {
+		Mat4::from_columns(self.column(0) - rhs.column(0),
+			self.column(1) - rhs.column(1),
+			self.column(2) - rhs.column(2),
+			self.column(3) - rhs.column(3))
 	}
 }
 
-impl ops::Mul<f32> for Matrix {
-	type Output = Matrix;
-	fn mul(self, rhs: f32) -> Self::Output {
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let v: [[f32; 4]; 4] = (a * rhs).into();
-		v.into()
+impl<S: Scalar> ops::Mul<S> for Mat4<S> {
+	type Output = Mat4<S>;
+	fn mul(self, rhs: S) -> Self::Output {
+		Mat4::from_columns(self.column(0) * rhs, self.column(1) * rhs,
+			self.column(2) * rhs, self.column(3) * rhs)
 	}
 }
 
-impl ops::Mul<(Vector, f32)> for Matrix {
-	type Output = Vector;
-	fn mul(self, rhs: (Vector, f32)) -> Self::Output {
-		let p = [rhs.0.x, rhs.0.y, rhs.0.z, rhs.1];
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let b: &cgmath::Vector4<f32> = (&p).into();
-		let v: [f32; 4] = (a * b).into();
+impl<S: Scalar> ops::Mul<Vec4<S>> for Mat4<S> {
+	type Output = Vec4<S>;
+	fn mul(self, rhs: Vec4<S>) -> Self::Output {
+		Vec4::new(
+			self.m00 * rhs.x + self.m10 * rhs.y + self.m20 * rhs.z
+				+ self.m30 * rhs.w,
+			self.m01 * rhs.x + self.m11 * rhs.y + self.m21 * rhs.z
+				+ self.m31 * rhs.w,
+			self.m02 * rhs.x + self.m12 * rhs.y + self.m22 * rhs.z
+				+ self.m32 * rhs.w,
+			self.m03 * rhs.x + self.m13 * rhs.y + self.m23 * rhs.z
+				+ self.m33 * rhs.w,
+		)
+	}
+}
 
-		Vector::new(v[0], v[1], v[2])
+impl<S: Scalar> ops::Mul<Vec4<S>> for &Mat4<S> {
+	type Output = Vec4<S>;
+	fn mul(self, rhs: Vec4<S>) -> Self::Output {
+		*self * rhs
 	}
 }
 
-impl<'a> ops::Mul<(Vector, f32)> for &'a Matrix {
-	type Output = Vector;
-	fn mul(self, rhs: (Vector, f32)) -> Self::Output {
-		let p = [rhs.0.x, rhs.0.y, rhs.0.z, rhs.1];
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let b: &cgmath::Vector4<f32> = (&p).into();
-		let v: [f32; 4] = (a * b).into();
+impl<S: Scalar> ops::Mul<(Vec3<S>, S)> for Mat4<S> {
+	type Output = Vec3<S>;
+	fn mul(self, rhs: (Vec3<S>, S)) -> Self::Output {
+		(self * rhs.0.extend(rhs.1)).truncate()
+	}
+}
 
-		Vector::new(v[0], v[1], v[2])
+impl<S: Scalar> ops::Mul<(Vec3<S>, S)> for &Mat4<S> {
+	type Output = Vec3<S>;
+	fn mul(self, rhs: (Vec3<S>, S)) -> Self::Output {
+		(*self * rhs.0.extend(rhs.1)).truncate()
 	}
 }
 
@@ -271,59 +567,159 @@ impl ops::Mul<Matrix> for f32 {
 	}
 }
 
-impl ops::Mul<Matrix> for Matrix {
-	type Output = Matrix;
-	fn mul(self, rhs: Matrix) -> Self::Output {
-		let a: &cgmath::Matrix4<f32> = self.as_ref().into();
-		let b: &cgmath::Matrix4<f32> = rhs.as_ref().into();
-		let v: [[f32; 4]; 4] = (a * b).into();
-		v.into()
+impl ops::Mul<DMatrix> for f64 {
+	type Output = DMatrix;
+	fn mul(self, rhs: DMatrix) -> Self::Output {
+		ops::Mul::mul(rhs, self)
 	}
 }
 
-impl Default for Matrix {
+impl<S: Scalar> ops::Mul<Mat4<S>> for Mat4<S> {
+	type Output = Mat4<S>;
+	fn mul(self, rhs: Mat4<S>) -> Self::Output {
+		Mat4::from_columns(self * rhs.column(0), self * rhs.column(1),
+			self * rhs.column(2), self * rhs.column(3))
+	}
+}
+
+impl<S: Scalar> Default for Mat4<S> {
 	fn default() -> Self {
-		matrix!()
+		Mat4::diagonal(S::one())
 	}
 }
 
-impl AsRef<[[f32; 4]; 4]> for Matrix {
-	fn as_ref(&self) -> &[[f32; 4]; 4] {
+impl<S> AsRef<[[S; 4]; 4]> for Mat4<S> {
+	fn as_ref(&self) -> &[[S; 4]; 4] {
+		// Safe because `Mat4` is `#[repr(C)]` with 16 fields of `S`.
 		unsafe {
-			mem::transmute(self)
+			&*(self as *const Mat4<S> as *const [[S; 4]; 4])
 		}
 	}
 }
 
-impl From<[[f32; 4]; 4]> for Matrix {
-	fn from(array: [[f32; 4]; 4]) -> Self {
-		unsafe {
-			mem::transmute(array)
+impl<S: Copy> From<[[S; 4]; 4]> for Mat4<S> {
+	fn from(m: [[S; 4]; 4]) -> Self {
+		Mat4 {
+			m00: m[0][0], m01: m[0][1], m02: m[0][2], m03: m[0][3],
+			m10: m[1][0], m11: m[1][1], m12: m[1][2], m13: m[1][3],
+			m20: m[2][0], m21: m[2][1], m22: m[2][2], m23: m[2][3],
+			m30: m[3][0], m31: m[3][1], m32: m[3][2], m33: m[3][3],
 		}
 	}
 }
 
-impl Into<[[f32; 4]; 4]> for Matrix {
-	fn into(self) -> [[f32; 4]; 4] {
-		unsafe {
-			mem::transmute(self)
-		}
+impl<S> From<Mat4<S>> for [[S; 4]; 4] {
+	fn from(m: Mat4<S>) -> [[S; 4]; 4] {
+		[
+			[m.m00, m.m01, m.m02, m.m03],
+			[m.m10, m.m11, m.m12, m.m13],
+			[m.m20, m.m21, m.m22, m.m23],
+			[m.m30, m.m31, m.m32, m.m33],
+		]
 	}
 }
 
-impl From<f32> for Matrix {
-	fn from(arg: f32) -> Self {
-		Matrix::diagonal(arg)
+impl<S: Scalar> From<S> for Mat4<S> {
+	fn from(arg: S) -> Self {
+		Mat4::diagonal(arg)
 	}
 }
 
-impl Into<[f32;16]> for Matrix {
-	fn into(self) -> [f32; 16] {
+impl<S> From<Mat4<S>> for [S; 16] {
+	fn from(m: Mat4<S>) -> [S; 16] {
 		[
-			self.m00, self.m01, self.m02, self.m03,
-			self.m10, self.m11, self.m12, self.m13,
-			self.m20, self.m21, self.m22, self.m23,
-			self.m30, self.m31, self.m32, self.m33,
+			m.m00, m.m01, m.m02, m.m03,
+			m.m10, m.m11, m.m12, m.m13,
+			m.m20, m.m21, m.m22, m.m23,
+			m.m30, m.m31, m.m32, m.m33,
 		]
 	}
 }
+
+impl From<Matrix> for DMatrix {
+	fn from(matrix: Matrix) -> DMatrix {
+		matrix.cast()
+	}
+}
+
+#[test]
+fn test_matrix_decompose() {
+	let scale = vector!(2.0, 0.5, 3.0);
+	let rotation = Rotation::new(vector!(1.0, 1.0, 0.0).normalize(), 0.8);
+	let translate = vector!(4.0, -5.0, 6.0);
+	let m = Matrix::default().srt(scale, rotation, translate);
+
+	let (s, r, t) = m.decompose().unwrap();
+	assert!(s.approx_eq(scale, 0.0001));
+	assert!((r.dot(rotation).abs() - 1.0).abs() < 0.0001);
+	assert_eq!(t, translate);
+	assert_eq!(m.translation(), translate);
+	assert_eq!(m.column(3), Vector4::point(translate));
+	assert_eq!(m.row(3), Vector4::new(0.0, 0.0, 0.0, 1.0));
+
+	// Mirrored.
+	let mirror = Matrix::from_scale(vector!(1.0, -2.0, 1.0));
+	let (s, r, _) = mirror.decompose().unwrap();
+	let p = vector!(1.0, 2.0, 3.0);
+	assert!(Matrix::default().srt(s, r, Vector::zero()).transform_point(p)
+		.approx_eq(mirror.transform_point(p), 0.0001));
+
+	let shear = Matrix::new(
+		1.0, 0.0, 0.0, 0.0,
+		1.0, 1.0, 0.0, 0.0,
+		0.0, 0.0, 1.0, 0.0,
+		0.0, 0.0, 0.0, 1.0,
+	);
+	assert!(shear.decompose().is_none());
+	assert!(Matrix::from_scale(Vector::zero()).decompose().is_none());
+	let proj = Matrix::finite_perspective_projection(1.0, 1.0, 1.0, 10.0);
+	assert!(proj.decompose().is_none());
+
+	// Affine inverse is the same as the general inverse.
+	let inv = m.inverse_affine();
+	let general = m.inverse();
+	for i in 0..4 {
+		let d = inv.column(i) - general.column(i);
+		assert!(d.length() < 0.0001);
+	}
+	assert!(inv.transform_point(m.transform_point(p)).approx_eq(p, 0.0001));
+	assert_eq!(Matrix::from_translation(translate),
+		Matrix::default().t(translate));
+	assert_eq!(Matrix::from_rotation(rotation), Matrix::from(rotation));
+}
+
+#[test]
+fn test_matrix_view_projection() {
+	let eye = vector!(1.0, 2.0, 3.0);
+	let view = Matrix::look_at(eye, vector!(1.0, 2.0, -7.0),
+		vector!(0.0, 1.0, 0.0));
+
+	assert!(view.transform_point(eye).approx_eq(Vector::zero(), 0.0001));
+	assert!(view.transform_point(vector!(1.0, 3.0, 0.0))
+		.approx_eq(vector!(0.0, 1.0, -3.0), 0.0001));
+	let view = Matrix::look_at(eye, vector!(5.0, 2.0, 3.0),
+		vector!(0.0, 1.0, 0.0));
+	assert!(view.transform_point(vector!(6.0, 2.0, 3.0))
+		.approx_eq(vector!(0.0, 0.0, -5.0), 0.0001));
+
+	let (near, far) = (0.5, 100.0);
+	let reversed = Matrix::reversed_perspective_projection(1.0, 1.5, near,
+		far);
+	let depth = |m: Matrix, d: f32| m.project_point(vector!(0.0, 0.0, -d)).z;
+	assert!((depth(reversed, near) - 1.0).abs() < 0.0001);
+	assert!(depth(reversed, far).abs() < 0.0001);
+
+	let infinite = Matrix::infinite_perspective_projection(1.0, 1.5, near);
+	assert!((depth(infinite, near) + 1.0).abs() < 0.0001);
+	assert!(depth(infinite, 1.0e6) < 1.0);
+	assert!((depth(infinite, 1.0e6) - 1.0).abs() < 0.0001);
+	let finite = Matrix::finite_perspective_projection(1.0, 1.5, near, far);
+	assert_eq!(infinite.column(0), finite.column(0));
+	assert_eq!(infinite.column(1), finite.column(1));
+
+	let infinite = Matrix::infinite_reversed_perspective_projection(1.0, 1.5,
+		near);
+	assert!((depth(infinite, near) - 1.0).abs() < 0.0001);
+	assert!(depth(infinite, 1.0e6) > 0.0);
+	assert!(depth(infinite, 1.0e6) < 0.0001);
+}
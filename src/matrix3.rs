@@ -0,0 +1,250 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::{fmt, ops};
+use *;
+
+/// 3x3 Matrix (by column, like `Mat4`), generic over the `Scalar` type.  Used
+/// for rotation and scale without translation, such as normal matrices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Mat3<S> {
+	pub m00: S, pub m01: S, pub m02: S,
+	pub m10: S, pub m11: S, pub m12: S,
+	pub m20: S, pub m21: S, pub m22: S,
+}
+
+/// Single-precision 3x3 Matrix.
+pub type Matrix3 = Mat3<f32>;
+
+/// Double-precision 3x3 Matrix.
+pub type DMatrix3 = Mat3<f64>;
+
+impl<S: Scalar> fmt::Display for Mat3<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"({}, {}, {}; {}, {}, {}; {}, {}, {})",
+			self.m00, self.m01, self.m02,
+			self.m10, self.m11, self.m12,
+			self.m20, self.m21, self.m22,
+		)
+	}
+}
+
+impl<S: Scalar> Mat3<S> {
+	/// Full constructor.
+	#[allow(clippy::too_many_arguments)]
+	pub const fn new(
+		m00: S, m01: S, m02: S,
+		m10: S, m11: S, m12: S,
+		m20: S, m21: S, m22: S,
+	) -> Self {
+		Mat3 {
+			m00, m01, m02,
+			m10, m11, m12,
+			m20, m21, m22,
+		}
+	}
+
+	/// Diagonal constructor.
+	pub fn diagonal(di: S) -> Self {
+		let z = S::zero();
+
+		Mat3::new(
+			di, z, z,
+			z, di, z,
+			z, z, di,
+		)
+	}
+
+	/// The normal matrix of `matrix` (the inverse transpose of the rotation
+	/// and scale), which transforms normals so they stay perpendicular to
+	/// surfaces.  `None` if `matrix` flattens space (has no inverse).
+	pub fn normal(matrix: Mat4<S>) -> Option<Self> {
+		matrix.truncate().try_invert().map(Mat3::transpose)
+	}
+
+	fn from_columns(a: Vec3<S>, b: Vec3<S>, c: Vec3<S>) -> Self {
+		Mat3::new(
+			a.x, a.y, a.z,
+			b.x, b.y, b.z,
+			c.x, c.y, c.z,
+		)
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Mat3<T> {
+		Mat3::from_columns(self.column(0).cast(), self.column(1).cast(),
+			self.column(2).cast())
+	}
+
+	/// Add a row and column, without translation, to make a `Mat4`.
+	pub fn extend(self) -> Mat4<S> {
+		let (o, z) = (S::one(), S::zero());
+
+		Mat4::new(
+			self.m00, self.m01, self.m02, z,
+			self.m10, self.m11, self.m12, z,
+			self.m20, self.m21, self.m22, z,
+			z, z, z, o,
+		)
+	}
+
+	/// Get column `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is not 0, 1 or 2.
+	pub fn column(&self, i: usize) -> Vec3<S> {
+		match i {
+			0 => Vec3::new(self.m00, self.m01, self.m02),
+			1 => Vec3::new(self.m10, self.m11, self.m12),
+			2 => Vec3::new(self.m20, self.m21, self.m22),
+			_ => panic!("Mat3 column {} out of range", i),
+		}
+	}
+
+	/// Get row `i`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `i` is not 0, 1 or 2.
+	pub fn row(&self, i: usize) -> Vec3<S> {
+		match i {
+			0 => Vec3::new(self.m00, self.m10, self.m20),
+			1 => Vec3::new(self.m01, self.m11, self.m21),
+			2 => Vec3::new(self.m02, self.m12, self.m22),
+			_ => panic!("Mat3 row {} out of range", i),
+		}
+	}
+
+	/// Computes the matrix determinant.
+	pub fn determinant(self) -> S {
+		self.column(0).dot(self.column(1).cross(self.column(2)))
+	}
+
+	/// Returns the matrix transpose.
+	pub fn transpose(self) -> Self {
+		Mat3::from_columns(self.row(0), self.row(1), self.row(2))
+	}
+
+	/// Computes the matrix inverse.
+	///
+	/// ## Panics
+	///
+	/// Panics if the matrix has no inverse (i.e. has zero determinant).
+	pub fn inverse(self) -> Self {
+		self.try_invert().unwrap()
+	}
+
+	/// Attempts to compute the matrix inverse, returning `None` if the
+	/// matrix is non-invertible (i.e. has zero determinant).
+	pub fn try_invert(self) -> Option<Self> {
+		let (a, b, c) = (self.column(0), self.column(1), self.column(2));
+		let det = self.determinant();
+
+		if det == S::zero() {
+			return None;
+		}
+
+		// The rows of the inverse are the cross products of the columns.
+		Some(Mat3::from_columns(b.cross(c), c.cross(a), a.cross(b))
+			.transpose() * det.recip())
+	}
+}
+
+impl<S: Scalar> ops::Mul<S> for Mat3<S> {
+	type Output = Mat3<S>;
+	fn mul(self, rhs: S) -> Self::Output {
+		Mat3::from_columns(self.column(0) * rhs, self.column(1) * rhs,
+			self.column(2) * rhs)
+	}
+}
+
+impl<S: Scalar> ops::Mul<Vec3<S>> for Mat3<S> {
+	type Output = Vec3<S>;
+	fn mul(self, rhs: Vec3<S>) -> Self::Output {
+		self.column(0) * rhs.x + self.column(1) * rhs.y
+			+ self.column(2) * rhs.z
+	}
+}
+
+impl<S: Scalar> ops::Mul<Mat3<S>> for Mat3<S> {
+	type Output = Mat3<S>;
+	fn mul(self, rhs: Mat3<S>) -> Self::Output {
+		Mat3::from_columns(self * rhs.column(0), self * rhs.column(1),
+			self * rhs.column(2))
+	}
+}
+
+impl<S: Scalar> Default for Mat3<S> {
+	fn default() -> Self {
+		Mat3::diagonal(S::one())
+	}
+}
+
+impl<S> AsRef<[[S; 3]; 3]> for Mat3<S> {
+	fn as_ref(&self) -> &[[S; 3]; 3] {
+		// Safe because `Mat3` is `#[repr(C)]` with 9 fields of `S`.
+		unsafe {
+			&*(self as *const Mat3<S> as *const [[S; 3]; 3])
+		}
+	}
+}
+
+impl<S: Copy> From<[[S; 3]; 3]> for Mat3<S> {
+	fn from(m: [[S; 3]; 3]) -> Self {
+		Mat3 {
+			m00: m[0][0], m01: m[0][1], m02: m[0][2],
+			m10: m[1][0], m11: m[1][1], m12: m[1][2],
+			m20: m[2][0], m21: m[2][1], m22: m[2][2],
+		}
+	}
+}
+
+impl<S> From<Mat3<S>> for [[S; 3]; 3] {
+	fn from(m: Mat3<S>) -> [[S; 3]; 3] {
+		[
+			[m.m00, m.m01, m.m02],
+			[m.m10, m.m11, m.m12],
+			[m.m20, m.m21, m.m22],
+		]
+	}
+}
+
+impl<S: Scalar> From<Quat<S>> for Mat3<S> {
+	fn from(rotation: Quat<S>) -> Mat3<S> {
+		Mat4::from(rotation).truncate()
+	}
+}
+
+impl From<Matrix3> for DMatrix3 {
+	fn from(matrix: Matrix3) -> DMatrix3 {
+		matrix.cast()
+	}
+}
+
+#[test]
+fn test_matrix3_normal() {
+	let m = Matrix::from_scale(vector!(2.0, 1.0, 1.0));
+	let normal = Matrix3::normal(m).unwrap();
+
+	// A 45° surface stretched along X tilts toward X, so the normal tilts
+	// away.
+	let n = normal * vector!(1.0, 1.0, 0.0);
+	let along = m.transform_vector(vector!(1.0, -1.0, 0.0));
+	assert!(n.dot(along).abs() < 0.0001);
+	assert!(Matrix3::normal(Matrix::from_scale(vector!(0.0, 1.0, 1.0)))
+		.is_none());
+
+	let r = Matrix3::from(Rotation::new(vector!(0.0, 1.0, 0.0), 0.7));
+	assert!((r * r.inverse()).column(0).approx_eq(vector!(1.0, 0.0, 0.0),
+		0.0001));
+	assert!((r.determinant() - 1.0).abs() < 0.0001);
+	assert_eq!(r.transpose().row(1), r.column(1));
+}
@@ -0,0 +1,274 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::fmt;
+
+pub(crate) const LINK: usize = 15;			// link to coincident leaf nodes
+pub(crate) const LEAF: u32 = 0xFF_FF_FF_FF;	// max u32 value (invalid handle)
+
+/// A 32-bit index value.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Id(pub(crate) u32);
+
+impl Id {
+	/// Get an `Id` that represents nothing.
+	pub(crate) fn none() -> Self {
+		Id(0)
+	}
+
+	/// Does this `Id` represent nothing?
+	pub(crate) fn is_none(&self) -> bool {
+		self.0 == 0
+	}
+
+	/// Does this `Id` represent something?
+	pub(crate) fn is_some(&self) -> bool {
+		!self.is_none()
+	}
+}
+
+impl From<usize> for Id {
+	fn from(index: usize) -> Id {
+		Id(index as u32 + 1)
+	}
+}
+
+impl From<Id> for usize {
+	fn from(id: Id) -> usize {
+		(id.0 - 1) as usize
+	}
+}
+
+/// A node is either a branch or a leaf.
+///
+/// A branch can have up to `B` child nodes (8 octants adjacent to the center
+/// for an octree, 4 quadrants for a quadtree) and `15 - B` objects, plus an
+/// optional link to a leaf.
+///
+/// A leaf can store up to 14 points; the first child must contain a LEAF
+/// sentinel value, and the last may link to another leaf node.
+///
+/// Each node has an implicit bounding box determined by its position in the
+/// tree.  The bounding box contains all descendant nodes.
+#[derive(Clone)]
+pub(crate) struct Node<const B: usize> {
+	/// child node handles
+	pub(crate) child: [Id; 16],
+}
+
+impl<const B: usize> fmt::Display for Node<B> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_leaf() {
+			let l = self.link();
+			if l.is_some() {
+				write!(f, " LINK ")?;
+			}
+			for i in 0..=14 {
+				let id = self.child[i];
+				if id.is_some() {
+					let id: usize = id.into();
+					write!(f, "{} ", id)?;
+				}
+			}	
+		} else {
+			write!(f, "Branch: [")?;
+			for i in B..=14 {
+				let id = self.child[i];
+				if id.is_some() {
+					let id: usize = id.into();
+					write!(f, "{} ", id)?;
+				}
+			}
+			write!(f, "] -D [")?;
+			for i in 0..B {
+				let id = self.child[i];
+				if id.is_some() {
+					let id: usize = id.into();
+					write!(f, "{}:{}", i, id)?;
+				}
+			}
+			write!(f, "];")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<const B: usize> fmt::Debug for Node<B> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_leaf() {
+			write!(f, "leaf")?;
+//			try!(write!(f, "leaf: {:?}", self.leaf_children()));
+			let l = self.link();
+			if l.is_some() {
+				write!(f, " link: {:?}", l)?;
+			}
+			Ok(())
+		} else {
+			write!(f, "branch: {:?}", self.child)
+		}
+	}
+}
+
+impl<const B: usize> Node<B> {
+	/// Create a new leaf node
+	pub(crate) fn new_leaf() -> Node<B> {
+		Node {
+			// no elements, no linking
+			child: [
+				Id(LEAF), Id::none(), Id::none(), Id::none(),
+				Id::none(), Id::none(), Id::none(), Id::none(),
+				Id::none(), Id::none(), Id::none(), Id::none(),
+				Id::none(), Id::none(), Id::none(), Id::none()
+			],
+		}
+	}
+
+	/// Create a new branch node
+	pub(crate) fn new_branch() -> Node<B> {
+		Node {
+			child: [Id::none(); 16],
+		}
+	}
+
+	/// Test if a node is a leaf
+	pub(crate) fn is_leaf(&self) -> bool {
+		self.child[0] == Id(LEAF)
+	}
+
+	/// Test if a node is a branch
+	pub(crate) fn is_branch(&self) -> bool {
+		!self.is_leaf()
+	}
+
+	/// Get link to next link node ID
+	pub(crate) fn link(&self) -> Option<usize> {
+		// Can be a branch or a leaf.
+		if self.child[LINK].is_none() {
+			// No link found - shouldn't happen.
+			None
+		} else {
+			// Convert link Id to usize
+			Some(self.child[LINK].into())
+		}
+	}
+
+	/// None has no child branches, no collider Ids and (for branches) no
+	/// link.  A leaf's link is spliced into its parent when it's removed.
+	pub(crate) fn is_empty(&self) -> bool {
+		let ids = if self.is_branch() {
+			// First B are branches.
+			&self.child[..]
+		} else {
+			// Skip sentinel and link.
+			&self.child[1..=14]
+		};
+
+		for i in ids {
+			if i.is_some() {
+				return false // isn't empty
+			}
+		}
+
+		true // is empty
+	}
+
+	/// Find the only full ch. branch, if there is only one, None otherwise.
+	pub(crate) fn branch_is_one(&self) -> Option<usize> {
+		assert!(self.is_branch());
+		// First B are branches.
+		let mut found = None;
+
+		for i in 0..B { // First B are branches
+			if self.child[i].is_some() {
+				if found.is_some() { // 2, not 1
+					return None;
+				} else {
+					found = Some(i);
+				}
+			}
+		}
+		for i in &self.child[B..] { // Collider Ids and link
+			if i.is_some() {
+				return None // isn't empty
+			}
+		}
+
+		found
+	}
+
+	/// Find the first open child slot in a branch, None if full.
+	pub(crate) fn branch_open_slot(&self) -> Option<usize> {
+		assert!(self.is_branch());
+		// Skip 0..B as that is descending the tree, and skip 15 (link)
+		for i in B..=14 {
+			if self.child[i].is_none() { return Some(i) }
+		}
+		None
+	}
+
+	/// Add a collider to a branch node.
+	pub(crate) fn branch_add_collider(&mut self, id: Id) -> Option<()> {
+		assert!(self.is_branch());
+		let s = self.branch_open_slot()?;
+		self.child[s] = id;
+		// Successfully added it.
+		Some(())
+	}
+
+	/// Add a collider to a leaf node.
+	pub(crate) fn leaf_add_collider(&mut self, id: Id) -> Option<()> {
+		assert!(self.is_leaf());
+		// Skip 0 (sentinel), and skip 15 (link)
+		for i in 1..=14 {
+			if self.child[i].is_none() {
+				self.child[i] = id;
+				// Successfully added it.
+				return Some(());
+			}
+		}
+		None
+	}
+
+	/// Remove a collider from a branch node.
+	pub(crate) fn branch_remove_collider(&mut self, id: Id) -> Option<()> {
+		assert!(self.is_branch());
+		// Look for collider in this branch.
+		for i in B..=14 {
+			// Delete it when found, and return successfully.
+			if self.child[i] == id {
+				self.child[i] = Id::none();
+				return Some(());
+			}
+		}
+		// Not here, look in links next.
+		None
+	}
+
+	/// Remove a collider from a leaf node.
+	pub(crate) fn leaf_remove_collider(&mut self, id: Id) -> Option<()> {
+		assert!(self.is_leaf());
+		// Look for collider in this branch.
+		for i in 1..=14 {
+			// Delete it when found, and return successfully.
+			if self.child[i] == id {
+				self.child[i] = Id::none();
+				return Some(());
+			}
+		}
+		// Not here, look in links next.
+		None
+	}
+
+	/// Remove a collider from a node.
+	pub(crate) fn remove_collider(&mut self, id: Id) -> Option<()> {
+		if self.is_branch() {
+			self.branch_remove_collider(id)
+		} else {
+			self.leaf_remove_collider(id)
+		}
+	}
+}
@@ -4,7 +4,13 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-use std::fmt;
+use frozen::FrozenNode;
+use node::{self, Id};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::{fmt, ops};
+use tree::{self, Cell};
 use *;
 
 /// An octree is a DAG that can quickly search for points in 3D space.
@@ -17,760 +23,1136 @@ use *;
 /// The nodes are stored in a vector, and are indexed using a 32-bit node ID.
 /// This saves memory over using pointers on 64-bit systems.  Node ID 1 is the
 /// first node in the vector.
-pub struct Octree<T: Collider> {
-	colliders: Vec<T>,
-	collider_garbage: Vec<Id>,
-	nodes: Vec<Node>,
-	garbage: Vec<Id>,
-	bcube: BCube,
-	root: Id,
-	n_colliders: u32,
+///
+/// By default, a collider that straddles the center of a branch stays in that
+/// branch.  A loose octree (see `Octree::loose()`) enlarges the bounds of each
+/// branch instead, and places colliders by their center and size, so that
+/// large colliders don't pile up near the root.
+///
+/// An octree is `Sync` if its colliders are, so it can be queried from many
+/// threads at once.  With the `rayon` feature, `par_query_bbox()` and
+/// `par_collision_pairs()` split a query between threads.  To keep querying
+/// while the octree changes, publish an `OctreeSnapshot` with `snapshot()`.
+///
+/// The root only shrinks when it has a single child branch, so after the
+/// colliders move far from where they were, `rebalance()` fits it to them
+/// again.  To stop outliers from growing the root forever, `set_max_root()`
+/// limits its size; colliders that don't fit are kept in an overflow list,
+/// which queries check one by one.  For a world with known extents,
+/// `Octree::with_bounds()` fixes the root instead, so that it never grows or
+/// shrinks, and colliders that aren't entirely inside go in the overflow list.
+pub struct Octree<T: Collider<S>, S: Scalar = f32> {
+	colliders: Vec<Option<T>>,
+	tree: Tree<S>,
 }
 
-const LINK: usize = 15;			// link to coincident leaf nodes
-const LEAF: u32 = 0xFF_FF_FF_FF;	// max u32 value (invalid handle)
+const BRANCHES: usize = 8;		// child branches (octants) per node
 
-/// A 32-bit index value.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct Id(u32);
+pub(crate) type Node = node::Node<BRANCHES>;
+type Tree<S> = tree::Tree<BCube<S>, S, BRANCHES>;
 
-impl Id {
-	/// Get an `Id` that represents nothing.
-	fn none() -> Self {
-		Id(0)
+impl<T, S> Default for Octree<T, S> where T: Collider<S>, S: Scalar {
+	fn default() -> Self {
+		Self::new()
 	}
+}
 
-	/// Does this `Id` represent nothing?
-	fn is_none(&self) -> bool {
-		self.0 == 0
+impl<T, S> Octree<T, S> where T: Collider<S>, S: Scalar {
+	/// Create a new octree
+	pub fn new() -> Octree<T, S> {
+		Octree { colliders: vec![], tree: Tree::new() }
 	}
 
-	/// Does this `Id` represent something?
-	fn is_some(&self) -> bool {
-		!self.is_none()
+	/// Create a new loose octree, where the bounds of each branch are
+	/// enlarged by `looseness` (2 is typical).  Colliders go in the child
+	/// containing their center if they fit in its enlarged bounds.
+	///
+	/// ## Panics
+	///
+	/// Panics if `looseness` is less than 1.
+	pub fn loose(looseness: S) -> Octree<T, S> {
+		Octree { colliders: vec![], tree: Tree::loose(looseness) }
 	}
-}
 
-impl Into<Id> for usize {
-	fn into(self) -> Id {
-		Id(self as u32 + 1)
+	/// Create a new octree with a fixed root `bounds`, which never grows or
+	/// shrinks.  Colliders that aren't entirely within `bounds` (or are flat
+	/// on one of its greater faces) go in the overflow list.
+	///
+	/// ## Panics
+	///
+	/// Panics if `bounds` is empty.
+	pub fn with_bounds(bounds: BCube<S>) -> Octree<T, S> {
+		Octree { colliders: vec![], tree: Tree::with_bounds(bounds) }
 	}
-}
 
-impl Into<usize> for Id {
-	fn into(self) -> usize {
-		(self.0 - 1) as usize
+	/// Get the fixed root bounds, if any (see `with_bounds()`).
+	pub fn bounds(&self) -> Option<BCube<S>> {
+		self.tree.bounds
 	}
-}
 
-/// A node is either a branch or a leaf.
-///
-/// A branch can have up to 8 child nodes (each octant adjacent to the center)
-/// and 7 objects, plus an optional link to a leaf.
-///
-/// A leaf can store up to 14 points; the first child must contain a LEAF
-/// sentinel value, and the last may link to another leaf node.
-///
-/// Each node has an implicit bounding box determined by its position in the
-/// octree.  The bounding box contains all descendant nodes.
-struct Node {
-	/// child node handles
-	child: [Id; 16],
-}
+	/// Get how much the bounds of each branch are enlarged (1 if not
+	/// loose).
+	pub fn looseness(&self) -> S {
+		self.tree.looseness
+	}
 
-impl fmt::Display for Node {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		if self.is_leaf() {
-			let l = self.link();
-			if l.is_some() {
-				write!(f, " LINK ")?;
-			}
-			for i in 0..=14 {
-				let id = self.child[i];
-				if id.is_some() {
-					let id: usize = id.into();
-					write!(f, "{} ", id)?;
-				}
-			}	
+	/// Limit the half length of the root to `half_len`, or don't limit it
+	/// if `None` (the default).  Colliders that would grow the root past it
+	/// go in the overflow list instead.  Colliders already in the octree
+	/// stay where they are until `rebalance()`.  Ignored if the root is
+	/// fixed with `with_bounds()`.
+	pub fn set_max_root(&mut self, half_len: Option<S>) {
+		self.tree.max_root = half_len;
+	}
+
+	/// Get the largest half length the root can grow to, if limited.
+	pub fn max_root(&self) -> Option<S> {
+		self.tree.max_root
+	}
+
+	/// Get the `Id`s of the colliders in the overflow list, which don't fit
+	/// under the largest root, or are outside the fixed bounds.
+	pub fn overflow(&self) -> &[Id] {
+		&self.tree.overflow
+	}
+
+	/// Clear the octree.
+	pub fn clear(&mut self) {
+		self.colliders.clear();
+		self.tree.clear();
+	}
+
+	/// Add a point in the octree
+	///
+	/// ## Panics
+	///
+	/// Panics if `try_add()` fails.
+	pub fn add(&mut self, point: T) -> Id {
+		self.try_add(point).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	/// Add a collider, or fail if its `BBox` is invalid, or the octree is
+	/// full.
+	pub fn try_add(&mut self, point: T) -> Result<Id, OctreeError> {
+		let id = self.tree.add(point.bbox())?;
+
+		// Put the collider at its id.
+		if usize::from(id) < self.colliders.len() {
+			self.colliders[usize::from(id)] = Some(point);
 		} else {
-			write!(f, "Branch: [")?;
-			for i in 8..=14 {
-				let id = self.child[i];
-				if id.is_some() {
-					let id: usize = id.into();
-					write!(f, "{} ", id)?;
-				}
-			}
-			write!(f, "] -D [")?;
-			for i in 0..8 {
-				let id = self.child[i];
-				if id.is_some() {
-					let id: usize = id.into();
-					write!(f, "{}:{}", i, id)?;
-				}
-			}
-			write!(f, "];")?;
+			self.colliders.push(Some(point));
 		}
 
-		Ok(())
+		Ok(id)
 	}
-}
 
-impl fmt::Debug for Node {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		if self.is_leaf() {
-			try!(write!(f, "leaf"));
-//			try!(write!(f, "leaf: {:?}", self.leaf_children()));
-			let l = self.link();
-			if l.is_some() {
-				try!(write!(f, " link: {:?}", l));
-			}
-			Ok(())
-		} else {
-			write!(f, "branch: {:?}", self.child)
+	/// Remove a point from the octree
+	///
+	/// ## Panics
+	///
+	/// Panics if `id` isn't in the octree.
+	pub fn remove(&mut self, id: Id) -> T {
+		self.try_remove(id).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	/// Remove a collider, or fail if `id` isn't in the octree.  The `Id` of
+	/// a removed collider is reused by the next one added, so removing with
+	/// it again after that removes the new collider instead.
+	pub fn try_remove(&mut self, id: Id) -> Result<T, OctreeError> {
+		self.tree.remove(id)?;
+
+		// Take the collider out.
+		let ret = self.colliders[usize::from(id)].take()
+			.expect("no such collider");
+
+		if self.is_empty() {
+			self.clear();
 		}
+
+		Ok(ret)
+	}
+
+	/// Rebuild the tree under a root fitted to the colliders in it, so the
+	/// root shrinks to where they are now (unless it's fixed).  Colliders in
+	/// the overflow list go back in the tree if they fit.  `Id`s don't
+	/// change.
+	pub fn rebalance(&mut self) {
+		self.tree.rebalance();
 	}
-}
 
-impl Node {
-	/// Create a new leaf node
-	fn new_leaf() -> Node {
-		Node {
-			// no elements, no linking
-			child: [
-				Id(LEAF), Id::none(), Id::none(), Id::none(),
-				Id::none(), Id::none(), Id::none(), Id::none(),
-				Id::none(), Id::none(), Id::none(), Id::none(),
-				Id::none(), Id::none(), Id::none(), Id::none()
-			],
+	/// Get a collider, or `None` if `id` isn't in the octree.
+	pub fn get(&self, id: Id) -> Option<&T> {
+		if id.is_none() {
+			return None;
 		}
+
+		self.colliders.get(usize::from(id))?.as_ref()
+	}
+
+	/// Get a collider to change.  If its `BBox` changes, it's moved in the
+	/// octree when the returned `ColliderMut` is dropped (unless the new
+	/// `BBox` is invalid; see `try_update()`).  `None` if `id` isn't in the
+	/// octree.
+	pub fn get_mut(&mut self, id: Id) -> Option<ColliderMut<'_, T, S>> {
+		self.get(id)?;
+
+		Some(ColliderMut { octree: self, id })
 	}
 
-	/// Create a new branch node
-	fn new_branch() -> Node {
-		Node {
-			child: [Id::none(); 16],
+	/// Get a collider to change, without moving it in the octree (like
+	/// `IndexMut`).  If its `BBox` changes, call `update()` after.  `None`
+	/// if `id` isn't in the octree.
+	pub fn get_mut_untracked(&mut self, id: Id) -> Option<&mut T> {
+		if id.is_none() {
+			return None;
 		}
+
+		self.colliders.get_mut(usize::from(id))?.as_mut()
 	}
 
-	/// Test if a node is a leaf
-	fn is_leaf(&self) -> bool {
-		self.child[0] == Id(LEAF)
+	/// Move a collider in the octree if its `BBox` changed since it was
+	/// added (or last updated).  Returns true if it moved.
+	///
+	/// ## Panics
+	///
+	/// Panics if `try_update()` fails.
+	pub fn update(&mut self, id: Id) -> bool {
+		self.try_update(id).unwrap_or_else(|e| panic!("{}", e))
 	}
 
-	/// Test if a node is a branch
-	fn is_branch(&self) -> bool {
-		!self.is_leaf()
+	/// Like `update()`, but fails if `id` isn't in the octree, or the new
+	/// `BBox` is invalid (then the collider stays where it was).
+	pub fn try_update(&mut self, id: Id) -> Result<bool, OctreeError> {
+		let bbox = self.get(id).ok_or(OctreeError::StaleId)?.bbox();
+
+		self.tree.update(id, bbox)
 	}
 
-	/// Get link to next link node ID
-	fn link(&self) -> Option<usize> {
-		// Can be a branch or a leaf.
-		if self.child[LINK].is_none() {
-			// No link found - shouldn't happen.
-			None
-		} else {
-			// Convert link Id to usize
-			Some(self.child[LINK].into())
-		}
+	/// Rebuild the octree densely, without any garbage, giving back the
+	/// memory.  Colliders keep their order, but move down to fill the gaps
+	/// left by removed ones; returns `(old, new)` for each changed `Id`.
+	pub fn compact(&mut self) -> Vec<(Id, Id)> {
+		self.colliders.retain(Option::is_some);
+		self.colliders.shrink_to_fit();
+		self.tree.compact()
 	}
 
-	/// None has no child branches and no collider Ids
-	fn is_empty(&self) -> bool {
-		assert!(self.is_branch());
-		// First 8 are branches.
-		for i in &self.child[..=14] { // skip link
-			if i.is_some() {
-				return false // isn't empty
-			}
-		}
+	/// Reserve room for at least `additional` more colliders, and a node
+	/// for each of them.  Trees that subdivide deeply may still need more
+	/// nodes than that.
+	pub fn reserve(&mut self, additional: usize) {
+		let colliders = additional
+			.saturating_sub(self.tree.collider_garbage.len());
 
-		true // is empty
+		self.colliders.reserve(colliders);
+		self.tree.reserve(additional);
 	}
 
-	/// Find the only full ch. branch, if there is only one, None otherwise.
-	fn branch_is_one(&self) -> Option<usize> {
-		assert!(self.is_branch());
-		// First 8 are branches.
-		let mut found = None;
+	/// Give back as much unused memory as possible, without moving any
+	/// colliders (see `compact()`).
+	pub fn shrink_to_fit(&mut self) {
+		self.colliders.shrink_to_fit();
+		self.tree.shrink_to_fit();
+	}
 
-		for i in 0..8 { // First 8 are branches
-			if self.child[i].is_some() {
-				if found.is_some() { // 2, not 1
-					return None;
-				} else {
-					found = Some(i);
-				}
-			}
-		}
-		for i in &self.child[8..=14] { // Skip link are collider Ids
-			if i.is_some() {
-				return None // isn't empty
-			}
-		}
+	/// Get the `BBox` a collider had when it was added, which is where it
+	/// is in the octree, and what queries test.  Changing a collider with
+	/// `IndexMut` doesn't change it.  `None` if `id` isn't in the octree.
+	pub fn stored_bbox(&self, id: Id) -> Option<BBox<S>> {
+		self.tree.stored_bounds(id)
+	}
 
-		found
+	/// Get the number of colliders in the octree.
+	pub fn len(&self) -> usize {
+		self.tree.len()
 	}
 
-	/// Find the first open child slot in a branch, None if full.
-	fn branch_open_slot(&self) -> Option<usize> {
-		assert!(self.is_branch());
-		// Skip 0-7 as that is descending the octree, and skip 15 (link)
-		for i in 8..=14 {
-			if self.child[i].is_none() { return Some(i) }
-		}
-		None
-	}
-
-	/// Add a collider to a branch node.
-	fn branch_add_collider(&mut self, id: Id) -> Option<()> {
-		assert!(self.is_branch());
-		let s = self.branch_open_slot()?;
-		self.child[s] = id;
-		// Successfully added it.
-		Some(())
-	}
-
-	/// Remove a collider from a branch node.
-	fn branch_remove_collider(&mut self, id: Id) -> Option<()> {
-		assert!(self.is_branch());
-		// Look for collider in this branch.
-		for i in 8..=14 {
-			// Delete it when found, and return successfully.
-			if self.child[i] == id {
-				self.child[i] = Id::none();
-				return Some(());
-			}
-		}
-		// Not here, look in links next.
-		return None;
-	}
-
-	/// Remove a collider from a leaf node.
-	fn leaf_remove_collider(&mut self, id: Id) -> Option<()> {
-		assert!(self.is_leaf());
-		// Look for collider in this branch.
-		for i in 1..=14 {
-			// Delete it when found, and return successfully.
-			if self.child[i] == id {
-				self.child[i] = Id::none();
-				return Some(());
-			}
-		}
-		// Not here, look in links next.
-		return None;
+	/// Check if there are no colliders in the octree.
+	pub fn is_empty(&self) -> bool {
+		self.tree.is_empty()
 	}
 
-	/// Remove a collider from a node.
-	fn remove_collider(&mut self, id: Id) -> Option<()> {
-		if self.is_branch() {
-			self.branch_remove_collider(id)
-		} else {
-			self.leaf_remove_collider(id)
-		}
+	/// Get the `Id`s of all colliders whose `BBox` collides with `bbox`.
+	pub fn query_bbox(&self, bbox: BBox<S>) -> Vec<Id> {
+		self.tree.query(
+			|bcube| bbox.collide_bcube(bcube),
+			|other| bbox.collide(other),
+		)
 	}
 
-	/// Determine which child for a branch point (2)
-	fn which_child2(c: Vector, p: Vector) -> [bool; 3] {
-		[p.x < c.x, p.y < c.y, p.z < c.z]
+	/// Get the `Id`s of all colliders whose `BBox` is hit by a ray from
+	/// `origin` along `direction`.
+	pub fn query_ray(&self, origin: Vec3<S>, direction: Vec3<S>)
+		-> Vec<Id>
+	{
+		self.tree.query(
+			|bcube| bcube.to_bbox().intersect_ray(origin, direction)
+				.is_some(),
+			|other| other.intersect_ray(origin, direction)
+				.is_some(),
+		)
 	}
 
-	/// Determine which child for a branch bbox, if there is one it fully
-	/// fits into.
-	fn which_child_bbox(c: Vector, mut p: BBox) -> Option<usize> {
-		if p.min.x >= c.x - ::std::f32::EPSILON && p.min.x <= c.x + ::std::f32::EPSILON {
-//			println!("MATCHED minX");
-			p.min.x = p.max.x;
-		}
-		if p.min.y >= c.y - ::std::f32::EPSILON && p.min.y <= c.y + ::std::f32::EPSILON {
-//			println!("MATCHED minY");
-			p.min.y = p.max.y;
-		}
-		if p.min.z >= c.z - ::std::f32::EPSILON && p.min.z <= c.z + ::std::f32::EPSILON {
-//			println!("MATCHED minZ");
-			p.min.z = p.max.z;
-		}
-		if p.max.x >= c.x - ::std::f32::EPSILON && p.max.x <= c.x + ::std::f32::EPSILON {
-//			println!("MATCHED maxX");
-			p.max.x = p.min.x;
+	/// Get each pair of colliders whose `BBox`es collide, with the lesser
+	/// `Id` first.
+	pub fn collision_pairs(&self) -> Vec<(Id, Id)> {
+		self.tree.collision_pairs()
+	}
+
+	/// Publish an `OctreeSnapshot` of the octree as it is now, which only
+	/// copies what changed since the last snapshot.
+	pub fn snapshot(&mut self) -> OctreeSnapshot<S> {
+		OctreeSnapshot {
+			nodes: self.tree.nodes.publish(),
+			bboxes: self.tree.stored.publish(),
+			bcube: self.tree.cell,
+			looseness: self.tree.looseness,
+			overflow: Arc::new(self.tree.overflow.clone()),
+			root: self.tree.root,
+			len: self.len(),
 		}
-		if p.max.y >= c.y - ::std::f32::EPSILON && p.max.y <= c.y + ::std::f32::EPSILON {
-//			println!("MATCHED maxY");
-			p.max.y = p.min.y;
+	}
+
+	/// Make a `FrozenOctree` copy of the octree, which is faster to query.
+	pub fn freeze(&self) -> FrozenOctree<S> {
+		let tree = &self.tree;
+		let mut frozen = FrozenOctree {
+			nodes: vec![],
+			colliders: Vec::with_capacity(self.len()),
+		};
+
+		// Colliders in the overflow list go first in the root, which is
+		// enlarged to contain them.
+		let mut outside: Option<BBox<S>> = None;
+		for id in &tree.overflow {
+			let bbox = tree.stored(*id);
+			frozen.colliders.push((bbox, *id));
+			outside = Some(outside.map_or(bbox, |b| b.union(bbox)));
 		}
-		if p.max.z >= c.z - ::std::f32::EPSILON && p.max.z <= c.z + ::std::f32::EPSILON {
-//			println!("MATCHED maxZ");
-			p.max.z = p.min.z;
+
+		// Breadth-first, with each node's children together sorted by
+		// their Morton digit.  The Morton code of a cell is its parent's
+		// code followed by that digit, so each level is in Morton order.
+		// The frozen index of each node is its index here.
+		let mut queue = vec![];
+		let mut i = 0;
+
+		if tree.root.is_some() {
+			queue.push((tree.root, tree.cell));
+		} else if let Some(bounds) = outside {
+			frozen.nodes.push(FrozenNode {
+				bounds,
+				first_child: 1,
+				n_children: 0,
+				first_collider: 0,
+				n_colliders: frozen.colliders.len() as u32,
+			});
 		}
 
-		let min = Self::which_child2(c, p.min);
-		let max = Self::which_child2(c, p.max);
+		while i < queue.len() {
+			let (node_id, bcube) = queue[i];
+			let node_id: usize = node_id.into();
+			let mut ids = vec![];
+			tree::node_colliders(&tree.nodes, node_id, &|_| true,
+				&mut ids);
 
-		if max != min {
-			return None;
-		}
+			let first_collider = if i == 0 {
+				0
+			} else {
+				frozen.colliders.len() as u32
+			};
+			for id in ids {
+				let bbox = tree.stored(id);
+				frozen.colliders.push((bbox, id));
+			}
 
-		let a = Some(match (min[0], min[1], min[2]) {
-			(true,  true,  true)  => 0,
-			(true,  true,  false) => 1,
-			(true,  false, true)  => 2,
-			(true,  false, false) => 3,
-			(false, true,  true)  => 4,
-			(false, true,  false) => 5,
-			(false, false, true)  => 6,
-			(false, false, false) => 7,
-		});
-
-//		println!("c {:?} min {:?} max {:?} -> {:?}", c, p.min, p.max, a);
-		a
-	}
-
-	/// Calculate the center of a child node
-	fn child_center(ch: usize, c: Vector, h: f32) -> Vector {
-		match ch {
-			0 => Vector::new(c.x - h, c.y - h, c.z - h),
-			1 => Vector::new(c.x - h, c.y - h, c.z + h),
-			2 => Vector::new(c.x - h, c.y + h, c.z - h),
-			3 => Vector::new(c.x - h, c.y + h, c.z + h),
-			4 => Vector::new(c.x + h, c.y - h, c.z - h),
-			5 => Vector::new(c.x + h, c.y - h, c.z + h),
-			6 => Vector::new(c.x + h, c.y + h, c.z - h),
-			7 => Vector::new(c.x + h, c.y + h, c.z + h),
-			a => panic!("ch must be 0-7, not {}", a),
+			let first_child = queue.len() as u32;
+			let mut children: Vec<_> = (0..BRANCHES)
+				.map(|ch| (ch, tree.nodes[node_id].child[ch]))
+				.filter(|(_, j)| j.is_some())
+				.map(|(ch, j)| (j, bcube.child(ch)))
+				.collect();
+			children.sort_by_key(|(_, bc)| {
+				morton_digit(bcube.center, bc.center)
+			});
+			queue.extend(children);
+
+			let bounds = tree.loose_cell(bcube).to_bbox();
+
+			frozen.nodes.push(FrozenNode {
+				bounds: match outside {
+					Some(b) if i == 0 => bounds.union(b),
+					_ => bounds,
+				},
+				first_child,
+				n_children: queue.len() as u32 - first_child,
+				first_collider,
+				n_colliders: frozen.colliders.len() as u32
+					- first_collider,
+			});
+			i += 1;
 		}
-	}
 
-	/// Calculate the bounding box of a child node
-	fn child_bcube(ch: usize, bcube: BCube) -> BCube {
-		assert!(bcube.half_len > 0.1);
-		let half_len = bcube.half_len / 2.0;
-		let center = Node::child_center(ch, bcube.center, half_len);
-		BCube { center: center, half_len: half_len }
+		frozen
 	}
+}
 
-/*	/// Get an array containing the leaf children
-	fn branch_children(&self) -> [u32; 7] {
-		assert!(self.is_leaf());
-
-		[self.child[1], self.child[2], self.child[3], self.child[4],
-			self.child[5], self.child[6]]
-	}*/
+/// Get the Morton digit of a child cell from which side of its parent's
+/// center it's on, with `x` as the most significant bit.
+fn morton_digit<S: Scalar>(parent: Vec3<S>, child: Vec3<S>) -> u8 {
+	(child.x > parent.x) as u8 * 4 + (child.y > parent.y) as u8 * 2
+		+ (child.z > parent.z) as u8
 }
 
-impl<T> Octree<T> where T: Collider {
-	/// Create a new octree
-	pub fn new() -> Octree<T> {
-		let o = Octree {
-			colliders: vec![],
-			collider_garbage: vec![],
-			nodes: vec![],
-			garbage: vec![],
-			bcube: BCube::empty(),
-			root: Id::none(),
-			n_colliders: 0,
-		};
+#[cfg(feature = "rayon")]
+impl<T, S> Octree<T, S>
+	where T: Collider<S> + Sync, S: Scalar + Send + Sync
+{
+	/// Like `query_bbox()`, but searches under each top octant in
+	/// parallel.
+	pub fn par_query_bbox(&self, bbox: BBox<S>) -> Vec<Id> {
+		let (mut found, octants) = self.par_split(
+			|bcube| bbox.collide_bcube(bcube),
+			|other| bbox.collide(other),
+		);
+
+		found.extend(octants.into_par_iter().flat_map_iter(|octant| {
+			self.tree.query_from(vec![octant],
+				|bcube| bbox.collide_bcube(bcube),
+				|other| bbox.collide(other))
+		}).collect::<Vec<Id>>());
 
-		o
+		found
 	}
 
-	/// Clear the octree.
-	pub fn clear(&mut self) {
-		*self = Self::new();
+	/// Like `collision_pairs()`, but finds the pairs for the colliders
+	/// under each top octant in parallel.
+	pub fn par_collision_pairs(&self) -> Vec<(Id, Id)> {
+		let (root, octants) = self.par_split(|_| true, |_| true);
+		let mut pairs: Vec<(Id, Id)> = root.into_par_iter()
+			.flat_map_iter(|id| self.tree.pairs_with(id))
+			.collect();
+
+		pairs.extend(octants.into_par_iter().flat_map_iter(|octant| {
+			self.tree.query_from(vec![octant], |_| true, |_| true)
+				.into_iter()
+				.flat_map(|id| self.tree.pairs_with(id))
+		}).collect::<Vec<(Id, Id)>>());
+
+		pairs
 	}
 
-	/// Add a point in the octree
-	pub fn add(&mut self, point: T) -> Id {
-//		println!("ADD BEGIN");
-		// Add to colliders and get the id.
-		let id = if let Some(id) = self.collider_garbage.pop() {
-			unsafe {
-				::std::ptr::copy_nonoverlapping(&point,
-					&mut self.colliders[{ let id: usize = id.into(); id }], 1);
-			}
-			::std::mem::forget(point); // don't drop it, it's moved!
-			id
-		} else {
-			self.colliders.push(point);
-			Id(self.colliders.len() as u32)
-		};
-
-		// Find position in octree for this new collider.
-		match self.n_colliders {
-			0 => self.add_0(id),
-			_ => self.add_n(id),
+	/// Split a query at the top octants: get the colliders in the root
+	/// (or the overflow list) that pass, and the root's child branches to
+	/// search under.
+	fn par_split<F, G>(&self, node_test: F, collider_test: G)
+		-> (Vec<Id>, Vec<(Id, BCube<S>)>)
+		where F: Fn(BCube<S>) -> bool, G: Fn(BBox<S>) -> bool
+	{
+		let tree = &self.tree;
+		let collider_test = |id| collider_test(tree.stored(id));
+		let mut found: Vec<Id> = tree.overflow.iter().cloned()
+			.filter(|id| collider_test(*id))
+			.collect();
+
+		if tree.root.is_none()
+			|| !node_test(tree.loose_cell(tree.cell))
+		{
+			return (found, vec![]);
 		}
 
-		// Increment number of colliders, and return id
-		self.n_colliders += 1;
+		let root: usize = tree.root.into();
+		tree::node_colliders(&tree.nodes, root, &collider_test,
+			&mut found);
 
-//		println!("ADD END {:?} to {}", id, self);
-//		println!("ADDED {}", {let i:usize = id.into();i});
+		let octants = (0..BRANCHES)
+			.filter(|ch| tree.nodes[root].child[*ch].is_some())
+			.map(|ch| (tree.nodes[root].child[ch],
+				tree.cell.child(ch)))
+			.collect();
 
-		id
+		(found, octants)
 	}
+}
 
-	/// Add a point when empty
-	fn add_0(&mut self, id: Id) {
-		// Number of colliders must be 0
-		assert!(self.n_colliders == 0);
+/// A collider borrowed from an `Octree` with `Octree::get_mut()`.  When it's
+/// dropped, the collider is moved in the octree if its `BBox` changed.
+///
+/// If it can't be moved (see `Octree::try_update()`), dropping it leaves the
+/// collider where it was in the octree, and panics in debug builds; use
+/// `commit()` to handle the error instead.
+pub struct ColliderMut<'a, T, S = f32>
+	where T: Collider<S> + 'a, S: Scalar + 'a
+{
+	octree: &'a mut Octree<T, S>,
+	id: Id,
+}
 
-		// Clear the octree
-		self.nodes.clear();
-		self.garbage.clear();
+impl<'a, T, S> ColliderMut<'a, T, S> where T: Collider<S>, S: Scalar {
+	/// Move the collider in the octree if its `BBox` changed, or get why it
+	/// couldn't be (then it stays where it was, like on drop).
+	pub fn commit(self) -> Result<(), OctreeError> {
+		let result = self.octree.try_update(self.id).map(|_| ());
 
-		// Make the root bcube contain the bbox of this first point.
-		self.bcube = self[id].bbox().into();
+		// Already updated, so don't update again on drop.
+		::std::mem::forget(self);
+		result
+	}
+}
 
-//		println!("ADD_0 {:?} / {:?}", self.bcube, self[id].bbox());
+impl<'a, T, S> ops::Deref for ColliderMut<'a, T, S>
+	where T: Collider<S>, S: Scalar
+{
+	type Target = T;
 
-		// Build the branch and add a collider.
-		let i = self.new_branch();
-		self.nodes[{ let i: usize = i.into(); i }].branch_add_collider(id).unwrap();
+	fn deref(&self) -> &T {
+		&self.octree[self.id]
+	}
+}
 
-		// Set this branch as the root node.
-		self.root = i;
+impl<'a, T, S> ops::DerefMut for ColliderMut<'a, T, S>
+	where T: Collider<S>, S: Scalar
+{
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.octree[self.id]
 	}
+}
 
-	/// Add a point when not empty
-	fn add_n(&mut self, id: Id) {
-		// Must have colliders already in the octree.
-		assert!(self.n_colliders > 0);
-		// Get BBox
-		let bbox = self[id].bbox();
+impl<'a, T, S> Drop for ColliderMut<'a, T, S>
+	where T: Collider<S>, S: Scalar
+{
+	fn drop(&mut self) {
+		// If it can't be moved, it stays where it was (see `commit()`).
+		let result = self.octree.try_update(self.id);
 
-		// While the bbox isn't within the root bcube, expand root bcube
-		while !bbox.collide_bcube(self.bcube) {
-			self.grow_root(bbox);
-//			println!("GROW {:?}", self.bcube);
-		}
+		debug_assert!(result.is_ok() || ::std::thread::panicking(),
+			"can't move collider {:?}: {} (use `commit()`)", self.id,
+			result.unwrap_err());
+	}
+}
 
-		// Add id inside the root bcube.
-		let bcube = self.bcube;
-		let root = self.root;
-		self.add_inside(id, root, bcube);
-
-//		println!("{}", self);
-	}
-
-	/// Grow the root node
-	fn grow_root(&mut self, bbox: BBox) {
-		// BBox can't collide with bcube when this function is called.
-		assert!(!bbox.collide_bcube(self.bcube));
-		assert!(self.nodes[{ let a: usize = self.root.into(); a }].is_branch());
-
-		// Get the old bcube center, to see which octant it goes in.
-		let old_bc = self.bcube;
-
-		// Extend bcube to attempt to accomodate for bbox.
-		// This function is limited to growing twice in size.
-		self.bcube.extend(bbox);
-
-		// Create new container branch for old root branch.
-		let ch = Node::which_child_bbox(self.bcube.center, old_bc.to_bbox()).unwrap();
-		let id = self.new_branch();
-		self.nodes[{ let a: usize = id.into(); a }].child[ch] = self.root;
-		self.root = id;
-
-//		println!("Extended: {}", self);
-	}
-
-	/// Add a point within the bounds
-	fn add_inside(&mut self, id: Id, node_id: Id, bcube: BCube) {
-		// Calculate bbox for this id.
-		let bbox = self[id].bbox();
-		// Convert node_id to usize for indexing.
-		let node_id: usize = node_id.into();
-
-		// BBox must collide with bcube when this function is called
-		assert!(bbox.collide_bcube(bcube));
-		// Must be a branch
-		assert!(self.nodes[node_id].is_branch());
-
-		// Attempt to add at root first.  Test is full
-		if self.nodes[node_id].branch_add_collider(id).is_none() {
-			// Attempt to push relative root colliders down the tree
-			for i in 8..=14 {
-				let collider = self.nodes[node_id].child[i];
-				if self.add_down(collider, node_id, bcube) {
-					// If it successfully pushed it the
-					// collider down the octree, remove it
-					// from it's old location.
-					self.nodes[node_id].child[i]
-						= Id::none();
-				}
-			}
+/// An error from a fallible `Octree` (or `Quadtree`) operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OctreeError {
+	/// The `BBox` isn't finite (or is too far away to grow the root around,
+	/// if the bounds aren't fixed), or its minimum is greater than its
+	/// maximum.
+	InvalidBounds,
+	/// The `Id` isn't in the octree.  A removed collider's `Id` is only
+	/// detected until it's reused by a collider added after.
+	StaleId,
+	/// There are no `Id`s left for colliders or nodes.
+	CapacityExhausted,
+	/// The collider wasn't in the tree where its stored `BBox` says it
+	/// should be, or couldn't be put there, which is a bug in the octree.
+	/// It's left in the octree (in the overflow list, if it was being
+	/// put there).
+	Corrupt,
+}
 
-			// Attempt to push this collider (id) down the tree
-			if self.add_down(id, node_id, bcube) {
-				return;
+impl fmt::Display for OctreeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			OctreeError::InvalidBounds => write!(f, "invalid bbox"),
+			OctreeError::StaleId => write!(f, "no such collider"),
+			OctreeError::CapacityExhausted => {
+				write!(f, "octree is out of ids")
 			}
-
-			// Try again, this time link if failed.
-			if self.nodes[node_id].branch_add_collider(id)
-				.is_none() // Is full, still!
-			{
-				let link_id = self.new_leaf();
-				self.nodes[node_id].child[LINK]
-					= link_id;
+			OctreeError::Corrupt => {
+				write!(f, "collider is missing from the octree")
 			}
 		}
 	}
+}
 
-	/// Move a collider down the tree, return true if it worked.
-	fn add_down(&mut self, id: Id, node_id: usize, bcube: BCube) -> bool {
-		// Calculate bbox for this id.
-		let bbox = self[id].bbox();
+impl ::std::error::Error for OctreeError {}
 
-		// can be put on a lower level.
-		if let Some(ch) = Node::which_child_bbox(bcube.center, bbox) {
-			let j = self.nodes[node_id].child[ch];
-			let bc = Node::child_bcube(ch, bcube);
+impl<T, S> ::std::ops::Index<Id> for Octree<T, S>
+	where T: Collider<S>, S: Scalar
+{
+	type Output = T;
 
-			if j.is_some() {
-				// already a branch here, add collider to it.
-				self.add_inside(id, j, bc);
-			} else {
-				// make a branch
-				let k = self.new_branch();
-				// set branch as the correct child
-				self.nodes[node_id].child[ch] = k;
-				// Add the collider
-				self.nodes[{ let k: usize = k.into(); k }]
-					.branch_add_collider(id)
-					.unwrap(); // shouldn't fail.
-			}
-			true
-		} else {
-			false
-		}
+	fn index(&self, index: Id) -> &T {
+		let index: usize = index.into();
+		self.colliders[index].as_ref().expect("no such collider")
 	}
+}
 
-	/// Add a new node
-	fn new_node(&mut self, n: Node) -> Id {
-		if let Some(i) = self.garbage.pop() {
-			let k: usize = i.into();
-			self.nodes[k] = n;
-			k.into()
-		} else {
-			self.nodes.push(n);
-			Id(self.nodes.len() as u32)
-		}
+/// Changing a collider's `BBox` this way doesn't move it in the octree; use
+/// `Octree::get_mut()` instead, or call `Octree::update()` after.
+impl<T, S> ::std::ops::IndexMut<Id> for Octree<T, S>
+	where T: Collider<S>, S: Scalar
+{
+	fn index_mut(&mut self, index: Id) -> &mut T {
+		let index: usize = index.into();
+		self.colliders[index].as_mut().expect("no such collider")
 	}
+}
 
-	/// Add a new leaf node
-	fn new_leaf(&mut self) -> Id {
-		self.new_node(Node::new_leaf())
+impl<T, S> fmt::Display for Octree<T, S>
+	where T: Collider<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.tree, f)
 	}
+}
 
-	/// Add a new branch node
-	fn new_branch(&mut self) -> Id {
-		self.new_node(Node::new_branch())
+impl<T, S> fmt::Debug for Octree<T, S>
+	where T: Collider<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.tree, f)
 	}
+}
 
-	/// Remove a point from the octree
-	pub fn remove(&mut self, id: Id) -> T {
-//		println!("REMOVE {}", {let i:usize = id.into();i});
-//		println!("REMOVE BEGIN {} from {}", { let a: usize = id.into(); a }, self);
-
-		// Must have colliders already in the octree.
-		assert!(self.n_colliders > 0);
-		// 
-		let bcube = self.bcube;
-		let root = self.root;
-		// Find and remove the collider Id from the octree.
-		let clear = self.remove_inside(id, root, bcube).is_some();
-		// Id is garbage now.
-		self.collider_garbage.push(id);
-		// Shrink root if: 1 branch, no nodes
-		loop {
-			let root: usize = self.root.into();
-			if let Some(ch) = self.nodes[root].branch_is_one() {
-				// Add root to garbage.
-				self.garbage.push(self.root);
-				// Set new root
-				self.root = self.nodes[root].child[ch];
-				//
-				self.bcube = Node::child_bcube(ch, self.bcube);
-			} else {
-				break;
-			}
-		}
-		// Decrement number of colliders
-		self.n_colliders -= 1;
+#[cfg(test)]
+pub(crate) struct TestCollider<S = f32>(pub(crate) BBox<S>);
+
+#[cfg(test)]
+impl<S: Scalar> Collider<S> for TestCollider<S> {
+	fn bbox(&self) -> BBox<S> {
+		self.0
+	}
+}
 
-		// Return the memory by copy.
-		let mut ret = unsafe { ::std::mem::uninitialized() };
+/// Add 500 pseudo-random boxes; every 8th is big, so straddles centers.
+#[cfg(test)]
+pub(crate) fn add_test_boxes(octree: &mut Octree<TestCollider>)
+	-> Vec<(Id, BBox)>
+{
+	let mut seed = 7u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % 10_000) as f32 / 100.0 - 50.0
+	};
+
+	(0..500).map(|i| {
+		let min = vector!(random(), random(), random());
+		let size = if i % 8 == 0 { 30.0 } else { 1.0 };
+		let bbox = BBox::new(min, min + vector!(size));
+
+		(octree.add(TestCollider(bbox)), bbox)
+	}).collect()
+}
 
-		unsafe {
-			::std::ptr::copy_nonoverlapping(
-				&self.colliders[{ let id: usize = id.into(); id }], &mut ret, 1);
-		}
+/// Check `query_bbox()` against testing every box.
+#[cfg(test)]
+fn check_query(octree: &Octree<TestCollider>, boxes: &[(Id, BBox)]) {
+	for i in 0..20 {
+		let c = vector!(i as f32 * 5.0 - 50.0);
+		let q = BBox::new(c, c + vector!(10.0, 20.0, 15.0));
+		let mut found = octree.query_bbox(q);
+		let mut expect: Vec<Id> = boxes.iter()
+			.filter(|(_, b)| b.collide(q))
+			.map(|(id, _)| *id)
+			.collect();
+		found.sort_by_key(|id| id.0);
+		expect.sort_by_key(|id| id.0);
+		assert_eq!(found, expect);
+	}
+}
 
-		if clear {
-			assert_eq!(self.n_colliders, 0);
-			self.clear();
+#[test]
+fn test_octree_query() {
+	let mut octree = Octree::new();
+	let boxes = add_test_boxes(&mut octree);
+	assert_eq!(octree.len(), 500);
+
+	check_query(&octree, &boxes);
+
+	// Remove every other one.
+	let mut kept = vec![];
+	for (i, (id, bbox)) in boxes.into_iter().enumerate() {
+		if i % 2 == 0 {
+			octree.remove(id);
+		} else {
+			kept.push((id, bbox));
 		}
+	}
+	assert_eq!(octree.len(), 250);
+	check_query(&octree, &kept);
+
+	let hits = octree.query_ray(vector!(-100.0, 0.5, 0.5),
+		vector!(1.0, 0.0, 0.0));
+	let expect = kept.iter()
+		.filter(|(_, b)| b.intersect_ray(vector!(-100.0, 0.5, 0.5),
+			vector!(1.0, 0.0, 0.0)).is_some())
+		.count();
+	assert_eq!(hits.len(), expect);
+
+	for (id, _) in kept {
+		octree.remove(id);
+	}
+	assert!(octree.is_empty());
+}
 
-//		println!("REMOVED {}", self);
+#[test]
+fn test_octree_f64() {
+	// Small boxes far from the origin, which f32 can't tell apart.
+	let mut octree: Octree<TestCollider<f64>, f64> = Octree::new();
+	let base = DVector::new(1.0e8, -1.0e8, 1.0e8);
+	let mut ids = vec![];
+	for i in 0..50 {
+		let min = base + DVector::new(f64::from(i) * 0.5, 0.0, 0.0);
+		let bbox = BBox::new(min, min + DVector::from(0.25));
+		ids.push(octree.add(TestCollider(bbox)));
+	}
 
-//		println!("REMOVE END");
+	for (i, id) in ids.iter().enumerate() {
+		let p = base + DVector::new(i as f64 * 0.5 + 0.1, 0.1, 0.1);
+		assert_eq!(octree.query_bbox(BBox::new(p, p)), vec![*id]);
+	}
+	assert_eq!(octree.query_ray(base + DVector::new(-1.0, 0.1, 0.1),
+		DVector::new(1.0, 0.0, 0.0)).len(), 50);
+}
 
-		ret
+#[test]
+fn test_octree_loose() {
+	let links = |octree: &Octree<TestCollider>| {
+		(0..octree.tree.nodes.len())
+			.filter(|i| !octree.tree.is_garbage[*i])
+			.filter(|i| octree.tree.nodes[*i].is_leaf())
+			.count()
+	};
+	let mut strict = Octree::new();
+	add_test_boxes(&mut strict);
+	let mut octree = Octree::loose(2.0);
+	let boxes = add_test_boxes(&mut octree);
+	assert_eq!(octree.looseness(), 2.0);
+
+	// Big colliders sink below the root instead of linking.
+	assert!(links(&octree) < links(&strict));
+	check_query(&octree, &boxes);
+
+	let hits = octree.query_ray(vector!(-100.0, 0.5, 0.5),
+		vector!(1.0, 0.0, 0.0));
+	let expect = boxes.iter()
+		.filter(|(_, b)| b.intersect_ray(vector!(-100.0, 0.5, 0.5),
+			vector!(1.0, 0.0, 0.0)).is_some())
+		.count();
+	assert_eq!(hits.len(), expect);
+
+	for (id, _) in boxes {
+		octree.remove(id);
 	}
+	assert!(octree.is_empty());
+	octree.clear();
+	assert_eq!(octree.looseness(), 2.0);
+}
 
-	/// Remove an Id from the octree.
-	fn remove_inside(&mut self, id: Id, node_id: Id, bcube: BCube)
-		-> Option<Id>
-	{
-		// Calculate bbox for this id.
-		let bbox = self[id].bbox();
-		// Get node_id as usize
-		let node_id: usize = node_id.into();
-
-		// BBox must collide with bcube when this function is called
-		assert!(bbox.collide_bcube(bcube));
-		// Must be a branch
-		assert!(self.nodes[node_id].is_branch());
-
-		// Could be found on a lower level.
-//		println!("R-INSIDE {:?} / {:?}", bcube, bbox);
-		if let Some(ch) = Node::which_child_bbox(bcube.center, bbox) {
-//			println!("WCHBBR {}", ch);
-			let j = self.nodes[node_id].child[ch];
-
-			if j.is_some() {
-//				println!("SOM");
-
-				// Yes, there is a branch here, where the Id is!
-				// Remove it from inside this branch.
-				let bcube = Node::child_bcube(ch, bcube);
-
-				if let Some(rm)
-					= self.remove_inside(id, j, bcube)
-				{ // Remove empty branch
-					// Child branch should be the one
-					// removed
-					assert_eq!(j, rm);
-					// Add to garbage.
-					self.garbage.push(rm);
-					// Remove child branch.
-					self.nodes[node_id].child[ch] =
-						Id::none();
-				}
-
-				// If the node is empty, mark for removal.
-				if self.nodes[node_id].is_empty() {
-					Some(node_id.into())
-				} else {
-					None // nothing to be removed.
-				}
-			} else {
-//				println!("SON");
-				// No, we don't have to descend - it's here!
-				self.remove_from_branch(id, node_id)
+#[test]
+fn test_octree_threads() {
+	let mut octree = Octree::loose(2.0);
+	let boxes = add_test_boxes(&mut octree);
+
+	let mut pairs = octree.collision_pairs();
+	let mut expect = vec![];
+	for (i, (a, bbox)) in boxes.iter().enumerate() {
+		for (b, other) in &boxes[i + 1..] {
+			if bbox.collide(*other) {
+				expect.push((*a, *b));
 			}
-		} else {
-//			println!("NON");
-			// No, we can't descend - it's here!
-			self.remove_from_branch(id, node_id)
 		}
 	}
+	pairs.sort_by_key(|(a, b)| (a.0, b.0));
+	assert_eq!(pairs, expect);
+
+	// Query from many threads at once.
+	let octree = &octree;
+	::std::thread::scope(|scope| {
+		for _ in 0..4 {
+			scope.spawn(|| check_query(octree, &boxes));
+		}
+	});
+}
 
-	/// Remove from branch, including any links that may exist.
-	fn remove_from_branch(&mut self, id: Id, node_id: usize) -> Option<Id> {
-//		println!("RFB {} {}", node_id, {let a:usize=id.into();a});
+#[cfg(feature = "rayon")]
+#[test]
+fn test_octree_par() {
+	let mut octree = Octree::new();
+	add_test_boxes(&mut octree);
+
+	let q = BBox::new(vector!(-20.0), vector!(10.0, 0.0, 25.0));
+	let mut found = octree.par_query_bbox(q);
+	let mut expect = octree.query_bbox(q);
+	found.sort_by_key(|id| id.0);
+	expect.sort_by_key(|id| id.0);
+	assert_eq!(found, expect);
+
+	let mut pairs = octree.par_collision_pairs();
+	let mut expect = octree.collision_pairs();
+	pairs.sort_by_key(|(a, b)| (a.0, b.0));
+	expect.sort_by_key(|(a, b)| (a.0, b.0));
+	assert_eq!(pairs, expect);
+	assert!(!pairs.is_empty());
+}
 
-		// Remove the collider
-		if self.nodes[node_id].remove_collider(id)
-			.is_some() // Found and removed
-		{
-			// If the node is empty, mark for removal.
-			if self.nodes[node_id].is_empty() {
-				return Some(node_id.into());
-			} else {
-				return None;
-			}
+#[test]
+fn test_octree_compact() {
+	let mut octree = Octree::new();
+	let boxes = add_test_boxes(&mut octree);
+	let n_nodes = octree.tree.nodes.len();
+
+	// Keep every 5th.
+	let mut kept = vec![];
+	for (i, (id, bbox)) in boxes.into_iter().enumerate() {
+		if i % 5 == 0 {
+			kept.push((id, bbox));
+		} else {
+			octree.remove(id);
+		}
+	}
+	assert_eq!(octree.to_string().lines().count(),
+		(octree.tree.nodes.len() - octree.tree.garbage.len()) * 2 + 1);
+
+	let moved = octree.compact();
+	assert!(octree.tree.nodes.len() < n_nodes);
+	assert!(octree.tree.garbage.is_empty());
+	assert!(octree.tree.collider_garbage.is_empty());
+	assert_eq!(octree.colliders.len(), 100);
+	for (id, _) in kept.iter_mut() {
+		if let Some(&(_, new)) = moved.iter().find(|m| m.0 == *id) {
+			*id = new;
 		}
+	}
+	check_query(&octree, &kept);
+
+	// Still works after.
+	octree.reserve(10);
+	assert!(octree.colliders.capacity() >= octree.colliders.len() + 10);
+	let is_garbage = &octree.tree.is_garbage;
+	assert!(is_garbage.capacity() >= is_garbage.len() + 10);
+	octree.shrink_to_fit();
+	let bbox = BBox::new(vector!(60.0), vector!(61.0));
+	let id = octree.add(TestCollider(bbox));
+	kept.push((id, bbox));
+	octree.remove(kept[0].0);
+	check_query(&octree, &kept[1..]);
+}
+
+#[test]
+fn test_octree_stored_bbox() {
+	use std::cell::Cell;
+	use std::rc::Rc;
 
-		// Couldn't Find it: Search Link Node
-		let node_id = self.nodes[node_id].link()
-			.unwrap(); // Shouldn't fail if not found yet.
-		let rm = self.remove_from_branch(id, node_id);
-
-		// If link leaf is now empty, remove.
-		if let Some(rm) = rm {
-			// Returned location should match LINK node location.
-			assert_eq!(rm, self.nodes[node_id].child[LINK]);
-			// Add to garbage.
-			self.garbage.push(rm);
-			// Remove Link.
-			self.nodes[node_id].child[LINK] = Id::none();
+	// Counts calls to `bbox()`.
+	struct Counted(BBox, Rc<Cell<u32>>);
+
+	impl Collider for Counted {
+		fn bbox(&self) -> BBox {
+			self.1.set(self.1.get() + 1);
+			self.0
 		}
+	}
 
-		None // Don't remove this node
+	let calls = Rc::new(Cell::new(0));
+	let mut octree = Octree::new();
+	let ids: Vec<Id> = (0..100).map(|i| {
+		let min = vector!(i as f32, 0.0, 0.0);
+		let bbox = BBox::new(min, min + vector!(0.5));
+		octree.add(Counted(bbox, calls.clone()))
+	}).collect();
+	let _ = octree.to_string();
+	let q = BBox::new(vector!(9.8, 0.0, 0.0), vector!(10.2, 0.0, 0.0));
+	assert_eq!(octree.query_bbox(q), vec![ids[10]]);
+	assert_eq!(calls.get(), 100);
+
+	// Changing a collider doesn't move it, and it can still be removed.
+	octree[ids[10]].0 = BBox::new(vector!(500.0), vector!(501.0));
+	assert_eq!(octree.stored_bbox(ids[10]).unwrap().center(),
+		vector!(10.25, 0.25, 0.25));
+	assert_eq!(octree.query_bbox(q), vec![ids[10]]);
+	for id in ids.iter() {
+		octree.remove(*id);
 	}
+	assert!(octree.is_empty());
+	assert_eq!(calls.get(), 100);
+
+	// Removed and unknown `Id`s have none.
+	assert!(octree.stored_bbox(ids[10]).is_none());
+	assert!(octree.stored_bbox(Id(1_000)).is_none());
+	assert!(octree.stored_bbox(Id::none()).is_none());
 }
 
-impl<T> ::std::ops::Index<Id> for Octree<T> where T: Collider {
-	type Output = T;
+#[test]
+fn test_octree_get_mut() {
+	let mut octree = Octree::new();
+	let mut boxes = add_test_boxes(&mut octree);
+	let moved = BBox::new(vector!(20.0), vector!(21.0));
+
+	// Moved when the guard is dropped.
+	let (id, _) = boxes[3];
+	octree.get_mut(id).unwrap().0 = moved;
+	boxes[3].1 = moved;
+	assert_eq!(octree.stored_bbox(id).unwrap().center(), moved.center());
+	check_query(&octree, &boxes);
+
+	// Moved on `commit()`.
+	let (id, _) = boxes[2];
+	let mut collider = octree.get_mut(id).unwrap();
+	collider.0 = moved;
+	assert_eq!(collider.commit(), Ok(()));
+	boxes[2].1 = moved;
+	assert_eq!(octree.stored_bbox(id).unwrap().center(), moved.center());
+	check_query(&octree, &boxes);
+
+	// Moved on `update()`.
+	let (id, _) = boxes[4];
+	octree.get_mut_untracked(id).unwrap().0 = moved;
+	boxes[4].1 = moved;
+	assert!(octree.update(id));
+	assert!(!octree.update(id));
+	check_query(&octree, &boxes);
+
+	// Changed without `update()`, but still removes.
+	let (id, _) = boxes.remove(5);
+	octree[id].0 = moved;
+	octree.remove(id);
+	check_query(&octree, &boxes);
+	assert!(octree.get(id).is_none() && octree.get_mut(id).is_none());
+
+	// Moving the only collider.
+	let mut octree = Octree::new();
+	let id = octree.add(TestCollider(moved));
+	octree.get_mut(id).unwrap().0 = BBox::new(vector!(-5.0), vector!(-4.0));
+	assert_eq!(octree.query_bbox(BBox::new(vector!(-4.5), vector!(-4.5))),
+		vec![id]);
+	assert!(octree.query_bbox(moved).is_empty());
+}
 
-	fn index<'a>(&'a self, index: Id) -> &'a T {
-		let index: usize = index.into();
-		&self.colliders[index]
+#[test]
+fn test_octree_errors() {
+	let mut octree = Octree::new();
+	let boxes = add_test_boxes(&mut octree);
+	let len = octree.len();
+	let infinite = BBox::new(vector!(0.0), vector!(f32::INFINITY));
+	let too_far = BBox::new(vector!(0.0), vector!(f32::MAX));
+
+	// Invalid bounds aren't added.
+	assert_eq!(octree.try_add(TestCollider(infinite)).err(),
+		Some(OctreeError::InvalidBounds));
+	assert_eq!(octree.try_add(TestCollider(too_far)).err(),
+		Some(OctreeError::InvalidBounds));
+	assert_eq!(octree.len(), len);
+	check_query(&octree, &boxes);
+
+	// Stale ids.
+	let (id, _) = boxes[0];
+	assert!(octree.try_remove(id).is_ok());
+	assert_eq!(octree.try_remove(id).err(), Some(OctreeError::StaleId));
+	assert_eq!(octree.try_update(id), Err(OctreeError::StaleId));
+	assert_eq!(OctreeError::StaleId.to_string(), "no such collider");
+
+	// An invalid update leaves the collider where it was.
+	let (id, bbox) = boxes[1];
+	octree.get_mut_untracked(id).unwrap().0 = infinite;
+	assert_eq!(octree.try_update(id), Err(OctreeError::InvalidBounds));
+	assert!(octree.query_bbox(bbox).contains(&id));
+	let mut collider = octree.get_mut(id).unwrap();
+	collider.0 = too_far;
+	assert_eq!(collider.commit(), Err(OctreeError::InvalidBounds));
+	assert!(octree.query_bbox(bbox).contains(&id));
+	if cfg!(debug_assertions) {
+		// Dropping the guard instead panics, after leaving it there.
+		let dropped = ::std::panic::catch_unwind(
+			::std::panic::AssertUnwindSafe(|| {
+				octree.get_mut(id).unwrap().0 = infinite;
+			}));
+		assert!(dropped.is_err());
+		assert!(octree.query_bbox(bbox).contains(&id));
 	}
+	octree[id].0 = bbox;
+	assert_eq!(octree.try_update(id), Ok(false));
+	check_query(&octree, &boxes[1..]);
+
+	// A reused `Id` isn't stale.
+	let (id, _) = boxes[0];
+	let reused = octree.add(TestCollider(bbox));
+	assert_eq!(reused, id);
+	assert!(octree.get(id).is_some());
+
+	// Not where its stored `BBox` says; an error, not a panic.
+	let (id, bbox) = boxes[2];
+	octree.tree.stored[usize::from(id)] = Some(bbox + vector!(30.0));
+	assert_eq!(octree.try_remove(id).err(), Some(OctreeError::Corrupt));
+	assert_eq!(octree.try_update(id), Err(OctreeError::Corrupt));
+	assert_eq!(octree.len(), len);
+	octree.tree.stored[usize::from(id)] = Some(bbox);
+	assert!(octree.try_remove(id).is_ok());
 }
 
-impl<T> ::std::ops::IndexMut<Id> for Octree<T> where T: Collider {
-	fn index_mut<'a>(&'a mut self, index: Id) -> &'a mut T {
-		let index: usize = index.into();
-		&mut self.colliders[index]
+#[test]
+fn test_octree_rebalance() {
+	let offset = vector!(1000.0, -2000.0, 500.0);
+	let mut octree = Octree::new();
+	let mut boxes = add_test_boxes(&mut octree);
+
+	// Move everything far away; the root is left covering both places.
+	for (id, bbox) in &mut boxes {
+		*bbox = *bbox + offset;
+		octree.get_mut(*id).unwrap().0 = *bbox;
 	}
+	let before = octree.tree.cell.half_len;
+	octree.rebalance();
+	assert!(octree.tree.cell.half_len < before);
+	assert!(octree.tree.cell.half_len <= 256.0);
+	let hits = octree.query_bbox(BBox::new(offset, offset));
+	assert!(hits.iter().all(|id| octree.stored_bbox(*id).unwrap()
+		.collide_vec3(offset)));
+	assert!(octree.query_bbox(BBox::new(vector!(0.0), vector!(1.0)))
+		.is_empty());
+
+	// Outliers go in the overflow list instead of growing the root.
+	let mut octree = Octree::loose(2.0);
+	octree.set_max_root(Some(256.0));
+	let mut boxes = add_test_boxes(&mut octree);
+	let far = BBox::new(vector!(1.0e6), vector!(1.0e6 + 1.0));
+	let outlier = octree.add(TestCollider(far));
+	let twin = octree.add(TestCollider(far));
+	assert_eq!(octree.overflow(), &[outlier, twin][..]);
+	assert!(octree.tree.cell.half_len <= 256.0);
+	boxes.push((outlier, far));
+	boxes.push((twin, far));
+	check_query(&octree, &boxes);
+	let mut found = octree.query_bbox(far);
+	found.sort_by_key(|id| id.0);
+	assert_eq!(found, vec![outlier, twin]);
+	assert!(octree.collision_pairs().contains(&(outlier, twin)));
+	assert_eq!(octree.freeze().query_bbox(far).len(), 2);
+	assert_eq!(octree.snapshot().query_bbox(far).len(), 2);
+
+	// Removing from the overflow list, and compacting.
+	let (last, _) = boxes[499];
+	octree.remove(last);
+	octree.remove(outlier);
+	assert_eq!(octree.compact(), vec![(twin, last)]);
+	assert_eq!(octree.overflow(), &[last][..]);
+	assert_eq!(octree.query_bbox(far).len(), 1);
+
+	// Without a limit, a rebalance takes the overflow back in.
+	octree.set_max_root(None);
+	octree.rebalance();
+	assert!(octree.overflow().is_empty());
+	assert_eq!(octree.len(), 500);
+	assert_eq!(octree.query_bbox(far).len(), 1);
 }
 
-impl<T> fmt::Display for Octree<T> where T: Collider {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		if self.root.is_none() {
-			write!(f, "No Root\n")?;
-		} else {
-			let root: usize = self.root.into();
-			write!(f, "Root {}:{:?}\n", root, self.bcube)?;
-		}
-
-		for i in 0..self.nodes.len() {
-			let id: Id = i.into();
-			if !self.garbage.contains(&id) {
-				writeln!(f, "{}: {}", i, self.nodes[i])?;
-				write!(f, "{}: ", i)?;
-				for j in 8..=14 { // 8
-					let index = self.nodes[i].child[j];
-					if index.is_some() {
-						write!(f, "{:?},", self[index].bbox())?;
-					}
-				}
-				writeln!(f, "")?;
-			}
-		}
+#[test]
+fn test_octree_bounds() {
+	let bounds = BCube::from(BBox::new(vector!(-64.0), vector!(64.0)));
+	let mut octree = Octree::with_bounds(bounds);
+	let mut boxes = add_test_boxes(&mut octree);
 
-		write!(f, "")
+	// Boxes reaching past 64 are outside.
+	for (id, bbox) in &boxes {
+		assert_eq!(octree.overflow().contains(id),
+			!bbox.within_bcube(bounds));
+	}
+	assert!(!octree.overflow().is_empty());
+	check_query(&octree, &boxes);
+
+	// A stray collider doesn't grow the root.
+	let stray = BBox::new(vector!(1.0e30), vector!(1.0e30));
+	let id = octree.add(TestCollider(stray));
+	assert!(octree.tree.cell == bounds);
+	assert_eq!(octree.query_bbox(stray), vec![id]);
+	boxes.push((id, stray));
+
+	// Even one too far away to grow a root around.
+	let huge = BBox::new(vector!(-1.0e38), vector!(f32::MAX));
+	let id = octree.try_add(TestCollider(huge)).unwrap();
+	assert!(octree.tree.cell == bounds);
+	assert!(octree.overflow().contains(&id));
+	assert!(octree.query_bbox(stray).contains(&id));
+	boxes.push((id, huge));
+	let infinite = BBox::new(vector!(0.0), vector!(f32::INFINITY));
+	assert_eq!(octree.try_add(TestCollider(infinite)).err(),
+		Some(OctreeError::InvalidBounds));
+
+	// Moving in and out of the bounds.
+	let (id, bbox) = boxes[1];
+	octree.get_mut(id).unwrap().0 = bbox + vector!(200.0);
+	assert!(octree.overflow().contains(&id));
+	octree.get_mut(id).unwrap().0 = bbox;
+	assert!(!octree.overflow().contains(&id));
+
+	// The root doesn't shrink, or get fitted to the colliders.
+	for (id, _) in boxes.drain(1..) {
+		octree.remove(id);
 	}
+	octree.rebalance();
+	assert!(octree.tree.cell == bounds);
+	check_query(&octree, &boxes);
+	octree.clear();
+	assert_eq!(octree.bounds(), Some(bounds));
+	let id = octree.add(TestCollider(BBox::new(vector!(1.0), vector!(2.0))));
+	assert!(octree.tree.cell == bounds);
+	assert_eq!(octree.query_bbox(BBox::new(vector!(0.0), vector!(1.5))),
+		vec![id]);
 }
 
-impl<T> fmt::Debug for Octree<T> where T: Collider {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		if self.root.is_none() {
-			write!(f, "No Root\n")?;
-		} else {
-			let root: usize = self.root.into();
-			write!(f, "root {}\n", root)?;
+#[test]
+fn test_octree_center_ties() {
+	let at = |a: [f32; 3], b: [f32; 3]| BBox::new(
+		vector!(a[0], a[1], a[2]), vector!(b[0], b[1], b[2]));
+	// Flat on the greater faces of the root before it grows, so then on
+	// the center plane of the grown root.
+	let boxes = [
+		at([1.0, -0.5, -3.5], [1.5, -0.25, -3.5]),
+		at([3.5, -0.5, -2.5], [3.5, -0.5, -2.5]),
+		at([-1.0, 3.5, -2.5], [0.0, 4.0, -2.5]),
+		at([3.0, 3.0, 0.0], [3.5, 3.25, 0.0]),
+		at([1.5, -2.5, 0.5], [1.5, -2.5, 0.5]),
+	];
+
+	for &looseness in &[1.0, 1.5, 2.0] {
+		let mut octree = Octree::loose(looseness);
+		let ids: Vec<Id> = boxes.iter()
+			.map(|bbox| octree.add(TestCollider(*bbox)))
+			.collect();
+		for (id, bbox) in ids.iter().zip(boxes.iter()) {
+			assert!(octree.query_bbox(*bbox).contains(id));
+		}
+		for id in ids {
+			octree.remove(id);
 		}
+		assert!(octree.is_empty());
+	}
 
-		for i in 0..self.nodes.len() {
-			let id: Id = i.into();
-			if !self.garbage.contains(&id) {
-				write!(f, "{}: {:?}\n", i, self.nodes[i])?;
+	// Random boxes on a grid of center planes.
+	let mut seed = 3u32;
+	let mut random = move |n: u32| {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % n) as f32
+	};
+
+	for &looseness in &[1.0, 1.5, 2.0] {
+		let mut octree = Octree::loose(looseness);
+		let mut boxes: Vec<(Id, BBox)> = vec![];
+
+		for i in 0..2000 {
+			if i % 3 == 2 {
+				let i = random(boxes.len() as u32) as usize;
+				octree.remove(boxes.swap_remove(i).0);
+			} else {
+				let mut grid = |n, step| random(n) * step;
+				let min = vector!(grid(33, 0.5), grid(33, 0.5),
+					grid(33, 0.5)) - vector!(8.0);
+				let size = vector!(grid(4, 0.25), grid(4, 0.25),
+					grid(4, 0.25));
+				let bbox = BBox::new(min, min + size);
+				let id = octree.add(TestCollider(bbox));
+				boxes.push((id, bbox));
+			}
+			if i % 500 == 0 {
+				octree.rebalance();
 			}
 		}
-
-		write!(f, "")
+		for (id, bbox) in &boxes {
+			assert!(octree.query_bbox(*bbox).contains(id));
+		}
+		for (id, _) in boxes {
+			octree.remove(id);
+		}
 	}
 }
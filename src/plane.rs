@@ -6,8 +6,31 @@
 
 use std::fmt;
 
-use Vector;
-use BCube;
+use *;
+
+/// Which side of a `Plane` a shape is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+	/// Entirely on the side the plane faces.
+	Front,
+	/// Entirely behind the plane.
+	Back,
+	/// On both sides of the plane.
+	Straddling,
+}
+
+impl Side {
+	/// Get the side from the minimum and maximum signed distances.
+	fn from_range(min: f32, max: f32) -> Side {
+		if min >= 0.0 {
+			Side::Front
+		} else if max <= 0.0 {
+			Side::Back
+		} else {
+			Side::Straddling
+		}
+	}
+}
 
 #[derive(Clone, Copy, PartialEq)]
 /// Single-precision plane
@@ -30,125 +53,286 @@ impl Plane {
 		Plane { facing: dir.normalize(), offset: ofs }
 	}
 
+	/// Create a new plane through 3 points.  The plane faces the side the
+	/// points wind counter-clockwise on.  Returns `None` if the points are
+	/// on a line.
+	pub fn from_points(a: Vector, b: Vector, c: Vector) -> Option<Plane> {
+		let normal = (b - a).cross(c - a);
+
+		if normal.squared_length() <= f32::EPSILON * f32::EPSILON {
+			return None;
+		}
+
+		Some(Plane::from_point_normal(a, normal))
+	}
+
+	/// Create a new plane through `point`, facing the direction of `normal`.
+	pub fn from_point_normal(point: Vector, normal: Vector) -> Plane {
+		let facing = normal.normalize();
+
+		Plane { facing, offset: facing.dot(point) }
+	}
+
+	/// Get the signed distance from the plane to point `p`; positive in
+	/// front of the plane, and negative behind it.
+	pub fn signed_distance(&self, p: Vector) -> f32 {
+		self.facing.dot(p) - self.offset
+	}
+
+	/// Get the point on the plane closest to `p`.
+	pub fn project_point(&self, p: Vector) -> Vector {
+		p - self.facing * self.signed_distance(p)
+	}
+
+	/// Mirror point `p` to the other side of the plane.
+	pub fn reflect(&self, p: Vector) -> Vector {
+		p - self.facing * (2.0 * self.signed_distance(p))
+	}
+
+	/// Get the same plane, facing the other way.
+	pub fn flip(&self) -> Plane {
+		Plane { facing: -self.facing, offset: -self.offset }
+	}
+
+	/// Move the plane by a transformation matrix (the same one that would
+	/// move points on the plane).
+	///
+	/// ## Panics
+	///
+	/// Panics if the matrix has no inverse (i.e. has zero determinant).
+	pub fn transform(&self, m: &Matrix) -> Plane {
+		self.try_transform(m).unwrap()
+	}
+
+	/// Attempt to move the plane by a transformation matrix, returning
+	/// `None` if the matrix is non-invertible (i.e. has zero determinant).
+	pub fn try_transform(&self, m: &Matrix) -> Option<Plane> {
+		// Planes transform by the inverse-transpose.
+		let it: [[f32; 4]; 4] = m.try_invert()?.transpose().into();
+		let p = [self.facing.x, self.facing.y, self.facing.z, -self.offset];
+		let mut q = [0.0; 4];
+
+		for (col, v) in it.iter().zip(p.iter()) {
+			for row in 0..4 {
+				q[row] += col[row] * v;
+			}
+		}
+
+		let normal = Vector::new(q[0], q[1], q[2]);
+		let len = normal.length();
+
+		Some(Plane { facing: normal / len, offset: -q[3] / len })
+	}
+
 	/// Returns true if distance from Plane to point is positive.
 	pub fn isdistpos_point(&self, p: Vector) -> bool {
-		(self.facing.x * (p.x - (self.facing.x * self.offset)))
-			+ (self.facing.y * (p.y - (self.facing.y * self.offset)))
-			+ (self.facing.z * (p.z - (self.facing.z * self.offset)))
-			 >= 0.0
+		self.signed_distance(p) >= 0.0
 	}
 
 	/// Returns true if distance from Plane to BCube is positive
 	pub fn isdistpos_bcube(&self, bcube: BCube) -> bool {
-		let (_, b) = bcube.pn_pair_from_normal(self.facing);
-
-/*		let pos_side = self.facing.dot(b) + self.offset;
-//		let pos_side = (self.facing.x * a.x)+(self.facing.y * a.y)+(self.facing.z * a.z)+self.offset;
-		if pos_side > 0.0 {
-			//box is completely on positive side of plane
-			return true;
-		}*/
-		let neg_side = self.facing.dot(b.into()) - self.offset;
-//		let neg_side = (self.facing.x * b.x)+(self.facing.y * b.y)+(self.facing.z * b.z)+self.offset;
-		if neg_side < 0.0 {
-			//box is completely on negative side of plane
-			return false;
-		}
+		// The corner farthest in front of the plane.
+		let (_, p) = bcube.pn_pair_from_normal(self.facing);
 
-		true
+		self.signed_distance(p) >= 0.0
+	}
 
-/*		let points = bcube.all_points();
+	/// Get which side of the plane a `BBox` is on.
+	pub fn classify_bbox(&self, bbox: BBox) -> Side {
+		// Distance from center, and the box's radius along the normal.
+		let d = self.signed_distance(bbox.center());
+		let e = (bbox.max - bbox.min) / 2.0;
+		let r = e.x * self.facing.x.abs() + e.y * self.facing.y.abs()
+			+ e.z * self.facing.z.abs();
 
-		for point in points.iter() {
-			// If point within, it's good!
-			if self.isdistpos_point(*point) {
-				return true;
-			}
-		}*/
+		Side::from_range(d - r, d + r)
+	}
 
-//		let (a, b) = bcube.pn_pair_from_normal(self.facing);
+	/// Get which side of the plane a `BCube` is on.
+	pub fn classify_bcube(&self, bcube: BCube) -> Side {
+		let (n, p) = bcube.pn_pair_from_normal(self.facing);
 
-		// If the extremes of the BCube is within the bounded area
-//		self.isdistpos_point(a) || !self.isdistpos_point(b)
+		Side::from_range(self.signed_distance(n), self.signed_distance(p))
+	}
+
+	/// Get which side of the plane a `BSphere` is on.
+	pub fn classify_sphere(&self, sphere: BSphere) -> Side {
+		let d = self.signed_distance(sphere.center);
+
+		Side::from_range(d - sphere.radius, d + sphere.radius)
+	}
+
+	/// Get the line where two planes meet, as a point on the line and the
+	/// line's direction.  Returns `None` if the planes are parallel.
+	pub fn intersect_plane(&self, other: &Plane) -> Option<(Vector, Vector)>
+	{
+		let dir = self.facing.cross(other.facing);
+		let len2 = dir.squared_length();
+
+		if len2 <= f32::EPSILON * f32::EPSILON {
+			return None;
+		}
+
+		let point = (other.facing.cross(dir) * self.offset
+			+ dir.cross(self.facing) * other.offset) / len2;
+
+		Some((point, dir / len2.sqrt()))
+	}
+
+	/// Get the point where three planes meet.  Returns `None` if any two of
+	/// the planes are parallel.
+	pub fn intersect_planes(&self, b: &Plane, c: &Plane) -> Option<Vector> {
+		let bc = b.facing.cross(c.facing);
+		let denom = self.facing.dot(bc);
 
-//		false
+		if denom.abs() <= f32::EPSILON {
+			return None;
+		}
+
+		Some((bc * self.offset
+			+ c.facing.cross(self.facing) * b.offset
+			+ self.facing.cross(b.facing) * c.offset) / denom)
 	}
 }
 
-/*#[test]
+#[test]
 fn test_plane_distpos() {
-	let t = ::Transform::new()
-		.rotate(-10.0, 20.0, -5.0)
-		.translate(500.0, -100.0, -115.0)
-		.rotate(1.0, 2.0, 0.3);
-
-	let a = Plane::new(t * Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 1.0, 0.0).transform_dir(t));
-	let b = Plane::new(t * Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, 0.0, -1.0).transform_dir(t));
-	let c = Plane::new(t * Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0).transform_dir(t));
-
-	assert!(a.isdistpos_point(t * Vector::new(-12.0, 2.0, 0.0)) == true);
-	assert!(a.isdistpos_point(t * Vector::new(-12.0, 0.0, 0.0)) == false);
-	assert!(b.isdistpos_point(t * Vector::new(15.0, 0.0, -12.0)) == true);
-	assert!(b.isdistpos_point(t * Vector::new(15.0, 0.0, 12.0)) == false);
-	assert!(c.isdistpos_point(t * Vector::new(5.0, -10.0, 0.5)) == true);
-	assert!(c.isdistpos_point(t * Vector::new(-5.0, -10.0, -0.5)) == false);
-}*/
-
-/*#[test]
+	let turn = |angles: Vector| Transform::new(Vector::zero(),
+		Rotation::euler(angles), Vector::from(1.0));
+	let t = turn(vector!(-10.0, 20.0, -5.0))
+		.then(Transform::new(vector!(500.0, -100.0, -115.0),
+			Rotation::identity(), Vector::from(1.0)))
+		.then(turn(vector!(1.0, 2.0, 0.3)));
+	let point = |p: Vector| t.transform_point(p);
+	let dir = |d: Vector| t.transform_vector(d);
+
+	let a = Plane::from_point_normal(point(vector!(0.0, 1.0, 0.0)),
+		dir(vector!(0.0, 1.0, 0.0)));
+	let b = Plane::from_point_normal(point(vector!(0.0, 0.0, 1.0)),
+		dir(vector!(0.0, 0.0, -1.0)));
+	let c = Plane::from_point_normal(point(vector!(0.0, 0.0, 0.0)),
+		dir(vector!(0.0, 0.0, 1.0)));
+
+	assert!(a.isdistpos_point(point(vector!(-12.0, 2.0, 0.0))));
+	assert!(!a.isdistpos_point(point(vector!(-12.0, 0.0, 0.0))));
+	assert!(b.isdistpos_point(point(vector!(15.0, 0.0, -12.0))));
+	assert!(!b.isdistpos_point(point(vector!(15.0, 0.0, 12.0))));
+	assert!(c.isdistpos_point(point(vector!(5.0, -10.0, 0.5))));
+	assert!(!c.isdistpos_point(point(vector!(-5.0, -10.0, -0.5))));
+
+	assert!((a.signed_distance(point(vector!(3.0, 4.0, -2.0))) - 3.0).abs()
+		< 0.001);
+}
+
+#[test]
 fn test_bcube_in_plane() {
-	let t = ::Transform::new()
-		.rotate(-10.0, 20.0, -5.0)
-		.translate(500.0, -100.0, -115.0)
-		.rotate(1.0, 2.0, 0.3);
+	let z = vector!(0.0, 0.0, 1.0);
+	let y_axis = vector!(0.0, 1.0, 0.0);
+	let turned = |angle: f32| Rotation::new(y_axis, angle).rotate(z);
 
-	// Plane is behind box	
-	let a = Plane::new(Vector::new(0.0, 0.0, 1.0)/*.transform_dir(t)*/, -2.5);
+	// Plane is behind box
+	let a = Plane::new(z, -2.5);
 	// Plane intersects box
-	let b = Plane::new(Vector::new(0.0, 0.0, 1.0)/*.transform_dir(t)*/, 0.0);
+	let b = Plane::new(z, 0.0);
 	// Plane is in front of box, plz cull
-	let c = Plane::new(Vector::new(0.0, 0.0, 1.0)/*.transform_dir(t)*/, 2.5);
+	let c = Plane::new(z, 2.5);
 	// Plane intersects box at 45° angle
-	let d = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, -0.5, 0.0)), 0.0);
+	let d = Plane::new(turned(-0.5), 0.0);
 	// Plane intersects box at 135° angle
-	let e = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, -1.5, 0.0)), 0.0);
+	let e = Plane::new(turned(-1.5), 0.0);
 	// Plane is behind box from 45° angle.
-	let f = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, 0.25, 0.0)), -2.5);
-	// Plane is behind box from 135° angle.	
-	let g = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, 0.75, 0.0)), -2.5);
+	let f = Plane::new(turned(0.25), -2.5);
+	// Plane is behind box from 135° angle.
+	let g = Plane::new(turned(0.75), -2.5);
 	// Plane is behind box from 225° angle.
-	let h = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, 1.25, 0.0)), -2.5);
+	let h = Plane::new(turned(1.25), -2.5);
 	// Plane is behind box from 315° angle.
-	let i = Plane::new(Vector::new(0.0, 0.0, 1.0).transform_dir(
-		::Transform::new()
-			.rotate(0.0, 1.75, 0.0)), -2.5);
-
-	assert!(a.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(b.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(c.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == false);
-	assert!(d.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(e.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(f.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(g.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(h.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-	assert!(i.isdistpos_bcube(BCube::new(Vector::new(0, 0.0, 0.0))) == true);
-
-	assert!(a.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(b.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(c.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == false);
-	assert!(d.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(e.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(f.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(g.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(h.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-	assert!(i.isdistpos_bcube(BCube::new(Vector::new(0, -2.0, 0.0))) == true);
-}*/
+	let i = Plane::new(turned(1.75), -2.5);
+
+	for center in [vector!(0.0, 0.0, 0.0), vector!(0.0, -2.0, 0.0)].iter() {
+		let bcube = BCube::new(*center);
+
+		assert!(a.isdistpos_bcube(bcube));
+		assert!(b.isdistpos_bcube(bcube));
+		assert!(!c.isdistpos_bcube(bcube));
+		assert!(d.isdistpos_bcube(bcube));
+		assert!(e.isdistpos_bcube(bcube));
+		assert!(f.isdistpos_bcube(bcube));
+		assert!(g.isdistpos_bcube(bcube));
+		assert!(h.isdistpos_bcube(bcube));
+		assert!(i.isdistpos_bcube(bcube));
+
+		assert_eq!(a.classify_bcube(bcube), Side::Front);
+		assert_eq!(b.classify_bcube(bcube), Side::Straddling);
+		assert_eq!(c.classify_bcube(bcube), Side::Back);
+		assert_eq!(d.classify_bcube(bcube), Side::Straddling);
+		assert_eq!(f.classify_bcube(bcube), Side::Front);
+		assert_eq!(a.classify_bbox(bcube.to_bbox()), Side::Front);
+		assert_eq!(d.classify_bbox(bcube.to_bbox()), Side::Straddling);
+		assert_eq!(c.classify_bbox(bcube.to_bbox()), Side::Back);
+	}
+
+	let sphere = BSphere::new(vector!(0.0, 0.0, 1.0), 1.0);
+	assert_eq!(a.classify_sphere(sphere), Side::Front);
+	assert_eq!(Plane::new(z, 0.5).classify_sphere(sphere),
+		Side::Straddling);
+	assert_eq!(Plane::new(-z, 0.0).classify_sphere(sphere), Side::Back);
+}
+
+#[test]
+fn test_plane_transform() {
+	let local = Plane::from_point_normal(vector!(0.0, 1.0, 0.0),
+		vector!(1.0, 1.0, 0.0));
+	let m = Matrix::default()
+		.s(vector!(2.0, 1.0, 0.5))
+		.r(Rotation::euler(vector!(0.3, -1.2, 0.7)))
+		.t(vector!(10.0, -4.0, 3.0));
+	let world = local.transform(&m);
+
+	// Points keep their side, and points on the plane stay on it.
+	for p in [vector!(0.0, 1.0, 0.0), vector!(-1.0, 2.0, 5.0),
+		vector!(3.0, 0.0, -2.0), vector!(-4.0, 0.0, 1.0)].iter()
+	{
+		let d = local.signed_distance(*p);
+		let w = world.signed_distance(m * (*p, 1.0));
+		assert!(d.abs() > 0.001 || w.abs() < 0.001);
+		assert_eq!(d > 0.001, w > 0.001);
+	}
+
+	// Rigid motion keeps distances.
+	let rigid = Matrix::default()
+		.rt(Rotation::euler(vector!(1.0, 2.0, 0.3)), vector!(5.0, 6.0, 7.0));
+	let moved = local.transform(&rigid);
+	let p = vector!(3.0, 4.0, -1.0);
+	assert!((local.signed_distance(p)
+		- moved.signed_distance(rigid * (p, 1.0))).abs() < 0.001);
+
+	let flipped = local.flip();
+	assert_eq!(flipped.signed_distance(p), -local.signed_distance(p));
+	assert!(local.try_transform(&Matrix::diagonal(0.0)).is_none());
+}
+
+#[test]
+fn test_plane_construct() {
+	let p = Plane::from_points(vector!(1.0, 2.0, 0.0), vector!(3.0, 2.0, 0.0),
+		vector!(1.0, 2.0, -5.0)).unwrap();
+	assert_eq!(p.facing, vector!(0.0, 1.0, 0.0));
+	assert_eq!(p.offset, 2.0);
+	assert!(Plane::from_points(vector!(0.0, 0.0, 0.0),
+		vector!(1.0, 1.0, 1.0), vector!(2.0, 2.0, 2.0)).is_none());
+
+	assert_eq!(p.project_point(vector!(4.0, 7.0, 1.0)),
+		vector!(4.0, 2.0, 1.0));
+	assert_eq!(p.reflect(vector!(4.0, 7.0, 1.0)), vector!(4.0, -3.0, 1.0));
+
+	let q = Plane::new(vector!(1.0, 0.0, 0.0), 3.0);
+	let (point, dir) = p.intersect_plane(&q).unwrap();
+	assert!(p.signed_distance(point).abs() < 0.0001);
+	assert!(q.signed_distance(point).abs() < 0.0001);
+	assert!(dir.dot(vector!(0.0, 0.0, 1.0)).abs() > 0.9999);
+	assert!(p.intersect_plane(&Plane::new(vector!(0.0, -1.0, 0.0), 1.0))
+		.is_none());
+
+	let r = Plane::new(vector!(0.0, 0.0, 1.0), -4.0);
+	assert_eq!(p.intersect_planes(&q, &r), Some(vector!(3.0, 2.0, -4.0)));
+}
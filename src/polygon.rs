@@ -0,0 +1,227 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// How close to a plane a point must be to count as on it.
+pub(crate) const PLANE_EPSILON: f32 = 0.000_01;
+
+/// Single-precision convex polygon
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polygon {
+	/// The corners of the polygon, in winding order.
+	pub points: Vec<Vector>,
+}
+
+impl From<Triangle> for Polygon {
+	fn from(triangle: Triangle) -> Self {
+		Polygon::new(vec![triangle.a, triangle.b, triangle.c])
+	}
+}
+
+impl Polygon {
+	/// Create a new polygon from a list of points in winding order.
+	pub fn new(points: Vec<Vector>) -> Polygon {
+		Polygon { points }
+	}
+
+	/// Create an empty polygon.
+	pub fn empty() -> Polygon {
+		Polygon { points: vec![] }
+	}
+
+	/// Check if the polygon has no area (fewer than 3 points).
+	pub fn is_empty(&self) -> bool {
+		self.points.len() < 3
+	}
+
+	/// Get the (not normalized) normal of the polygon, using Newell's
+	/// method.  Counter-clockwise winding is front facing.
+	pub fn normal(&self) -> Vector {
+		let mut normal = Vector::zero();
+
+		for (i, p) in self.points.iter().enumerate() {
+			let q = self.points[(i + 1) % self.points.len()];
+
+			normal.x += (p.y - q.y) * (p.z + q.z);
+			normal.y += (p.z - q.z) * (p.x + q.x);
+			normal.z += (p.x - q.x) * (p.y + q.y);
+		}
+
+		normal
+	}
+
+	/// Split the polygon by a `Plane` (Sutherland–Hodgman).  Returns the
+	/// part in front of the plane, and the part behind it.  Points within
+	/// a small distance of the plane count as on it; a polygon lying on
+	/// the plane is in front if it faces the same way as the plane.
+	pub fn clip(&self, plane: &Plane) -> (Polygon, Polygon) {
+		if self.is_empty() {
+			return (Polygon::empty(), Polygon::empty());
+		}
+
+		let dist: Vec<f32> = self.points.iter()
+			.map(|p| plane.signed_distance(*p))
+			.collect();
+		let any_front = dist.iter().any(|d| *d > PLANE_EPSILON);
+		let any_back = dist.iter().any(|d| *d < -PLANE_EPSILON);
+
+		match (any_front, any_back) {
+			(false, false) => {
+				return if self.normal().dot(plane.facing) >= 0.0 {
+					(self.clone(), Polygon::empty())
+				} else {
+					(Polygon::empty(), self.clone())
+				};
+			}
+			(true, false) => return (self.clone(), Polygon::empty()),
+			(false, true) => return (Polygon::empty(), self.clone()),
+			(true, true) => {}
+		}
+
+		let mut front = Vec::with_capacity(self.points.len() + 1);
+		let mut back = Vec::with_capacity(self.points.len() + 1);
+
+		for i in 0..self.points.len() {
+			let j = (i + 1) % self.points.len();
+			let (p, q) = (self.points[i], self.points[j]);
+			let (dp, dq) = (dist[i], dist[j]);
+
+			// Points on the plane go to both sides.
+			if dp >= -PLANE_EPSILON {
+				front.push(p);
+			}
+			if dp <= PLANE_EPSILON {
+				back.push(p);
+			}
+
+			// Edge crosses the plane.
+			if (dp > PLANE_EPSILON && dq < -PLANE_EPSILON)
+				|| (dp < -PLANE_EPSILON && dq > PLANE_EPSILON)
+			{
+				let x = p + (q - p) * (dp / (dp - dq));
+				front.push(x);
+				back.push(x);
+			}
+		}
+
+		(Polygon::new(front), Polygon::new(back))
+	}
+
+	/// Get only the part of the polygon in front of a `Plane`.
+	pub fn clip_front(&self, plane: &Plane) -> Polygon {
+		self.clip(plane).0
+	}
+
+	/// Get the part of the polygon inside a `Frustum`.
+	pub fn clip_to_frustum(&self, frustum: &Frustum) -> Polygon {
+		self.clip_to_planes(&frustum.planes())
+	}
+
+	/// Get the part of the polygon inside a `BBox`.
+	pub fn clip_to_bbox(&self, bbox: BBox) -> Polygon {
+		self.clip_to_planes(&[
+			Plane::new(vector!(1.0, 0.0, 0.0), bbox.min.x),
+			Plane::new(vector!(-1.0, 0.0, 0.0), -bbox.max.x),
+			Plane::new(vector!(0.0, 1.0, 0.0), bbox.min.y),
+			Plane::new(vector!(0.0, -1.0, 0.0), -bbox.max.y),
+			Plane::new(vector!(0.0, 0.0, 1.0), bbox.min.z),
+			Plane::new(vector!(0.0, 0.0, -1.0), -bbox.max.z),
+		])
+	}
+
+	/// Get the part of the polygon in front of all of the planes.
+	pub fn clip_to_planes(&self, planes: &[Plane]) -> Polygon {
+		let mut polygon = self.clone();
+
+		for plane in planes {
+			if polygon.is_empty() {
+				break;
+			}
+			polygon = polygon.clip_front(plane);
+		}
+
+		if polygon.is_empty() {
+			Polygon::empty()
+		} else {
+			polygon
+		}
+	}
+
+	/// Triangulate the polygon as a fan.
+	pub fn triangles(&self) -> Vec<Triangle> {
+		let mut triangles = vec![];
+
+		for i in 2..self.points.len() {
+			triangles.push(Triangle::new(self.points[0],
+				self.points[i - 1], self.points[i]));
+		}
+
+		triangles
+	}
+}
+
+#[test]
+fn test_polygon_clip() {
+	let square = Polygon::new(vec![
+		vector!(0.0, 0.0, 0.0),
+		vector!(2.0, 0.0, 0.0),
+		vector!(2.0, 2.0, 0.0),
+		vector!(0.0, 2.0, 0.0),
+	]);
+
+	// Cut in half.
+	let (front, back) = square.clip(&Plane::new(vector!(1.0, 0.0, 0.0),
+		1.0));
+	assert_eq!(front.points.len(), 4);
+	assert_eq!(back.points.len(), 4);
+	assert!(front.points.iter().all(|p| p.x >= 1.0));
+	assert!(back.points.iter().all(|p| p.x <= 1.0));
+
+	// Cut along an edge (nearly): nothing behind.
+	let (front, back) = square.clip(&Plane::new(vector!(1.0, 0.0, 0.0),
+		-0.000_001));
+	assert_eq!(front, square);
+	assert!(back.is_empty());
+
+	// Coplanar goes with facing.
+	let (front, back) = square.clip(&Plane::new(vector!(0.0, 0.0, -1.0),
+		0.0));
+	assert!(front.is_empty());
+	assert_eq!(back, square);
+
+	// Clip a corner off with a box.
+	let clipped = square.clip_to_bbox(BBox::new(vector!(1.0, 1.0, -1.0),
+		vector!(3.0, 3.0, 1.0)));
+	assert_eq!(clipped.points.len(), 4);
+	assert!((clipped.normal().length() / 2.0 - 1.0).abs() < 0.0001);
+	assert!(square.clip_to_bbox(BBox::new(vector!(3.0, 3.0, -1.0),
+		vector!(4.0, 4.0, 1.0))).is_empty());
+
+	// In front of a frustum looking down +z.
+	let frustum = Frustum::new(vector!(0.0, 0.0, -10.0), 100.0, 0.0, 0.0,
+		1.0, 1.0);
+	let visible = square.clip_to_frustum(&frustum);
+	assert_eq!(visible, square);
+	let behind = Frustum::new(vector!(0.0, 0.0, 10.0), 100.0, 0.0, 0.0,
+		1.0, 1.0);
+	assert!(square.clip_to_frustum(&behind).is_empty());
+
+	// 90 degree field of view cuts a wide quad to |x| <= z.
+	let wide = Polygon::new(vec![
+		vector!(-5.0, -0.5, 1.0),
+		vector!(5.0, -0.5, 1.0),
+		vector!(5.0, 0.5, 1.0),
+		vector!(-5.0, 0.5, 1.0),
+	]);
+	let quarter = ::std::f32::consts::FRAC_PI_2;
+	let frustum = Frustum::new(Vector::zero(), 100.0, 0.0, 0.0, quarter,
+		quarter);
+	let visible = wide.clip_to_frustum(&frustum);
+	assert_eq!(visible.points.len(), 4);
+	assert!(visible.points.iter().all(|p| p.x.abs() <= 1.0001));
+	assert!(visible.points.iter().any(|p| p.x >= 0.9999));
+}
@@ -0,0 +1,457 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::fmt;
+use tree;
+use *;
+
+/// A quadtree is a DAG that can quickly search for points in 2D space.  It
+/// works the same as an `Octree`, but with 4 quadrants per node rather than 8
+/// octants.
+///
+/// The bounding square of the root node contains all points in the quadtree.
+/// If a point outside the bounding square is added, a new root node is created
+/// which contains the old root as one of its quadrants.  This process is
+/// repeated until the point is contained.
+///
+/// The nodes are stored in a vector, and are indexed using a 32-bit node ID.
+/// This saves memory over using pointers on 64-bit systems.  Node ID 1 is the
+/// first node in the vector.
+///
+/// Like an `Octree`, it can be loose (see `Quadtree::loose()`), limit the
+/// size of its root with `set_max_root()`, or have a fixed root (see
+/// `Quadtree::with_bounds()`); colliders that don't fit go in an overflow
+/// list.
+pub struct Quadtree<T: Collider2<S>, S: Scalar = f32> {
+	colliders: Vec<Option<T>>,
+	tree: Tree<S>,
+}
+
+const BRANCHES: usize = 4;		// child branches (quadrants) per node
+
+type Tree<S> = tree::Tree<BSquare<S>, S, BRANCHES>;
+
+impl<T, S> Default for Quadtree<T, S> where T: Collider2<S>, S: Scalar {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T, S> Quadtree<T, S> where T: Collider2<S>, S: Scalar {
+	/// Create a new quadtree
+	pub fn new() -> Quadtree<T, S> {
+		Quadtree { colliders: vec![], tree: Tree::new() }
+	}
+
+	/// Create a new loose quadtree, where the bounds of each branch are
+	/// enlarged by `looseness` (2 is typical).  Colliders go in the child
+	/// containing their center if they fit in its enlarged bounds.
+	///
+	/// ## Panics
+	///
+	/// Panics if `looseness` is less than 1.
+	pub fn loose(looseness: S) -> Quadtree<T, S> {
+		Quadtree { colliders: vec![], tree: Tree::loose(looseness) }
+	}
+
+	/// Create a new quadtree with a fixed root `bounds`, which never grows
+	/// or shrinks.  Colliders that aren't entirely within `bounds` (or are
+	/// flat on one of its greater sides) go in the overflow list.
+	///
+	/// ## Panics
+	///
+	/// Panics if `bounds` is empty.
+	pub fn with_bounds(bounds: BSquare<S>) -> Quadtree<T, S> {
+		Quadtree { colliders: vec![], tree: Tree::with_bounds(bounds) }
+	}
+
+	/// Get the fixed root bounds, if any (see `with_bounds()`).
+	pub fn bounds(&self) -> Option<BSquare<S>> {
+		self.tree.bounds
+	}
+
+	/// Get how much the bounds of each branch are enlarged (1 if not
+	/// loose).
+	pub fn looseness(&self) -> S {
+		self.tree.looseness
+	}
+
+	/// Limit the half length of the root to `half_len`, or don't limit it
+	/// if `None` (the default).  Colliders that would grow the root past it
+	/// go in the overflow list instead.  Ignored if the root is fixed with
+	/// `with_bounds()`.
+	pub fn set_max_root(&mut self, half_len: Option<S>) {
+		self.tree.max_root = half_len;
+	}
+
+	/// Get the largest half length the root can grow to, if limited.
+	pub fn max_root(&self) -> Option<S> {
+		self.tree.max_root
+	}
+
+	/// Get the `Id`s of the colliders in the overflow list, which don't fit
+	/// under the largest root, or are outside the fixed bounds.
+	pub fn overflow(&self) -> &[Id] {
+		&self.tree.overflow
+	}
+
+	/// Clear the quadtree.
+	pub fn clear(&mut self) {
+		self.colliders.clear();
+		self.tree.clear();
+	}
+
+	/// Add a point in the quadtree
+	///
+	/// ## Panics
+	///
+	/// Panics if `try_add()` fails.
+	pub fn add(&mut self, point: T) -> Id {
+		self.try_add(point).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	/// Add a collider, or fail if its `BRect` is invalid, or the quadtree
+	/// is full.
+	pub fn try_add(&mut self, point: T) -> Result<Id, OctreeError> {
+		let id = self.tree.add(point.brect())?;
+
+		// Put the collider at its id.
+		if usize::from(id) < self.colliders.len() {
+			self.colliders[usize::from(id)] = Some(point);
+		} else {
+			self.colliders.push(Some(point));
+		}
+
+		Ok(id)
+	}
+
+	/// Remove a point from the quadtree
+	///
+	/// ## Panics
+	///
+	/// Panics if `try_remove()` fails.
+	pub fn remove(&mut self, id: Id) -> T {
+		self.try_remove(id).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	/// Remove a collider, or fail if `id` isn't in the quadtree.  The `Id`
+	/// of a removed collider is reused by the next one added.
+	pub fn try_remove(&mut self, id: Id) -> Result<T, OctreeError> {
+		self.tree.remove(id)?;
+
+		// Take the collider out.
+		let ret = self.colliders[usize::from(id)].take()
+			.expect("no such collider");
+
+		if self.is_empty() {
+			self.clear();
+		}
+
+		Ok(ret)
+	}
+
+	/// Rebuild the tree under a root fitted to the colliders in it (unless
+	/// it's fixed).  Colliders in the overflow list go back in the tree if
+	/// they fit.  `Id`s don't change.
+	pub fn rebalance(&mut self) {
+		self.tree.rebalance();
+	}
+
+	/// Get a collider, or `None` if `id` isn't in the quadtree.
+	pub fn get(&self, id: Id) -> Option<&T> {
+		if id.is_none() {
+			return None;
+		}
+
+		self.colliders.get(usize::from(id))?.as_ref()
+	}
+
+	/// Move a collider in the quadtree if its `BRect` changed since it was
+	/// added (or last updated).  Returns true if it moved.
+	///
+	/// ## Panics
+	///
+	/// Panics if `try_update()` fails.
+	pub fn update(&mut self, id: Id) -> bool {
+		self.try_update(id).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	/// Like `update()`, but fails if `id` isn't in the quadtree, or the new
+	/// `BRect` is invalid (then the collider stays where it was).
+	pub fn try_update(&mut self, id: Id) -> Result<bool, OctreeError> {
+		let brect = self.get(id).ok_or(OctreeError::StaleId)?.brect();
+
+		self.tree.update(id, brect)
+	}
+
+	/// Get the `BRect` a collider had when it was added, which is where it
+	/// is in the quadtree, and what queries test.  Changing a collider with
+	/// `IndexMut` doesn't change it.  `None` if `id` isn't in the quadtree.
+	pub fn stored_brect(&self, id: Id) -> Option<BRect<S>> {
+		self.tree.stored_bounds(id)
+	}
+
+	/// Get the number of colliders in the quadtree.
+	pub fn len(&self) -> usize {
+		self.tree.len()
+	}
+
+	/// Check if there are no colliders in the quadtree.
+	pub fn is_empty(&self) -> bool {
+		self.tree.is_empty()
+	}
+
+	/// Get the `Id`s of all colliders whose `BRect` collides with `brect`.
+	pub fn query_brect(&self, brect: BRect<S>) -> Vec<Id> {
+		self.tree.query(
+			|bsquare| brect.collide_bsquare(bsquare),
+			|other| brect.collide(other),
+		)
+	}
+
+	/// Get the `Id`s of all colliders whose `BRect` is hit by a ray from
+	/// `origin` along `direction`.
+	pub fn query_ray(&self, origin: Vec2<S>, direction: Vec2<S>)
+		-> Vec<Id>
+	{
+		self.tree.query(
+			|bsquare| bsquare.to_brect().intersect_ray(origin, direction)
+				.is_some(),
+			|other| other.intersect_ray(origin, direction)
+				.is_some(),
+		)
+	}
+
+	/// Get each pair of colliders whose `BRect`s collide, with the lesser
+	/// `Id` first.
+	pub fn collision_pairs(&self) -> Vec<(Id, Id)> {
+		self.tree.collision_pairs()
+	}
+}
+
+impl<T, S> ::std::ops::Index<Id> for Quadtree<T, S>
+	where T: Collider2<S>, S: Scalar
+{
+	type Output = T;
+
+	fn index(&self, index: Id) -> &T {
+		let index: usize = index.into();
+		self.colliders[index].as_ref().expect("no such collider")
+	}
+}
+
+/// Changing a collider's `BRect` this way doesn't move it in the quadtree;
+/// call `Quadtree::update()` after.
+impl<T, S> ::std::ops::IndexMut<Id> for Quadtree<T, S>
+	where T: Collider2<S>, S: Scalar
+{
+	fn index_mut(&mut self, index: Id) -> &mut T {
+		let index: usize = index.into();
+		self.colliders[index].as_mut().expect("no such collider")
+	}
+}
+
+impl<T, S> fmt::Display for Quadtree<T, S>
+	where T: Collider2<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.tree, f)
+	}
+}
+
+impl<T, S> fmt::Debug for Quadtree<T, S>
+	where T: Collider2<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.tree, f)
+	}
+}
+
+
+#[cfg(test)]
+struct TestCollider(BRect);
+
+#[cfg(test)]
+impl Collider2 for TestCollider {
+	fn brect(&self) -> BRect {
+		self.0
+	}
+}
+
+#[test]
+fn test_quadtree_query() {
+	// Deterministic pseudo-random rectangles.
+	let mut seed = 11u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % 10_000) as f32 / 100.0 - 50.0
+	};
+
+	let mut quadtree = Quadtree::new();
+	let mut rects = vec![];
+	for i in 0..400 {
+		let min = Vector2::new(random(), random());
+		// Every 8th is big, so straddles centers and must link.
+		let size = if i % 8 == 0 { 30.0 } else { 1.0 };
+		let brect = BRect::new(min, min + Vector2::from(size));
+		rects.push((quadtree.add(TestCollider(brect)), brect));
+	}
+	assert_eq!(quadtree.len(), 400);
+
+	let check = |quadtree: &Quadtree<TestCollider>, rects: &[(Id, BRect)]| {
+		for i in 0..20 {
+			let c = Vector2::from(i as f32 * 5.0 - 50.0);
+			let q = BRect::new(c, c + Vector2::new(10.0, 20.0));
+			let mut found = quadtree.query_brect(q);
+			let mut expect: Vec<Id> = rects.iter()
+				.filter(|(_, r)| r.collide(q))
+				.map(|(id, _)| *id)
+				.collect();
+			found.sort_by_key(|id| id.0);
+			expect.sort_by_key(|id| id.0);
+			assert_eq!(found, expect);
+		}
+	};
+
+	check(&quadtree, &rects);
+
+	// Remove every other one.
+	let mut kept = vec![];
+	for (i, (id, brect)) in rects.into_iter().enumerate() {
+		if i % 2 == 0 {
+			quadtree.remove(id);
+		} else {
+			kept.push((id, brect));
+		}
+	}
+	assert_eq!(quadtree.len(), 200);
+	check(&quadtree, &kept);
+
+	let origin = Vector2::new(-100.0, 0.5);
+	let direction = Vector2::new(1.0, 0.0);
+	let expect = kept.iter()
+		.filter(|(_, r)| r.intersect_ray(origin, direction).is_some())
+		.count();
+	assert_eq!(quadtree.query_ray(origin, direction).len(), expect);
+
+	for (id, _) in kept {
+		quadtree.remove(id);
+	}
+	assert!(quadtree.is_empty());
+}
+
+#[test]
+fn test_quadtree_drop() {
+	use std::cell::Cell;
+	use std::rc::Rc;
+
+	// Counts how many times it's dropped.
+	struct Counted(BRect, Rc<Cell<u32>>);
+
+	impl Collider2 for Counted {
+		fn brect(&self) -> BRect {
+			self.0
+		}
+	}
+
+	impl Drop for Counted {
+		fn drop(&mut self) {
+			self.1.set(self.1.get() + 1);
+		}
+	}
+
+	let drops = Rc::new(Cell::new(0));
+	let at = |x: f32| BRect::new(Vector2::new(x, 0.0),
+		Vector2::new(x + 1.0, 1.0));
+	let mut quadtree = Quadtree::new();
+	let a = quadtree.add(Counted(at(0.0), drops.clone()));
+	quadtree.add(Counted(at(5.0), drops.clone()));
+	drop(quadtree.remove(a));
+	assert_eq!(drops.get(), 1);
+
+	// Reuses the slot of `a`.
+	let c = quadtree.add(Counted(at(9.0), drops.clone()));
+	assert_eq!(c, a);
+	assert_eq!(quadtree.query_brect(at(9.0)), vec![c]);
+	drop(quadtree);
+	assert_eq!(drops.get(), 3);
+}
+
+#[test]
+fn test_quadtree_center_ties() {
+	let at = |a: [f32; 2], b: [f32; 2]| BRect::new(Vector2::new(a[0], a[1]),
+		Vector2::new(b[0], b[1]));
+	let check = |looseness: f32, rects: &[BRect]| {
+		let mut quadtree = Quadtree::loose(looseness);
+		let ids: Vec<Id> = rects.iter()
+			.map(|brect| quadtree.add(TestCollider(*brect)))
+			.collect();
+		for (id, brect) in ids.iter().zip(rects.iter()) {
+			assert!(quadtree.query_brect(*brect).contains(id));
+		}
+		for id in ids {
+			assert!(quadtree.try_remove(id).is_ok());
+		}
+		assert!(quadtree.is_empty());
+	};
+
+	// Flat on the greater sides of the root before it grows, so then on
+	// the center lines of the grown root.
+	let rects = [
+		at([1.5, -0.5], [1.5, 0.0]),
+		at([-0.5, -2.0], [0.0, -1.5]),
+		at([2.0, -1.5], [2.0, -1.0]),
+		at([2.0, 1.5], [2.5, 1.75]),
+	];
+	for &looseness in &[1.0, 1.5, 2.0] {
+		check(looseness, &rects);
+	}
+
+	// Random rectangles on a grid of center lines.
+	let mut seed = 5u32;
+	let mut random = move |n: u32| {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		((seed >> 8) % n) as f32
+	};
+
+	for i in 0..600 {
+		let rects: Vec<BRect> = (0..6).map(|_| {
+			let min = Vector2::new(random(17), random(17)) * 0.5
+				- Vector2::from(4.0);
+			let size = Vector2::new(random(3), random(3)) * 0.25;
+			BRect::new(min, min + size)
+		}).collect();
+		check([1.0, 1.5, 2.0][i % 3], &rects);
+	}
+}
+
+#[test]
+fn test_quadtree_errors() {
+	let at = |x: f32| BRect::new(Vector2::new(x, 0.0),
+		Vector2::new(x + 1.0, 1.0));
+	let bad = BRect::new(Vector2::new(0.0, 0.0),
+		Vector2::new(f32::INFINITY, 1.0));
+	let mut quadtree = Quadtree::new();
+	assert_eq!(quadtree.try_add(TestCollider(bad)).err(),
+		Some(OctreeError::InvalidBounds));
+	let a = quadtree.add(TestCollider(at(0.0)));
+	let b = quadtree.add(TestCollider(at(5.0)));
+	assert_eq!(quadtree.stored_brect(a).unwrap().min, at(0.0).min);
+
+	// Moving it in the quadtree.
+	quadtree[a].0 = at(9.0);
+	assert_eq!(quadtree.query_brect(at(9.0)), vec![]);
+	assert_eq!(quadtree.try_update(a), Ok(true));
+	assert_eq!(quadtree.query_brect(at(9.0)), vec![a]);
+	quadtree[a].0 = bad;
+	assert_eq!(quadtree.try_update(a), Err(OctreeError::InvalidBounds));
+	assert_eq!(quadtree.query_brect(at(9.0)), vec![a]);
+
+	quadtree.remove(b);
+	assert_eq!(quadtree.try_remove(b).err(), Some(OctreeError::StaleId));
+	assert!(quadtree.stored_brect(b).is_none());
+	assert!(quadtree.get(b).is_none());
+}
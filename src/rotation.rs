@@ -4,33 +4,38 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-use cgmath;
-use std::{fmt, mem, ops};
-
-use cgmath::{InnerSpace, Rotation3};
+use std::{fmt, ops};
 use *;
 
-/// Single-precision quaternion.
+/// Quaternion, generic over the `Scalar` type.
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[allow(missing_docs)]
 #[repr(C)]
-pub struct Rotation {
-	pub x: f32,
-	pub y: f32,
-	pub z: f32,
-	pub s: f32,
+pub struct Quat<S> {
+	pub x: S,
+	pub y: S,
+	pub z: S,
+	pub s: S,
 }
 
-impl fmt::Display for Rotation {
+/// Single-precision quaternion.
+pub type Rotation = Quat<f32>;
+
+/// Double-precision quaternion.
+pub type DRotation = Quat<f64>;
+
+impl<S: Scalar> fmt::Display for Quat<S> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(f, "({}, {}, {}; {})", self.x, self.y, self.z, self.s)
 	}
 }
 
-impl Rotation {
+impl<S: Scalar> Quat<S> {
 	/// Identity constructor.
 	pub fn identity() -> Self {
-		Rotation { x: 0.0, y: 0.0, z: 0.0, s: 1.0 }
+		let z = S::zero();
+
+		Quat { x: z, y: z, z, s: S::one() }
 	}
 
 	/// Constructor for a rotation defined by a set of Euler angles
@@ -38,89 +43,423 @@ impl Rotation {
 	/// The rotation order is Z, then X, then Y. From the point of the
 	/// object, this is equivalent to a yaw in `angles.y`, a pitch in
 	/// `angles.x`, and a roll in `angles.z`.
-	pub fn euler(angles: Vector) -> Self {
-		let roll = Rotation::new(vector!(0.0, 0.0, 1.0), angles.z);
-		let pitch = Rotation::new(vector!(1.0, 0.0, 0.0), angles.x);
-		let yaw = Rotation::new(vector!(0.0, 1.0, 0.0), angles.y);
+	pub fn euler(angles: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+		let roll = Quat::new(Vec3::new(z, z, o), angles.z);
+		let pitch = Quat::new(Vec3::new(o, z, z), angles.x);
+		let yaw = Quat::new(Vec3::new(z, o, z), angles.y);
 		roll * pitch * yaw
 	}
 
 	/// Create a new `Rotation` from axis and angle.
-	pub fn new(axis: Vector, angle: f32) -> Self {
-		let q = cgmath::Quaternion::from_axis_angle(
-			cgmath::Vector3::new(axis.x, axis.y, axis.z).normalize(),
-			cgmath::Rad(angle),
-		);
+	pub fn new(axis: Vec3<S>, angle: S) -> Self {
+		let (sin, cos) = (angle / S::from_f64(2.0)).sin_cos();
+		let axis = axis.normalize() * sin;
 
-		Rotation { x: q.v.x, y: q.v.y, z: q.v.z, s: q.s }
+		Quat { x: axis.x, y: axis.y, z: axis.z, s: cos }
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Quat<T> {
+		Quat {
+			x: self.x.cast(),
+			y: self.y.cast(),
+			z: self.z.cast(),
+			s: self.s.cast(),
+		}
 	}
 
 	/// Return the application of the rotation represented by this quaternion
 	/// to the vector argument.
-	pub fn rotate(&self, vector: Vector) -> Vector {
-		use cgmath::Rotation;
-		let rotation = cgmath::Quaternion::new(self.s, self.x, self.y, self.z);
-		let point = cgmath::Point3::new(vector.x, vector.y, vector.z);
-		let result = rotation.rotate_point(point);
-		vector!(result.x, result.y, result.z)
+	pub fn rotate(&self, vector: Vec3<S>) -> Vec3<S> {
+		// Same as `self * vector * self.conjugate()`, for unit `self`.
+		let v = Vec3::new(self.x, self.y, self.z);
+		let t = v.cross(vector) * S::from_f64(2.0);
+
+		vector + t * self.s + v.cross(t)
 	}
 
-	/// Add another `Rotation` after the current `Rotation`.
+	/// Add another `Rotation` after the current `Rotation`, so that
+	/// `a.then(b).rotate(v)` is `b.rotate(a.rotate(v))`.
 	pub fn then(self, rhs: Self) -> Self {
-		// TODO: check to make sure this order is correct.
-		// (that it is not `self * rhs`).  Internet search is of no help
-		// so experimentation is the only way to find out.
 		rhs * self
 	}
+
+	/// Create the shortest arc `Rotation` that turns direction `from` to
+	/// direction `to`.  Opposite directions turn π around any
+	/// perpendicular axis.
+	pub fn from_to(from: Vec3<S>, to: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+		let from = from.normalize();
+		let to = to.normalize();
+		let d = from.dot(to);
+		let epsilon = S::from_f64(0.000_001);
+
+		if d >= o - epsilon {
+			Quat::identity()
+		} else if d <= epsilon - o {
+			let axis = Vec3::new(o, z, z).cross(from).try_normalize()
+				.unwrap_or_else(|| Vec3::new(z, o, z).cross(from)
+					.normalize());
+
+			Quat { x: axis.x, y: axis.y, z: axis.z, s: z }
+		} else {
+			let axis = from.cross(to);
+
+			Quat { x: axis.x, y: axis.y, z: axis.z, s: o + d }
+				.normalize()
+		}
+	}
+
+	/// Create a `Rotation` that turns +Z to face `forward`, with +Y turned
+	/// as close to `up` as possible.  If `forward` is parallel to `up`,
+	/// this is the same as `from_to(+Z, forward)`.
+	pub fn look_rotation(forward: Vec3<S>, up: Vec3<S>) -> Self {
+		let (o, z) = (S::one(), S::zero());
+		let f = forward.normalize();
+		let r = match up.cross(f).try_normalize() {
+			Some(r) => r,
+			None => return Quat::from_to(Vec3::new(z, z, o), f),
+		};
+		let u = f.cross(r);
+
+		Quat::from_matrix(Mat4::new(
+			r.x, r.y, r.z, z,
+			u.x, u.y, u.z, z,
+			f.x, f.y, f.z, z,
+			z, z, z, o,
+		))
+	}
+
+	/// Create a `Rotation` from the rotation part of a `Matrix`.  The
+	/// `Matrix` must not have any scale or shear.
+	pub fn from_matrix(matrix: Mat4<S>) -> Self {
+		let m: [[S; 4]; 4] = matrix.into();
+		// Row `r`, column `c` (the matrix is stored by column).
+		let at = |r: usize, c: usize| m[c][r];
+		let (o, two) = (S::one(), S::from_f64(2.0));
+		let quarter = S::from_f64(0.25);
+		let trace = at(0, 0) + at(1, 1) + at(2, 2);
+
+		if trace > S::zero() {
+			let s4 = (trace + o).sqrt() * two;
+			Quat {
+				x: (at(2, 1) - at(1, 2)) / s4,
+				y: (at(0, 2) - at(2, 0)) / s4,
+				z: (at(1, 0) - at(0, 1)) / s4,
+				s: quarter * s4,
+			}
+		} else if at(0, 0) > at(1, 1) && at(0, 0) > at(2, 2) {
+			let s4 = (o + at(0, 0) - at(1, 1) - at(2, 2)).sqrt() * two;
+			Quat {
+				x: quarter * s4,
+				y: (at(0, 1) + at(1, 0)) / s4,
+				z: (at(0, 2) + at(2, 0)) / s4,
+				s: (at(2, 1) - at(1, 2)) / s4,
+			}
+		} else if at(1, 1) > at(2, 2) {
+			let s4 = (o + at(1, 1) - at(0, 0) - at(2, 2)).sqrt() * two;
+			Quat {
+				x: (at(0, 1) + at(1, 0)) / s4,
+				y: quarter * s4,
+				z: (at(1, 2) + at(2, 1)) / s4,
+				s: (at(0, 2) - at(2, 0)) / s4,
+			}
+		} else {
+			let s4 = (o + at(2, 2) - at(0, 0) - at(1, 1)).sqrt() * two;
+			Quat {
+				x: (at(0, 2) + at(2, 0)) / s4,
+				y: (at(1, 2) + at(2, 1)) / s4,
+				z: quarter * s4,
+				s: (at(1, 0) - at(0, 1)) / s4,
+			}
+		}
+	}
+
+	/// Get the conjugate, which is the inverse of a unit quaternion.
+	pub fn conjugate(self) -> Self {
+		Quat { x: -self.x, y: -self.y, z: -self.z, s: self.s }
+	}
+
+	/// Get the inverse `Rotation`, which undoes this one.
+	pub fn inverse(self) -> Self {
+		let n = self.dot(self);
+		let c = self.conjugate();
+
+		Quat { x: c.x / n, y: c.y / n, z: c.z / n, s: c.s / n }
+	}
+
+	/// Returns the dot product of two quaternions.
+	pub fn dot(self, rhs: Self) -> S {
+		self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.s * rhs.s
+	}
+
+	/// Scale to a unit quaternion, to correct for rounding errors.
+	pub fn normalize(self) -> Self {
+		let n = self.dot(self).sqrt();
+
+		Quat { x: self.x / n, y: self.y / n, z: self.z / n, s: self.s / n }
+	}
+
+	/// Normalized linear interpolation from `self` (`t` is 0) to `rhs`
+	/// (`t` is 1), along the shortest path.  Faster than `slerp()`, but
+	/// doesn't turn at a constant speed.
+	pub fn nlerp(self, rhs: Self, t: S) -> Self {
+		let rhs = if self.dot(rhs) < S::zero() { -rhs } else { rhs };
+		let u = S::one() - t;
+
+		Quat {
+			x: self.x * u + rhs.x * t,
+			y: self.y * u + rhs.y * t,
+			z: self.z * u + rhs.z * t,
+			s: self.s * u + rhs.s * t,
+		}.normalize()
+	}
+
+	/// Spherical linear interpolation from `self` (`t` is 0) to `rhs` (`t`
+	/// is 1), along the shortest path at a constant speed.
+	pub fn slerp(self, rhs: Self, t: S) -> Self {
+		let mut d = self.dot(rhs);
+		let rhs = if d < S::zero() {
+			d = -d;
+			-rhs
+		} else {
+			rhs
+		};
+
+		// Nearly the same, so avoid dividing by a tiny sine.
+		if d > S::from_f64(0.9995) {
+			return self.nlerp(rhs, t);
+		}
+
+		let theta = d.acos();
+		let sin = theta.sin();
+		let a = ((S::one() - t) * theta).sin() / sin;
+		let b = (t * theta).sin() / sin;
+
+		Quat {
+			x: self.x * a + rhs.x * b,
+			y: self.y * a + rhs.y * b,
+			z: self.z * a + rhs.z * b,
+			s: self.s * a + rhs.s * b,
+		}
+	}
+
+	/// Get the (unit) axis and angle (from 0 to 2π) of the `Rotation`.  The
+	/// identity has no axis, and returns +X with an angle of 0.
+	pub fn to_axis_angle(self) -> (Vec3<S>, S) {
+		let q = self.normalize();
+		let (o, z) = (S::one(), S::zero());
+		let angle = q.s.max(-o).min(o).acos() * S::from_f64(2.0);
+
+		match Vec3::new(q.x, q.y, q.z).try_normalize() {
+			Some(axis) => (axis, angle),
+			None => (Vec3::new(o, z, z), z),
+		}
+	}
+
+	/// Get the Euler angles, so that `Rotation::euler(r.to_euler())` is
+	/// `r` (see `euler()` for their order).  Pitch (`x`) is from -π/2 to
+	/// π/2, and yaw (`y`) and roll (`z`) from -π to π.
+	pub fn to_euler(self) -> Vec3<S> {
+		let q = self.normalize();
+		let (o, two) = (S::one(), S::from_f64(2.0));
+		let sin_pitch = two * (q.y * q.z + q.s * q.x);
+
+		if sin_pitch.abs() >= o - S::from_f64(0.000_001) {
+			// Gimbal lock: yaw and roll turn the same way, use roll.
+			let pitch = S::from_f64(::std::f64::consts::FRAC_PI_2)
+				* sin_pitch.signum();
+			let roll = (two * (q.x * q.y + q.s * q.z))
+				.atan2(o - two * (q.y * q.y + q.z * q.z));
+
+			return Vec3::new(pitch, S::zero(), roll);
+		}
+
+		let pitch = sin_pitch.asin();
+		let yaw = (-two * (q.x * q.z - q.s * q.y))
+			.atan2(o - two * (q.x * q.x + q.y * q.y));
+		let roll = (-two * (q.x * q.y - q.s * q.z))
+			.atan2(o - two * (q.x * q.x + q.z * q.z));
+
+		Vec3::new(pitch, yaw, roll)
+	}
+}
+
+impl<S: Scalar> ops::Neg for Quat<S> {
+	type Output = Quat<S>;
+
+	/// The same rotation, from the opposite side of the hypersphere.
+	fn neg(self) -> Quat<S> {
+		Quat { x: -self.x, y: -self.y, z: -self.z, s: -self.s }
+	}
 }
 
-impl ops::Mul<f32> for Rotation {
-	type Output = Rotation;
-	fn mul(mut self, rhs: f32) -> Rotation {
+impl<S: Scalar> From<Quat<S>> for Mat4<S> {
+	fn from(rotation: Quat<S>) -> Mat4<S> {
+		Mat4::default().r(rotation)
+	}
+}
+
+impl<S: Scalar> ops::Mul<S> for Quat<S> {
+	type Output = Quat<S>;
+	fn mul(mut self, rhs: S) -> Quat<S> {
 		self.s *= rhs;
 		self
 	}
 }
 
-impl ops::Mul<Rotation> for Rotation {
-	type Output = Rotation;
-	fn mul(self, rhs: Rotation) -> Rotation {
-		let a: &cgmath::Quaternion<f32> = self.as_ref().into();
-		let b: &cgmath::Quaternion<f32> = rhs.as_ref().into();
-		let q = a * b;
-
-		Rotation { x: q.v.x, y: q.v.y, z: q.v.z, s: q.s }
+impl<S: Scalar> ops::Mul<Quat<S>> for Quat<S> {
+	type Output = Quat<S>;
+	fn mul(self, rhs: Quat<S>) -> Quat<S> {
+		// Hamilton product.
+		Quat {
+			x: self.s * rhs.x + self.x * rhs.s + self.y * rhs.z
+				- self.z * rhs.y,
+			y: self.s * rhs.y - self.x * rhs.z + self.y * rhs.s
+				+ self.z * rhs.x,
+			z: self.s * rhs.z + self.x * rhs.y - self.y * rhs.x
+				+ self.z * rhs.s,
+			s: self.s * rhs.s - self.x * rhs.x - self.y * rhs.y
+				- self.z * rhs.z,
+		}
 	}
 }
 
-impl ops::MulAssign<Rotation> for Rotation {
-	fn mul_assign(&mut self, rhs: Rotation) {
+impl<S: Scalar> ops::MulAssign<Quat<S>> for Quat<S> {
+	fn mul_assign(&mut self, rhs: Quat<S>) {
 		*self = *self * rhs;
 	}
 }
 
-impl Default for Rotation {
+impl<S: Scalar> Default for Quat<S> {
 	fn default() -> Self {
 		Self::identity()
 	}
 }
 
-impl AsRef<[f32; 4]> for Rotation {
-	fn as_ref(&self) -> &[f32; 4] {
+impl<S> AsRef<[S; 4]> for Quat<S> {
+	fn as_ref(&self) -> &[S; 4] {
+		// Safe because `Quat` is `#[repr(C)]` with 4 fields of `S`.
 		unsafe {
-			mem::transmute(self)
+			&*(self as *const Quat<S> as *const [S; 4])
 		}
 	}
 }
 
-impl From<[f32; 4]> for Rotation {
-	fn from(q: [f32; 4]) -> Self {
-		Rotation { x: q[0], y: q[1], z: q[2], s: q[3] }
+impl<S: Copy> From<[S; 4]> for Quat<S> {
+	fn from(q: [S; 4]) -> Self {
+		Quat { x: q[0], y: q[1], z: q[2], s: q[3] }
 	}
 }
 
-impl Into<[f32; 4]> for Rotation {
-	fn into(self) -> [f32; 4] {
-		[self.x, self.y, self.z, self.s]
+impl<S> From<Quat<S>> for [S; 4] {
+	fn from(q: Quat<S>) -> [S; 4] {
+		[q.x, q.y, q.z, q.s]
+	}
+}
+
+impl From<Rotation> for DRotation {
+	fn from(rotation: Rotation) -> DRotation {
+		rotation.cast()
+	}
+}
+
+#[test]
+fn test_rotation_product() {
+	let y = vector!(0.0, 1.0, 0.0);
+	let q = Rotation::new(y, 0.5) * Rotation::new(y, 0.25);
+	let r = Rotation::new(y, 0.75);
+	assert!((q.x - r.x).abs() < 0.000_001);
+	assert!((q.y - r.y).abs() < 0.000_001);
+	assert!((q.z - r.z).abs() < 0.000_001);
+	assert!((q.s - r.s).abs() < 0.000_001);
+
+	// Euler angles apply yaw, then pitch, then roll.
+	let e = Rotation::euler(vector!(0.25, 0.5, 0.75));
+	let yaw = Rotation::new(y, 0.5);
+	let pitch = Rotation::new(vector!(1.0, 0.0, 0.0), 0.25);
+	let roll = Rotation::new(vector!(0.0, 0.0, 1.0), 0.75);
+	let p = vector!(1.0, 2.0, 3.0);
+	let v = roll.rotate(pitch.rotate(yaw.rotate(p)));
+	assert!((e.rotate(p) - v).length() < 0.0001);
+
+	// Quarter turn around X, then a quarter turn around Z.
+	let quarter = ::std::f64::consts::FRAC_PI_2;
+	let x = DRotation::new(DVector::new(1.0, 0.0, 0.0), quarter);
+	let z = DRotation::new(DVector::new(0.0, 0.0, 1.0), quarter);
+	let v = (z * x).rotate(DVector::new(0.0, 1.0, 0.0));
+	assert!((v - DVector::new(0.0, 0.0, 1.0)).length() < 1.0e-12);
+	assert_eq!(DRotation::from(Rotation::identity()), DRotation::identity());
+}
+
+#[test]
+fn test_rotation_then() {
+	let quarter = ::std::f32::consts::FRAC_PI_2;
+	let x = Rotation::new(vector!(1.0, 0.0, 0.0), quarter);
+	let z = Rotation::new(vector!(0.0, 0.0, 1.0), quarter);
+	let v = vector!(0.0, 1.0, 0.0);
+
+	// X first takes +Y to +Z, which Z leaves alone.
+	assert!(x.then(z).rotate(v).approx_eq(vector!(0.0, 0.0, 1.0), 0.0001));
+	assert!(x.then(z).rotate(v).approx_eq(z.rotate(x.rotate(v)), 0.0001));
+	// Z first takes +Y to -X, which X leaves alone.
+	assert!(z.then(x).rotate(v).approx_eq(vector!(-1.0, 0.0, 0.0), 0.0001));
+	// `then` matches the order of `Matrix` transforms.
+	let m = Matrix::from(x.then(z));
+	assert!(m.transform_vector(v).approx_eq(Matrix::default().r(x).r(z)
+		.transform_vector(v), 0.0001));
+}
+
+#[test]
+fn test_rotation_api() {
+	let close = |a: Rotation, b: Rotation| (a.dot(b).abs() - 1.0).abs()
+		< 0.0001;
+	let axis = vector!(1.0, 2.0, 3.0).normalize();
+	let r = Rotation::new(axis, 1.2);
+	let v = vector!(3.0, -1.0, 2.0);
+
+	assert!(r.inverse().rotate(r.rotate(v)).approx_eq(v, 0.0001));
+	assert!(close(r.conjugate(), r.inverse()));
+	let scaled = Rotation { x: r.x * 3.0, y: r.y * 3.0, z: r.z * 3.0,
+		s: r.s * 3.0 };
+	assert!(close(scaled.normalize(), r));
+	assert!(close(scaled.inverse() * scaled, Rotation::identity()));
+
+	let (a, angle) = r.to_axis_angle();
+	assert!(a.approx_eq(axis, 0.0001));
+	assert!((angle - 1.2).abs() < 0.0001);
+	assert_eq!(Rotation::identity().to_axis_angle().1, 0.0);
+
+	// Interpolation halfway is half of the angle.
+	let y = vector!(0.0, 1.0, 0.0);
+	let a = Rotation::new(y, 0.2);
+	let b = Rotation::new(y, 1.4);
+	assert!(close(a.slerp(b, 0.5), Rotation::new(y, 0.8)));
+	assert!(close(a.nlerp(b, 0.5), Rotation::new(y, 0.8)));
+	assert!(close(a.slerp(-b, 0.25), Rotation::new(y, 0.5)));
+
+	let angles = vector!(0.3, -1.1, 2.0);
+	assert!(Rotation::euler(angles).to_euler().approx_eq(angles, 0.0001));
+	let locked = Rotation::euler(vector!(::std::f32::consts::FRAC_PI_2, 0.0,
+		0.5));
+	assert!(close(Rotation::euler(locked.to_euler()), locked));
+
+	let from = vector!(1.0, 0.0, 0.0);
+	for to in [vector!(0.0, 0.0, 3.0), vector!(-1.0, 0.0, 0.0), from].iter() {
+		let q = Rotation::from_to(from, *to);
+		assert!(q.rotate(from).approx_eq(to.normalize(), 0.0001));
+	}
+
+	let forward = vector!(1.0, 0.0, 1.0).normalize();
+	let q = Rotation::look_rotation(forward, y);
+	assert!(q.rotate(vector!(0.0, 0.0, 1.0)).approx_eq(forward, 0.0001));
+	assert!(q.rotate(y).approx_eq(y, 0.0001));
+	let q = Rotation::look_rotation(y, y);
+	assert!(q.rotate(vector!(0.0, 0.0, 1.0)).approx_eq(y, 0.0001));
+
+	// Round trip through a matrix.
+	for q in [r, Rotation::new(y, 3.0), Rotation::new(axis, -2.9)].iter() {
+		assert!(close(Rotation::from_matrix(Matrix::from(*q)), *q));
 	}
 }
@@ -0,0 +1,124 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::{fmt, ops};
+
+/// A floating point type that the geometry types are generic over; either
+/// `f32` (single-precision) or `f64` (double-precision).
+pub trait Scalar: Copy + Default + PartialOrd + fmt::Debug + fmt::Display
+	+ ops::Add<Output = Self> + ops::Sub<Output = Self>
+	+ ops::Mul<Output = Self> + ops::Div<Output = Self>
+	+ ops::Neg<Output = Self> + ops::AddAssign + ops::SubAssign
+	+ ops::MulAssign + ops::DivAssign
+{
+	/// Convert from an `f64`, rounding if `Self` is less precise.
+	fn from_f64(value: f64) -> Self;
+
+	/// Convert into an `f64` (lossless).
+	fn into_f64(self) -> f64;
+
+	/// Convert to another `Scalar` type, rounding if it is less precise.
+	fn cast<T: Scalar>(self) -> T {
+		T::from_f64(self.into_f64())
+	}
+
+	/// 0
+	fn zero() -> Self;
+	/// 1
+	fn one() -> Self;
+	/// The difference between 1 and the next larger value.
+	fn epsilon() -> Self;
+	/// Positive infinity.
+	fn infinity() -> Self;
+
+	/// Absolute value.
+	fn abs(self) -> Self;
+	/// The smaller of two values.
+	fn min(self, other: Self) -> Self;
+	/// The larger of two values.
+	fn max(self, other: Self) -> Self;
+	/// Round down.
+	fn floor(self) -> Self;
+	/// Round up.
+	fn ceil(self) -> Self;
+	/// Round to the nearest integer, away from 0 at halfway.
+	fn round(self) -> Self;
+	/// 1, 0 or -1 for the sign (1 or -1 for ±0).
+	fn signum(self) -> Self;
+	/// 1 / `self`.
+	fn recip(self) -> Self;
+	/// Square root.
+	fn sqrt(self) -> Self;
+	/// Sine (of radians).
+	fn sin(self) -> Self;
+	/// Cosine (of radians).
+	fn cos(self) -> Self;
+	/// Sine and cosine (of radians).
+	fn sin_cos(self) -> (Self, Self) {
+		(self.sin(), self.cos())
+	}
+	/// Tangent (of radians).
+	fn tan(self) -> Self;
+	/// Arcsine, in radians.
+	fn asin(self) -> Self;
+	/// Arccosine, in radians.
+	fn acos(self) -> Self;
+	/// Four quadrant arctangent of `self` (y) and `other` (x), in radians.
+	fn atan2(self, other: Self) -> Self;
+	/// Not infinite or NaN.
+	fn is_finite(self) -> bool;
+}
+
+macro_rules! scalar {
+	($t:ident) => {
+		fn zero() -> Self { 0.0 }
+		fn one() -> Self { 1.0 }
+		fn epsilon() -> Self { $t::EPSILON }
+		fn infinity() -> Self { $t::INFINITY }
+
+		fn abs(self) -> Self { $t::abs(self) }
+		fn min(self, other: Self) -> Self { $t::min(self, other) }
+		fn max(self, other: Self) -> Self { $t::max(self, other) }
+		fn floor(self) -> Self { $t::floor(self) }
+		fn ceil(self) -> Self { $t::ceil(self) }
+		fn round(self) -> Self { $t::round(self) }
+		fn signum(self) -> Self { $t::signum(self) }
+		fn recip(self) -> Self { $t::recip(self) }
+		fn sqrt(self) -> Self { $t::sqrt(self) }
+		fn sin(self) -> Self { $t::sin(self) }
+		fn cos(self) -> Self { $t::cos(self) }
+		fn sin_cos(self) -> (Self, Self) { $t::sin_cos(self) }
+		fn tan(self) -> Self { $t::tan(self) }
+		fn asin(self) -> Self { $t::asin(self) }
+		fn acos(self) -> Self { $t::acos(self) }
+		fn atan2(self, other: Self) -> Self { $t::atan2(self, other) }
+		fn is_finite(self) -> bool { $t::is_finite(self) }
+	}
+}
+
+impl Scalar for f32 {
+	fn from_f64(value: f64) -> Self {
+		value as f32
+	}
+
+	fn into_f64(self) -> f64 {
+		f64::from(self)
+	}
+
+	scalar!(f32);
+}
+
+impl Scalar for f64 {
+	fn from_f64(value: f64) -> Self {
+		value
+	}
+
+	fn into_f64(self) -> f64 {
+		self
+	}
+
+	scalar!(f64);
+}
@@ -0,0 +1,387 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// A hierarchy of `Transform`s, with world-space bounds kept in an `Octree`.
+///
+/// Each node has a local `Transform` relative to its parent, and an optional
+/// local `BBox`.  Moving a node marks it and its descendants dirty; their
+/// world matrices are recalculated when needed, and their world `BBox`es are
+/// moved in the octree on `update()`.
+///
+/// The `Id` of a removed node is reused by the next node added, so an `Id`
+/// kept after its node is removed may refer to another node later.
+pub struct Scene<S: Scalar = f32> {
+	nodes: Vec<Option<SceneNode<S>>>,
+	garbage: Vec<Id>,
+	octree: Octree<Bounds<S>, S>,
+}
+
+struct SceneNode<S> {
+	local: Transform<S>,
+	bbox: Option<BBox<S>>,
+	parent: Option<Id>,
+	children: Vec<Id>,
+	// Cached world matrix, only valid if not dirty.
+	world: Mat4<S>,
+	dirty: bool,
+	// Where the world `BBox` is in the octree.
+	bounds: Option<Id>,
+}
+
+/// World-space `BBox` of a scene node, as stored in the octree.
+struct Bounds<S> {
+	node: Id,
+	bbox: BBox<S>,
+}
+
+impl<S: Scalar> Collider<S> for Bounds<S> {
+	fn bbox(&self) -> BBox<S> {
+		self.bbox
+	}
+}
+
+impl<S: Scalar> Default for Scene<S> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<S: Scalar> Scene<S> {
+	/// Create a new empty scene.
+	pub fn new() -> Scene<S> {
+		Scene { nodes: vec![], garbage: vec![], octree: Octree::new() }
+	}
+
+	/// Add a node as a child of `parent` (or at the top level if `None`),
+	/// with an optional `bbox` in the node's local space.
+	///
+	/// ## Panics
+	///
+	/// Panics if `parent` isn't in the scene (then the scene is unchanged).
+	pub fn add(&mut self, parent: Option<Id>, local: Transform<S>,
+		bbox: Option<BBox<S>>) -> Id
+	{
+		// Check first, so a bad parent doesn't leave an orphan behind.
+		if let Some(parent) = parent {
+			self.node(parent);
+		}
+
+		let node = SceneNode {
+			local,
+			bbox,
+			parent,
+			children: vec![],
+			world: Mat4::default(),
+			dirty: true,
+			bounds: None,
+		};
+
+		let id = if let Some(id) = self.garbage.pop() {
+			self.nodes[usize::from(id)] = Some(node);
+			id
+		} else {
+			self.nodes.push(Some(node));
+			Id(self.nodes.len() as u32)
+		};
+
+		if let Some(parent) = parent {
+			self.node_mut(parent).children.push(id);
+		}
+
+		id
+	}
+
+	/// Remove a node, and all of its descendants.  Their `Id`s are reused
+	/// by nodes added later.
+	///
+	/// ## Panics
+	///
+	/// Panics if `id` isn't in the scene.
+	pub fn remove(&mut self, id: Id) {
+		if let Some(parent) = self.node(id).parent {
+			self.node_mut(parent).children.retain(|child| *child != id);
+		}
+
+		let mut stack = vec![id];
+
+		while let Some(id) = stack.pop() {
+			let node = self.nodes[usize::from(id)].take()
+				.expect("no such scene node");
+
+			if let Some(bounds) = node.bounds {
+				self.octree.remove(bounds);
+			}
+
+			stack.extend(node.children);
+			self.garbage.push(id);
+		}
+	}
+
+	/// Get the number of nodes in the scene.
+	pub fn len(&self) -> usize {
+		self.nodes.len() - self.garbage.len()
+	}
+
+	/// Check if there are no nodes in the scene.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Get the parent of a node (`None` at the top level).
+	pub fn parent(&self, id: Id) -> Option<Id> {
+		self.node(id).parent
+	}
+
+	/// Get the children of a node.
+	pub fn children(&self, id: Id) -> &[Id] {
+		&self.node(id).children
+	}
+
+	/// Move a node (and its descendants) under a new `parent`, keeping its
+	/// local `Transform`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `parent` is the node or one of its descendants.
+	pub fn set_parent(&mut self, id: Id, parent: Option<Id>) {
+		let mut up = parent;
+		while let Some(ancestor) = up {
+			assert!(ancestor != id, "scene node can't be its own parent");
+			up = self.node(ancestor).parent;
+		}
+
+		if let Some(old) = self.node(id).parent {
+			self.node_mut(old).children.retain(|child| *child != id);
+		}
+		if let Some(parent) = parent {
+			self.node_mut(parent).children.push(id);
+		}
+
+		self.node_mut(id).parent = parent;
+		self.mark_dirty(id);
+	}
+
+	/// Get the local `Transform` of a node.
+	pub fn local(&self, id: Id) -> Transform<S> {
+		self.node(id).local
+	}
+
+	/// Set the local `Transform` of a node, which moves its descendants too.
+	pub fn set_local(&mut self, id: Id, local: Transform<S>) {
+		self.node_mut(id).local = local;
+		self.mark_dirty(id);
+	}
+
+	/// Set the local `BBox` of a node (`None` to leave it out of queries).
+	pub fn set_bbox(&mut self, id: Id, bbox: Option<BBox<S>>) {
+		self.node_mut(id).bbox = bbox;
+		self.mark_dirty(id);
+	}
+
+	/// Get the world matrix of a node (its local `Transform`, then its
+	/// parent's world matrix), recalculating it if it moved.
+	pub fn world_matrix(&mut self, id: Id) -> Mat4<S> {
+		self.update_node(id)
+	}
+
+	/// Get the world `BBox` of a node, if it has a local `BBox`.
+	pub fn world_bbox(&mut self, id: Id) -> Option<BBox<S>> {
+		self.update_node(id);
+
+		self.node(id).bounds.map(|bounds| self.octree[bounds].bbox)
+	}
+
+	/// Recalculate all nodes that moved, and move their world `BBox`es in
+	/// the octree.  Call before querying.
+	pub fn update(&mut self) {
+		for i in 0..self.nodes.len() {
+			let dirty = match self.nodes[i] {
+				Some(ref node) => node.dirty,
+				None => false,
+			};
+
+			if dirty {
+				self.update_node(i.into());
+			}
+		}
+	}
+
+	/// Get the nodes whose world `BBox` collides with `bbox`.  Nodes that
+	/// moved since `update()` may be missed.
+	pub fn query_bbox(&self, bbox: BBox<S>) -> Vec<Id> {
+		self.octree.query_bbox(bbox).into_iter()
+			.map(|bounds| self.octree[bounds].node)
+			.collect()
+	}
+
+	/// Get the nodes whose world `BBox` is hit by a ray from `origin` along
+	/// `direction`.  Nodes that moved since `update()` may be missed.
+	pub fn query_ray(&self, origin: Vec3<S>, direction: Vec3<S>)
+		-> Vec<Id>
+	{
+		self.octree.query_ray(origin, direction).into_iter()
+			.map(|bounds| self.octree[bounds].node)
+			.collect()
+	}
+
+	/// Mark a node and its descendants dirty.  Descendants of a dirty node
+	/// are always dirty, so stop there.
+	fn mark_dirty(&mut self, id: Id) {
+		let mut stack = vec![id];
+
+		while let Some(id) = stack.pop() {
+			let node = self.node_mut(id);
+
+			if !node.dirty {
+				node.dirty = true;
+				stack.extend(node.children.iter().cloned());
+			}
+		}
+	}
+
+	/// Recalculate the world matrix of a node (and its ancestors) if
+	/// dirty, and move its world `BBox` in the octree.
+	fn update_node(&mut self, id: Id) -> Mat4<S> {
+		if !self.node(id).dirty {
+			return self.node(id).world;
+		}
+
+		let parent = match self.node(id).parent {
+			Some(parent) => self.update_node(parent),
+			None => Mat4::default(),
+		};
+		let world = self.node(id).local.to_matrix().m(parent);
+
+		if let Some(bounds) = self.node(id).bounds {
+			self.octree.remove(bounds);
+		}
+		let bounds = self.node(id).bbox.map(|bbox| {
+			let bbox = bbox.transform(&world);
+
+			self.octree.add(Bounds { node: id, bbox })
+		});
+
+		let node = self.node_mut(id);
+		node.world = world;
+		node.bounds = bounds;
+		node.dirty = false;
+
+		world
+	}
+
+	fn node(&self, id: Id) -> &SceneNode<S> {
+		self.nodes.get(usize::from(id)).and_then(|node| node.as_ref())
+			.expect("no such scene node")
+	}
+
+	fn node_mut(&mut self, id: Id) -> &mut SceneNode<S> {
+		self.nodes.get_mut(usize::from(id))
+			.and_then(|node| node.as_mut())
+			.expect("no such scene node")
+	}
+}
+
+#[test]
+fn test_scene() {
+	let unit = BBox::new(vector!(-0.5), vector!(0.5));
+	let at = |p: Vector| Transform::new(p, Rotation::identity(),
+		Vector::from(1.0));
+	let mut scene = Scene::new();
+
+	let car = scene.add(None, at(vector!(10.0, 0.0, 0.0)), Some(unit));
+	let wheel = scene.add(Some(car), at(vector!(0.0, 0.0, 2.0)), Some(unit));
+	let tag = scene.add(Some(wheel), at(vector!(0.0, 1.0, 0.0)), None);
+	scene.update();
+
+	assert_eq!(scene.len(), 3);
+	assert_eq!(scene.children(car), &[wheel]);
+	assert_eq!(scene.query_bbox(BBox::new(vector!(10.0, 0.0, 2.0),
+		vector!(10.0, 0.0, 2.0))), vec![wheel]);
+	assert_eq!(scene.world_matrix(tag).translation(),
+		vector!(10.0, 1.0, 2.0));
+
+	// Moving and turning the parent moves the children.
+	let mut local = scene.local(car);
+	local.translation = vector!(-5.0, 0.0, 0.0);
+	local.rotation = Rotation::new(vector!(0.0, 1.0, 0.0),
+		::std::f32::consts::FRAC_PI_2);
+	scene.set_local(car, local);
+	scene.update();
+	assert!(scene.query_bbox(BBox::new(vector!(9.0), vector!(11.0)))
+		.is_empty());
+	let found = scene.query_bbox(BBox::new(vector!(-3.0, 0.0, 0.0),
+		vector!(-3.0, 0.0, 0.0)));
+	assert_eq!(found, vec![wheel]);
+	assert!(scene.world_matrix(tag).translation()
+		.approx_eq(vector!(-3.0, 1.0, 0.0), 0.0001));
+	let bbox = scene.world_bbox(wheel).unwrap();
+	assert!(bbox.center().approx_eq(vector!(-3.0, 0.0, 0.0), 0.0001));
+
+	// Reparent to the top level.
+	scene.set_parent(wheel, None);
+	assert_eq!(scene.parent(wheel), None);
+	assert!(scene.children(car).is_empty());
+	assert!(scene.world_matrix(tag).translation()
+		.approx_eq(vector!(0.0, 1.0, 2.0), 0.0001));
+	scene.update();
+	assert_eq!(scene.query_ray(vector!(0.0, 0.0, -10.0),
+		vector!(0.0, 0.0, 1.0)), vec![wheel]);
+
+	scene.remove(wheel);
+	assert_eq!(scene.len(), 1);
+
+	// A removed parent panics without adding anything.
+	let stale = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(
+		|| scene.add(Some(wheel), at(Vector::zero()), Some(unit))));
+	assert!(stale.is_err());
+	assert_eq!(scene.len(), 1);
+	assert!(scene.query_ray(vector!(0.0, 0.0, -10.0),
+		vector!(0.0, 0.0, 1.0)).is_empty());
+	let again = scene.add(Some(car), at(Vector::zero()), Some(unit));
+	scene.update();
+	assert_eq!(scene.query_bbox(BBox::new(vector!(-5.0, 0.0, 0.0),
+		vector!(-5.0, 0.0, 0.0))).len(), 2);
+	assert_eq!(scene.children(car), &[again]);
+}
+
+#[test]
+fn test_scene_moves() {
+	let unit = BBox::new(vector!(-0.5), vector!(0.5));
+	let at = |p: Vector| Transform::new(p, Rotation::identity(),
+		Vector::from(1.0));
+	let mut seed = 5u32;
+	let mut random = move || {
+		seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		// On a grid, so often on the center planes of the octree.
+		((seed >> 8) % 65) as f32 * 0.5 - 16.0
+	};
+	let mut scene = Scene::new();
+	let mut nodes: Vec<(Id, Vector)> = (0..300).map(|_| {
+		let p = vector!(random(), random(), random());
+
+		(scene.add(None, at(p), Some(unit)), p)
+	}).collect();
+
+	// Move them all around, back and forth across octant boundaries.
+	for _ in 0..10 {
+		scene.update();
+		for (id, p) in &nodes {
+			let found = scene.query_bbox(BBox::new(*p, *p));
+			assert!(found.contains(id));
+		}
+		for (id, p) in &mut nodes {
+			*p = vector!(random(), random(), random());
+			scene.set_local(*id, at(*p));
+		}
+	}
+
+	for (id, _) in nodes {
+		scene.remove(id);
+	}
+	assert!(scene.is_empty());
+}
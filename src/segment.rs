@@ -0,0 +1,104 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// Single-precision line segment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+	/// The start point of the segment.
+	pub a: Vector,
+	/// The end point of the segment.
+	pub b: Vector,
+}
+
+impl Segment {
+	/// Create a new line segment from `a` to `b`.
+	pub fn new(a: Vector, b: Vector) -> Segment {
+		Segment { a, b }
+	}
+
+	/// Get the point on the segment at `t` (`0.0` is `a`, `1.0` is `b`).
+	pub fn at(&self, t: f32) -> Vector {
+		self.a + (self.b - self.a) * t
+	}
+
+	/// Get the parameter `t` (clamped to `0.0..=1.0`) of the point on the
+	/// segment closest to `p`.
+	pub fn closest_t(&self, p: Vector) -> f32 {
+		let ab = self.b - self.a;
+		let len2 = ab.squared_length();
+
+		if len2 <= f32::EPSILON {
+			return 0.0;
+		}
+
+		((p - self.a).dot(ab) / len2).clamp(0.0, 1.0)
+	}
+
+	/// Get the point on the segment closest to `p`.
+	pub fn closest_point(&self, p: Vector) -> Vector {
+		self.at(self.closest_t(p))
+	}
+
+	/// Get the squared distance from the segment to `p`.
+	pub fn squared_distance(&self, p: Vector) -> f32 {
+		(p - self.closest_point(p)).squared_length()
+	}
+
+	/// Get the closest pair of points between two segments.  The first
+	/// point is on `self`, the second on `other`.
+	pub fn closest_points(&self, other: Segment) -> (Vector, Vector) {
+		let d1 = self.b - self.a;
+		let d2 = other.b - other.a;
+		let r = self.a - other.a;
+		let a = d1.squared_length();
+		let e = d2.squared_length();
+		let f = d2.dot(r);
+
+		let (s, t) = if a <= f32::EPSILON && e <= f32::EPSILON {
+			// Both segments are points.
+			(0.0, 0.0)
+		} else if a <= f32::EPSILON {
+			// `self` is a point.
+			(0.0, (f / e).clamp(0.0, 1.0))
+		} else {
+			let c = d1.dot(r);
+
+			if e <= f32::EPSILON {
+				// `other` is a point.
+				((-c / a).clamp(0.0, 1.0), 0.0)
+			} else {
+				let b = d1.dot(d2);
+				let denom = a * e - b * b;
+				// Parallel segments pick any `s`.
+				let s = if denom != 0.0 {
+					((b * f - c * e) / denom).clamp(0.0, 1.0)
+				} else {
+					0.0
+				};
+				let t = (b * s + f) / e;
+
+				if t < 0.0 {
+					((-c / a).clamp(0.0, 1.0), 0.0)
+				} else if t > 1.0 {
+					(((b - c) / a).clamp(0.0, 1.0), 1.0)
+				} else {
+					(s, t)
+				}
+			}
+		};
+
+		(self.a + d1 * s, other.a + d2 * t)
+	}
+
+	/// Get the squared distance between two segments.
+	pub fn squared_distance_segment(&self, other: Segment) -> f32 {
+		let (a, b) = self.closest_points(other);
+
+		(b - a).squared_length()
+	}
+}
@@ -0,0 +1,151 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use arena::Shared;
+use octree::Node;
+use std::sync::Arc;
+use tree;
+use *;
+
+/// An immutable view of an `Octree`, published with `Octree::snapshot()`.
+///
+/// A snapshot shares the parts of the octree that didn't change since the last
+/// snapshot, so it's cheap to publish every tick, and cheap to clone.  Other
+/// threads can query it while the octree keeps changing.  It has the `BBox` of
+/// each collider from when it was added, but not the colliders themselves.
+#[derive(Clone)]
+pub struct OctreeSnapshot<S: Scalar = f32> {
+	pub(crate) nodes: Shared<Node>,
+	pub(crate) bboxes: Shared<Option<BBox<S>>>,
+	pub(crate) bcube: BCube<S>,
+	pub(crate) looseness: S,
+	pub(crate) overflow: Arc<Vec<Id>>,
+	pub(crate) root: Id,
+	pub(crate) len: usize,
+}
+
+impl<S: Scalar> OctreeSnapshot<S> {
+	/// Get the number of colliders in the snapshot.
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Check if there are no colliders in the snapshot.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Get the `BBox` of a collider, or `None` if it isn't in the snapshot.
+	pub fn bbox(&self, id: Id) -> Option<BBox<S>> {
+		if id.is_none() || usize::from(id) >= self.bboxes.len() {
+			return None;
+		}
+
+		self.bboxes[usize::from(id)]
+	}
+
+	/// Get the `Id`s of all colliders whose `BBox` collides with `bbox`.
+	pub fn query_bbox(&self, bbox: BBox<S>) -> Vec<Id> {
+		self.query(
+			|bcube| bbox.collide_bcube(bcube),
+			|other| bbox.collide(other),
+		)
+	}
+
+	/// Get the `Id`s of all colliders whose `BBox` is hit by a ray from
+	/// `origin` along `direction`.
+	pub fn query_ray(&self, origin: Vec3<S>, direction: Vec3<S>)
+		-> Vec<Id>
+	{
+		self.query(
+			|bcube| bcube.to_bbox().intersect_ray(origin, direction)
+				.is_some(),
+			|other| other.intersect_ray(origin, direction)
+				.is_some(),
+		)
+	}
+
+	/// Get each pair of colliders whose `BBox`es collide, with the lesser
+	/// `Id` first.
+	pub fn collision_pairs(&self) -> Vec<(Id, Id)> {
+		self.query(|_| true, |_| true).into_iter()
+			.flat_map(|id| {
+				let bbox = self.bboxes[usize::from(id)].unwrap();
+
+				self.query_bbox(bbox).into_iter()
+					.filter(move |other| id.0 < other.0)
+					.map(move |other| (id, other))
+			})
+			.collect()
+	}
+
+	/// Walk the nodes that pass `node_test`, collecting the colliders
+	/// (and those in the overflow list) whose `BBox` passes `bbox_test`.
+	fn query<F, G>(&self, node_test: F, bbox_test: G) -> Vec<Id>
+		where F: Fn(BCube<S>) -> bool, G: Fn(BBox<S>) -> bool
+	{
+		let bbox_of = |id: Id| self.bboxes[usize::from(id)].unwrap();
+		let mut found = if self.root.is_none() {
+			vec![]
+		} else {
+			tree::walk(&self.nodes, self.looseness,
+				vec![(self.root, self.bcube)], node_test,
+				|id| bbox_test(bbox_of(id)))
+		};
+
+		found.extend(self.overflow.iter()
+			.filter(|id| bbox_test(bbox_of(**id))));
+		found
+	}
+}
+
+#[test]
+fn test_octree_snapshot() {
+	use std::sync::Arc;
+
+	struct Test(BBox);
+
+	impl Collider for Test {
+		fn bbox(&self) -> BBox {
+			self.0
+		}
+	}
+
+	let at = |x: f32| BBox::new(vector!(x, 0.0, 0.0), vector!(x + 0.5));
+	let mut octree = Octree::new();
+	let ids: Vec<Id> = (0..600).map(|i| octree.add(Test(at(i as f32))))
+		.collect();
+	let before = octree.snapshot();
+	let q = BBox::new(vector!(10.2, 0.0, 0.0), vector!(12.2, 0.0, 0.0));
+	assert_eq!(before.query_bbox(q), octree.query_bbox(q));
+
+	// Change the octree; the snapshot stays the same.
+	octree.remove(ids[11]);
+	let moved = octree.add(Test(at(1000.0)));
+	let extra = octree.add(Test(at(10.25)));
+	let after = octree.snapshot();
+	let mut found = before.query_bbox(q);
+	found.sort_by_key(|id| id.0);
+	assert_eq!(found, vec![ids[10], ids[11], ids[12]]);
+	assert_eq!(after.query_bbox(q).len(), 3);
+	assert_eq!(after.query_ray(vector!(1000.2, 0.2, -5.0),
+		vector!(0.0, 0.0, 1.0)), vec![moved]);
+	assert_eq!(after.bbox(moved).unwrap().center(), at(1000.0).center());
+	assert_eq!(before.bbox(moved).unwrap().center(), at(11.0).center());
+	assert!(before.bbox(extra).is_none());
+	assert_eq!((before.len(), after.len()), (600, 601));
+	assert_eq!(after.collision_pairs(), vec![(ids[10], extra)]);
+
+	// Only the changed chunk of `BBox`es was copied.
+	let (old, new) = (&before.bboxes.chunks, &after.bboxes.chunks);
+	assert!(!Arc::ptr_eq(&old[0], &new[0]));
+	assert!(Arc::ptr_eq(&old[1], &new[1]));
+
+	// Query from another thread while the octree changes.
+	let reader = ::std::thread::spawn(move || after.query_bbox(q).len());
+	octree.clear();
+	assert_eq!(reader.join().unwrap(), 3);
+}
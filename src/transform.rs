@@ -0,0 +1,149 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// Scale, then rotation, then translation (the same order as `Matrix::srt`),
+/// single-precision unless `S` is `f64`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform<S = f32> {
+	/// Translation, applied last.
+	pub translation: Vec3<S>,
+	/// Rotation, applied after scale.
+	pub rotation: Quat<S>,
+	/// Scale, applied first.
+	pub scale: Vec3<S>,
+}
+
+impl<S: Scalar> Default for Transform<S> {
+	fn default() -> Self {
+		Transform::identity()
+	}
+}
+
+impl<S: Scalar> Transform<S> {
+	/// Create a new `Transform`.
+	pub fn new(translation: Vec3<S>, rotation: Quat<S>, scale: Vec3<S>)
+		-> Self
+	{
+		Transform { translation, rotation, scale }
+	}
+
+	/// Create a `Transform` that doesn't change anything.
+	pub fn identity() -> Self {
+		Transform::new(Vec3::zero(), Quat::identity(), Vec3::from(S::one()))
+	}
+
+	/// Create a `Transform` from a `Matrix`, with `Matrix::decompose()`.
+	/// `None` if the matrix has a projection, shear or zero scale.
+	pub fn from_matrix(matrix: Mat4<S>) -> Option<Self> {
+		matrix.decompose()
+			.map(|(scale, rotation, translation)| {
+				Transform::new(translation, rotation, scale)
+			})
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Transform<T> {
+		Transform {
+			translation: self.translation.cast(),
+			rotation: self.rotation.cast(),
+			scale: self.scale.cast(),
+		}
+	}
+
+	/// Get the `Matrix`, which is `Matrix::default().srt(..)`.
+	pub fn to_matrix(self) -> Mat4<S> {
+		Mat4::default().srt(self.scale, self.rotation, self.translation)
+	}
+
+	/// Add another `Transform` after this one (such as a parent's), so that
+	/// `a.then(b).transform_point(p)` is
+	/// `b.transform_point(a.transform_point(p))`.  Exact when `rhs` has a
+	/// uniform scale; otherwise the shear can't be kept, and is dropped.
+	pub fn then(self, rhs: Self) -> Self {
+		Transform {
+			translation: rhs.transform_point(self.translation),
+			rotation: self.rotation.then(rhs.rotation),
+			scale: self.scale * rhs.scale,
+		}
+	}
+
+	/// Get the `Transform` that undoes this one.  Exact when the scale is
+	/// uniform.
+	pub fn inverse(self) -> Self {
+		let scale = Vec3::from(S::one()) / self.scale;
+		let rotation = self.rotation.inverse();
+
+		Transform {
+			translation: -(scale * rotation.rotate(self.translation)),
+			rotation,
+			scale,
+		}
+	}
+
+	/// Transform point `p`.
+	pub fn transform_point(&self, p: Vec3<S>) -> Vec3<S> {
+		self.translation + self.transform_vector(p)
+	}
+
+	/// Transform direction `v`, ignoring translation.
+	pub fn transform_vector(&self, v: Vec3<S>) -> Vec3<S> {
+		self.rotation.rotate(self.scale * v)
+	}
+
+	/// Interpolate from `self` (`t` is 0) to `rhs` (`t` is 1), with
+	/// `Rotation::slerp()` for the rotation.
+	pub fn interpolate(self, rhs: Self, t: S) -> Self {
+		Transform {
+			translation: self.translation.lerp(rhs.translation, t),
+			rotation: self.rotation.slerp(rhs.rotation, t),
+			scale: self.scale.lerp(rhs.scale, t),
+		}
+	}
+}
+
+impl<S: Scalar> From<Transform<S>> for Mat4<S> {
+	fn from(transform: Transform<S>) -> Mat4<S> {
+		transform.to_matrix()
+	}
+}
+
+impl From<Transform> for Transform<f64> {
+	fn from(transform: Transform) -> Transform<f64> {
+		transform.cast()
+	}
+}
+
+#[test]
+fn test_transform() {
+	let a = Transform::new(vector!(1.0, 2.0, 3.0),
+		Rotation::euler(vector!(0.3, -1.0, 0.2)), vector!(2.0, 0.5, 1.0));
+	let b = Transform::new(vector!(-4.0, 0.0, 1.0),
+		Rotation::new(vector!(0.0, 1.0, 0.0), 1.1), Vector::from(3.0));
+	let p = vector!(0.5, -2.0, 4.0);
+
+	assert!(a.transform_point(p).approx_eq(a.to_matrix().transform_point(p),
+		0.0001));
+	assert!(a.transform_vector(p).approx_eq(Matrix::from(a)
+		.transform_vector(p), 0.0001));
+	assert!(a.then(b).transform_point(p).approx_eq(
+		b.transform_point(a.transform_point(p)), 0.0001));
+	assert!(b.inverse().transform_point(b.transform_point(p))
+		.approx_eq(p, 0.0001));
+	assert!(b.then(b.inverse()).transform_point(p).approx_eq(p, 0.0001));
+
+	let c = Transform::from_matrix(a.to_matrix()).unwrap();
+	assert!(c.transform_point(p).approx_eq(a.transform_point(p), 0.0001));
+
+	assert_eq!(a.interpolate(b, 0.0).translation, a.translation);
+	let half = a.interpolate(b, 0.5);
+	assert_eq!(half.translation, vector!(-1.5, 1.0, 2.0));
+	assert_eq!(half.scale, vector!(2.5, 1.75, 2.0));
+	assert!((half.rotation.dot(a.rotation.slerp(b.rotation, 0.5)) - 1.0)
+		.abs() < 0.0001);
+	assert_eq!(Transform::default().transform_point(p), p);
+}
@@ -0,0 +1,1242 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use arena::Arena;
+use node::{self, Id, LINK};
+use std::{fmt, ops};
+use *;
+
+const MIN_HALF_LEN: f64 = 0.1;		// smallest branch that can subdivide
+const MAX_IDS: usize = node::LEAF as usize - 1;	// most nodes, colliders
+const MAX_NEW_NODES: usize = 4096;	// most nodes one add can make
+
+/// The bounds of a collider in a `Tree`: a `BBox` or a `BRect`.
+pub(crate) trait Bounds<S: Scalar>: Copy + fmt::Debug {
+	/// Get the least value on an axis.
+	fn min_on(&self, axis: usize) -> S;
+
+	/// Get the greatest value on an axis.
+	fn max_on(&self, axis: usize) -> S;
+
+	/// Get the smallest bounds containing both `self` and `other`.
+	fn union(&self, other: Self) -> Self;
+
+	/// Check if the bounds collide with `other`.
+	fn collide(&self, other: Self) -> bool;
+
+	/// Get the center on an axis.
+	fn center_on(&self, axis: usize) -> S {
+		(self.min_on(axis) + self.max_on(axis)) / S::from_f64(2.0)
+	}
+}
+
+/// The cell of a node in a `Tree`, which is split in half on every axis for
+/// its child branches: a `BCube` or a `BSquare`.
+pub(crate) trait Cell<S: Scalar>: Copy + PartialEq + fmt::Debug {
+	/// The bounds of the colliders in it.
+	type Bounds: Bounds<S>;
+
+	/// Number of axes (there are `1 << AXES` child branches).
+	const AXES: usize;
+
+	/// Create a cell from its center on each axis, and its half length.
+	fn from_center<F: Fn(usize) -> S>(center: F, half_len: S) -> Self;
+
+	/// Get the center on an axis.
+	fn center_on(&self, axis: usize) -> S;
+
+	/// Get the half length.
+	fn half_len(&self) -> S;
+
+	/// Double the size of the cell, toward `bounds`.
+	fn grow(&mut self, bounds: Self::Bounds);
+
+	/// Turn into bounds.
+	fn to_bounds(&self) -> Self::Bounds;
+
+	/// Check if `bounds` collides with the cell.
+	fn collide(&self, bounds: Self::Bounds) -> bool;
+
+	/// Check if `bounds` is entirely within the cell.
+	fn contains(&self, bounds: Self::Bounds) -> bool;
+
+	/// Create an empty cell.
+	fn empty() -> Self {
+		Self::from_center(|_| S::zero(), -S::one())
+	}
+
+	/// Get the cell of child branch `ch`.
+	///
+	/// ## Panics
+	///
+	/// Panics if the cell is too small to subdivide.
+	fn child(&self, ch: usize) -> Self {
+		assert!(self.half_len() > S::from_f64(MIN_HALF_LEN));
+		child_cell(*self, ch)
+	}
+
+	/// Get the cell enlarged by `looseness`.
+	fn loose(&self, looseness: S) -> Self {
+		Self::from_center(|axis| self.center_on(axis),
+			self.half_len() * looseness)
+	}
+}
+
+impl<S: Scalar> Bounds<S> for BBox<S> {
+	fn min_on(&self, axis: usize) -> S {
+		self.min[axis]
+	}
+
+	fn max_on(&self, axis: usize) -> S {
+		self.max[axis]
+	}
+
+	fn union(&self, other: BBox<S>) -> BBox<S> {
+		BBox::union(self, other)
+	}
+
+	fn collide(&self, other: BBox<S>) -> bool {
+		BBox::collide(self, other)
+	}
+}
+
+impl<S: Scalar> Bounds<S> for BRect<S> {
+	fn min_on(&self, axis: usize) -> S {
+		self.min[axis]
+	}
+
+	fn max_on(&self, axis: usize) -> S {
+		self.max[axis]
+	}
+
+	fn union(&self, other: BRect<S>) -> BRect<S> {
+		BRect::union(self, other)
+	}
+
+	fn collide(&self, other: BRect<S>) -> bool {
+		BRect::collide(self, other)
+	}
+}
+
+impl<S: Scalar> Cell<S> for BCube<S> {
+	type Bounds = BBox<S>;
+
+	const AXES: usize = 3;
+
+	fn from_center<F: Fn(usize) -> S>(center: F, half_len: S) -> Self {
+		BCube {
+			center: Vec3::new(center(0), center(1), center(2)),
+			half_len,
+		}
+	}
+
+	fn center_on(&self, axis: usize) -> S {
+		self.center[axis]
+	}
+
+	fn half_len(&self) -> S {
+		self.half_len
+	}
+
+	fn grow(&mut self, bounds: BBox<S>) {
+		self.extend(bounds)
+	}
+
+	fn to_bounds(&self) -> BBox<S> {
+		self.to_bbox()
+	}
+
+	fn collide(&self, bounds: BBox<S>) -> bool {
+		bounds.collide_bcube(*self)
+	}
+
+	fn contains(&self, bounds: BBox<S>) -> bool {
+		bounds.within_bcube(*self)
+	}
+}
+
+impl<S: Scalar> Cell<S> for BSquare<S> {
+	type Bounds = BRect<S>;
+
+	const AXES: usize = 2;
+
+	fn from_center<F: Fn(usize) -> S>(center: F, half_len: S) -> Self {
+		BSquare { center: Vec2::new(center(0), center(1)), half_len }
+	}
+
+	fn center_on(&self, axis: usize) -> S {
+		self.center[axis]
+	}
+
+	fn half_len(&self) -> S {
+		self.half_len
+	}
+
+	fn grow(&mut self, bounds: BRect<S>) {
+		self.extend(bounds)
+	}
+
+	fn to_bounds(&self) -> BRect<S> {
+		self.to_brect()
+	}
+
+	fn collide(&self, bounds: BRect<S>) -> bool {
+		bounds.collide_bsquare(*self)
+	}
+
+	fn contains(&self, bounds: BRect<S>) -> bool {
+		bounds.within_bsquare(*self)
+	}
+}
+
+/// Get the cell of child branch `ch`, which is on the greater side of the
+/// center on each axis whose bit is set (the first axis is the most
+/// significant).
+fn child_cell<C: Cell<S>, S: Scalar>(cell: C, ch: usize) -> C {
+	let half_len = cell.half_len() / S::from_f64(2.0);
+
+	C::from_center(|axis| {
+		if ch >> (C::AXES - 1 - axis) & 1 == 1 {
+			cell.center_on(axis) + half_len
+		} else {
+			cell.center_on(axis) - half_len
+		}
+	}, half_len)
+}
+
+/// Determine which side of a center plane at `c` the span `lo..hi` is on
+/// (true for the lesser side), if it's all on one side.  On a tie, it goes
+/// on the greater side, unless it reaches down past the plane.
+fn which_side<S: Scalar>(c: S, lo: S, hi: S) -> Option<bool> {
+	if lo >= c {
+		Some(false)
+	} else if hi <= c {
+		Some(true)
+	} else {
+		None
+	}
+}
+
+/// Determine which child of `cell` `bounds` fully fits into, if it's on one
+/// side of the center on every axis.
+fn which_child_exact<C: Cell<S>, S: Scalar>(cell: C, bounds: C::Bounds)
+	-> Option<usize>
+{
+	let mut ch = 0;
+
+	for axis in 0..C::AXES {
+		let lesser = which_side(cell.center_on(axis),
+			bounds.min_on(axis), bounds.max_on(axis))?;
+		ch = ch << 1 | !lesser as usize;
+	}
+
+	Some(ch)
+}
+
+/// The tree that `Octree` and `Quadtree` are built on, with `B` child
+/// branches per node, each with a cell `C` (`BCube` or `BSquare`).  It has
+/// the `Id`s and stored bounds of the colliders, but not the colliders.
+pub(crate) struct Tree<C: Cell<S>, S: Scalar, const B: usize> {
+	// The bounds of each collider when it was added.
+	pub(crate) stored: Arena<Option<C::Bounds>>,
+	pub(crate) collider_garbage: Vec<Id>,
+	pub(crate) nodes: Arena<node::Node<B>>,
+	pub(crate) garbage: Vec<Id>,
+	// Whether each node is in `garbage`.
+	pub(crate) is_garbage: Vec<bool>,
+	pub(crate) cell: C,
+	pub(crate) looseness: S,
+	// Largest half length of the root, if limited.
+	pub(crate) max_root: Option<S>,
+	// The root cell, if fixed.
+	pub(crate) bounds: Option<C>,
+	// Colliders that don't fit under the largest (or fixed) root.
+	pub(crate) overflow: Vec<Id>,
+	pub(crate) root: Id,
+	pub(crate) n_colliders: u32,
+}
+
+impl<C, S, const B: usize> Tree<C, S, B> where C: Cell<S>, S: Scalar {
+	/// Create a new empty tree.
+	pub(crate) fn new() -> Self {
+		Tree {
+			stored: Arena::new(),
+			collider_garbage: vec![],
+			nodes: Arena::new(),
+			garbage: vec![],
+			is_garbage: vec![],
+			cell: C::empty(),
+			looseness: S::one(),
+			max_root: None,
+			bounds: None,
+			overflow: vec![],
+			root: Id::none(),
+			n_colliders: 0,
+		}
+	}
+
+	/// Create a new loose tree, where the cell of each branch is enlarged
+	/// by `looseness`.
+	///
+	/// ## Panics
+	///
+	/// Panics if `looseness` is less than 1.
+	pub(crate) fn loose(looseness: S) -> Self {
+		assert!(looseness >= S::one(), "looseness must be at least 1");
+
+		Tree { looseness, ..Self::new() }
+	}
+
+	/// Create a new tree with a fixed root cell, which never grows or
+	/// shrinks.
+	///
+	/// ## Panics
+	///
+	/// Panics if `bounds` is empty.
+	pub(crate) fn with_bounds(bounds: C) -> Self {
+		assert!(bounds.half_len() > S::zero(),
+			"bounds must not be empty");
+
+		Tree { bounds: Some(bounds), cell: bounds, ..Self::new() }
+	}
+
+	/// Remove everything, keeping the looseness and the limits of the
+	/// root.
+	pub(crate) fn clear(&mut self) {
+		*self = Tree {
+			looseness: self.looseness,
+			max_root: self.max_root,
+			bounds: self.bounds,
+			cell: self.bounds.unwrap_or_else(C::empty),
+			..Self::new()
+		};
+	}
+
+	/// Determine which child of a branch `bounds` fully fits into, if any.
+	fn which_child(&self, cell: C, bounds: C::Bounds) -> Option<usize> {
+		if self.looseness == S::one() {
+			which_child_exact(cell, bounds)
+		} else {
+			self.which_child_loose(cell, bounds)
+		}
+	}
+
+	/// Determine which child of a loose branch `bounds` goes in (by its
+	/// center), if it fits within the enlarged cell of that child.
+	fn which_child_loose(&self, cell: C, bounds: C::Bounds)
+		-> Option<usize>
+	{
+		let ch = (0..C::AXES).fold(0, |ch, axis| {
+			let greater = bounds.center_on(axis)
+				>= cell.center_on(axis);
+
+			ch << 1 | greater as usize
+		});
+
+		if child_cell(cell, ch).loose(self.looseness).contains(bounds) {
+			Some(ch)
+		} else {
+			None
+		}
+	}
+
+	/// Get the enlarged cell of a branch, which contains all colliders in
+	/// it and its descendants.
+	pub(crate) fn loose_cell(&self, cell: C) -> C {
+		cell.loose(self.looseness)
+	}
+
+	/// Add the bounds of a collider, and get its `Id`, or fail if the
+	/// bounds are invalid, or the tree is full.
+	pub(crate) fn add(&mut self, bounds: C::Bounds)
+		-> Result<Id, OctreeError>
+	{
+		self.check_bounds(bounds)?;
+		if self.collider_garbage.is_empty()
+			&& self.stored.len() >= MAX_IDS
+		{
+			return Err(OctreeError::CapacityExhausted);
+		}
+		self.check_nodes()?;
+
+		// Get the id.
+		let id = if let Some(id) = self.collider_garbage.pop() {
+			self.stored[usize::from(id)] = Some(bounds);
+			id
+		} else {
+			self.stored.push(Some(bounds));
+			Id(self.stored.len() as u32)
+		};
+
+		if let Err(e) = self.insert_id(id) {
+			// Take it back out of the overflow list.
+			self.remove(id)?;
+			return Err(e);
+		}
+
+		Ok(id)
+	}
+
+	/// Check that bounds are finite, with room to grow the root around them
+	/// (unless the root is fixed), and that their minimum isn't greater
+	/// than their maximum.
+	fn check_bounds(&self, bounds: C::Bounds) -> Result<(), OctreeError> {
+		let room = if self.bounds.is_some() {
+			// Never grows, anything outside goes in the overflow.
+			S::one()
+		} else {
+			S::from_f64(4.0)
+		};
+		let valid = (0..C::AXES).all(|axis| {
+			let min = bounds.min_on(axis);
+			let max = bounds.max_on(axis);
+
+			(min * room).is_finite() && (max * room).is_finite()
+				&& min <= max
+		});
+
+		if valid {
+			Ok(())
+		} else {
+			Err(OctreeError::InvalidBounds)
+		}
+	}
+
+	/// Check that there are enough node `Id`s left to add a collider.
+	fn check_nodes(&self) -> Result<(), OctreeError> {
+		if self.nodes.len() + MAX_NEW_NODES > MAX_IDS {
+			Err(OctreeError::CapacityExhausted)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Put a collider Id in the tree, at its stored bounds, or in the
+	/// overflow list if it doesn't fit.  If the tree is corrupt so that it
+	/// can't go where it fits, it goes in the overflow list anyway, so it's
+	/// still found, and the error is returned.
+	fn insert_id(&mut self, id: Id) -> Result<(), OctreeError> {
+		// Find position in tree for this new collider.
+		let placed = if !self.fits(self.stored(id)) {
+			self.overflow.push(id);
+			Ok(())
+		} else if self.root.is_none() {
+			self.add_0(id)
+		} else {
+			self.add_n(id)
+		};
+
+		if placed.is_err() {
+			self.overflow.push(id);
+		}
+
+		// Increment number of colliders
+		self.n_colliders += 1;
+
+		placed
+	}
+
+	/// Check if bounds fit in the tree without growing the root past
+	/// `max_root`, or are within the fixed root.
+	fn fits(&self, bounds: C::Bounds) -> bool {
+		if let Some(root) = self.bounds {
+			return Self::within_root(bounds, root);
+		}
+
+		if self.max_root.is_none() {
+			return true;
+		}
+
+		if self.root.is_none() {
+			let cell = Self::root_cell(bounds);
+
+			return self.within_max(cell.half_len());
+		}
+
+		// Grow a copy of the root cell, like `grow_root()`.
+		let mut cell = self.cell;
+		while !Self::within_root(bounds, cell) {
+			cell.grow(bounds);
+			if !self.within_max(cell.half_len()) {
+				return false;
+			}
+		}
+
+		true
+	}
+
+	/// Check if a root with half length `half_len` is within `max_root`.
+	fn within_max(&self, half_len: S) -> bool {
+		match self.max_root {
+			Some(max) => half_len <= max,
+			None => true,
+		}
+	}
+
+	/// Add a collider when empty
+	fn add_0(&mut self, id: Id) -> Result<(), OctreeError> {
+		// Clear the nodes
+		self.reset_nodes();
+
+		// Make the root cell contain the bounds of this first collider,
+		// unless it's fixed.
+		self.cell = match self.bounds {
+			Some(root) => root,
+			None => Self::root_cell(self.stored(id)),
+		};
+
+		// Build the branch and add a collider.
+		let i = self.new_branch();
+		self.nodes[usize::from(i)].branch_add_collider(id)
+			.ok_or(OctreeError::Corrupt)?;
+
+		// Set this branch as the root node.
+		self.root = i;
+
+		Ok(())
+	}
+
+	/// Check if `bounds` can go under a root `cell`: they're within it, and
+	/// not flat on one of its greater faces.  Those are on the greater side
+	/// of the center plane if the root grows past the face (see
+	/// `which_side()`), so wouldn't be found under the old root.
+	fn within_root(bounds: C::Bounds, cell: C) -> bool {
+		let max = |axis| cell.center_on(axis) + cell.half_len();
+
+		cell.contains(bounds)
+			&& (0..C::AXES).all(|a| bounds.min_on(a) < max(a))
+	}
+
+	/// Get a root cell containing `bounds`, aligned to a power of two grid
+	/// so that the centers of grown roots and children are exact.
+	fn root_cell(bounds: C::Bounds) -> C {
+		let size = (0..C::AXES)
+			.map(|axis| bounds.max_on(axis) - bounds.min_on(axis))
+			.fold(S::zero(), |size, span| size.max(span))
+			.into_f64()
+			.max(MIN_HALF_LEN);
+		let half_len = S::from_f64(2.0f64
+			.powi(size.log2().ceil() as i32));
+		let snap = |x: S| (x / half_len).round() * half_len;
+
+		C::from_center(|axis| snap(bounds.center_on(axis)), half_len)
+	}
+
+	/// Add a collider when not empty
+	fn add_n(&mut self, id: Id) -> Result<(), OctreeError> {
+		// Get bounds
+		let bounds = self.stored(id);
+
+		// While the bounds aren't within the root cell, expand it
+		while !Self::within_root(bounds, self.cell) {
+			self.grow_root(bounds)?;
+		}
+
+		// Add id inside the root cell.
+		let cell = self.cell;
+		let root = self.root;
+		self.add_inside(id, root, cell)
+	}
+
+	/// Grow the root node, with the old root as one of its children.
+	fn grow_root(&mut self, bounds: C::Bounds) -> Result<(), OctreeError> {
+		let old = self.cell;
+		let mut cell = old;
+
+		// Extend cell to attempt to accomodate for bounds.
+		// This function is limited to growing twice in size.
+		cell.grow(bounds);
+
+		// Create new container branch for old root branch.
+		let ch = which_child_exact(cell, old.to_bounds())
+			.ok_or(OctreeError::Corrupt)?;
+		let id = self.new_branch();
+		self.nodes[usize::from(id)].child[ch] = self.root;
+		self.root = id;
+		self.cell = cell;
+
+		Ok(())
+	}
+
+	/// Add a collider within the cell of a branch
+	fn add_inside(&mut self, id: Id, node_id: Id, cell: C)
+		-> Result<(), OctreeError>
+	{
+		// Calculate bounds for this id.
+		let bounds = self.stored(id);
+		// Convert node_id to usize for indexing.
+		let node_id: usize = node_id.into();
+
+		// Bounds must collide with cell, and be in a branch.
+		if !self.loose_cell(cell).collide(bounds)
+			|| !self.nodes[node_id].is_branch()
+		{
+			return Err(OctreeError::Corrupt);
+		}
+
+		// If there's already a branch it fits in, add it there.
+		if let Some(ch) = self.which_child(cell, bounds) {
+			let j = self.nodes[node_id].child[ch];
+
+			if j.is_some() {
+				return self.add_inside(id, j, cell.child(ch));
+			}
+		}
+
+		// Attempt to add at root first.  Test is full
+		if self.nodes[node_id].branch_add_collider(id).is_none() {
+			// Attempt to push relative root colliders down the tree
+			for i in B..=14 {
+				let collider = self.nodes[node_id].child[i];
+				if self.add_down(collider, node_id, cell)? {
+					// If it successfully pushed it the
+					// collider down the tree, remove it
+					// from it's old location.
+					self.nodes[node_id].child[i]
+						= Id::none();
+				}
+			}
+
+			// Attempt to push this collider (id) down the tree
+			if self.add_down(id, node_id, cell)? {
+				return Ok(());
+			}
+
+			// Try again, this time link if failed.
+			if self.nodes[node_id].branch_add_collider(id)
+				.is_none() // Is full, still!
+			{
+				self.add_link(id, node_id)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Add a collider to the chain of leaves linked from a node.
+	fn add_link(&mut self, id: Id, mut node_id: usize)
+		-> Result<(), OctreeError>
+	{
+		// Find a leaf with an open slot.
+		while let Some(link) = self.nodes[node_id].link() {
+			if self.nodes[link].leaf_add_collider(id).is_some() {
+				return Ok(());
+			}
+			node_id = link;
+		}
+
+		// All full, add a new leaf to the end of the chain.
+		let link_id = self.new_leaf();
+		self.nodes[node_id].child[LINK] = link_id;
+		self.nodes[usize::from(link_id)].leaf_add_collider(id)
+			.ok_or(OctreeError::Corrupt)
+	}
+
+	/// Move a collider down the tree, return true if it worked.
+	fn add_down(&mut self, id: Id, node_id: usize, cell: C)
+		-> Result<bool, OctreeError>
+	{
+		// Calculate bounds for this id.
+		let bounds = self.stored(id);
+
+		// Too small to subdivide, colliders must be linked instead.
+		if cell.half_len() <= S::from_f64(MIN_HALF_LEN) {
+			return Ok(false);
+		}
+
+		// can be put on a lower level.
+		if let Some(ch) = self.which_child(cell, bounds) {
+			let j = self.nodes[node_id].child[ch];
+
+			if j.is_some() {
+				// already a branch here, add collider to it.
+				self.add_inside(id, j, cell.child(ch))?;
+			} else {
+				// make a branch
+				let k = self.new_branch();
+				// set branch as the correct child
+				self.nodes[node_id].child[ch] = k;
+				// Add the collider
+				self.nodes[usize::from(k)]
+					.branch_add_collider(id)
+					.ok_or(OctreeError::Corrupt)?;
+			}
+			Ok(true)
+		} else {
+			Ok(false)
+		}
+	}
+
+	/// Add a new node
+	fn new_node(&mut self, n: node::Node<B>) -> Id {
+		if let Some(i) = self.garbage.pop() {
+			let k: usize = i.into();
+			self.nodes[k] = n;
+			self.is_garbage[k] = false;
+			k.into()
+		} else {
+			self.nodes.push(n);
+			self.is_garbage.push(false);
+			Id(self.nodes.len() as u32)
+		}
+	}
+
+	/// Add a node to the garbage, to be reused.
+	fn free_node(&mut self, id: Id) {
+		self.garbage.push(id);
+		self.is_garbage[usize::from(id)] = true;
+	}
+
+	/// Add a new leaf node
+	fn new_leaf(&mut self) -> Id {
+		self.new_node(node::Node::new_leaf())
+	}
+
+	/// Add a new branch node
+	fn new_branch(&mut self) -> Id {
+		self.new_node(node::Node::new_branch())
+	}
+
+	/// Take a collider out, or fail if `id` isn't in the tree.  The `Id` is
+	/// reused by the next collider added.
+	pub(crate) fn remove(&mut self, id: Id) -> Result<(), OctreeError> {
+		if self.stored_bounds(id).is_none() {
+			return Err(OctreeError::StaleId);
+		}
+
+		self.remove_id(id)?;
+		// Id is garbage now.
+		self.collider_garbage.push(id);
+		self.stored[usize::from(id)] = None;
+
+		Ok(())
+	}
+
+	/// Take a collider Id out of the tree, or the overflow list.
+	fn remove_id(&mut self, id: Id) -> Result<(), OctreeError> {
+		// Must have colliders already in the tree.
+		assert!(self.n_colliders > 0);
+
+		if let Some(i) = self.overflow.iter().position(|o| *o == id) {
+			self.overflow.swap_remove(i);
+			self.n_colliders -= 1;
+			return Ok(());
+		}
+
+		if self.root.is_none() {
+			return Err(OctreeError::Corrupt);
+		}
+
+		let cell = self.cell;
+		let root = self.root;
+		// Find and remove the collider Id from the tree.
+		let empty = self.remove_inside(id, root, cell)?.is_some();
+		// Decrement number of colliders
+		self.n_colliders -= 1;
+
+		if empty {
+			// The tree is empty.
+			self.reset_nodes();
+			return Ok(());
+		}
+		// A fixed root doesn't shrink.
+		if self.bounds.is_some() {
+			return Ok(());
+		}
+		// Shrink root if: 1 branch, no nodes
+		loop {
+			let root: usize = self.root.into();
+			if let Some(ch) = self.nodes[root].branch_is_one() {
+				// Add root to garbage.
+				let old = self.root;
+				self.free_node(old);
+				// Set new root
+				self.root = self.nodes[root].child[ch];
+				//
+				self.cell = self.cell.child(ch);
+			} else {
+				break;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Remove all of the nodes, and the root.
+	fn reset_nodes(&mut self) {
+		self.nodes.clear();
+		self.garbage.clear();
+		self.is_garbage.clear();
+		self.root = Id::none();
+	}
+
+	/// Rebuild the tree under a root fitted to the colliders in it (unless
+	/// it's fixed), putting those in the overflow list back if they fit.
+	pub(crate) fn rebalance(&mut self) {
+		let overflow = ::std::mem::take(&mut self.overflow);
+		let ids = self.query(|_| true, |_| true);
+		let bounds = ids.iter()
+			.map(|id| self.stored(*id))
+			.fold(None, |bounds: Option<C::Bounds>, b| {
+				Some(bounds.map_or(b, |bounds| bounds.union(b)))
+			});
+		let old = self.cell;
+
+		self.reset_nodes();
+		self.n_colliders = 0;
+
+		if let Some(bounds) = bounds {
+			// Keep the old root if it's smaller (or fixed).
+			let cell = Self::root_cell(bounds);
+			let cell = if self.bounds.is_some()
+				|| old.half_len() < cell.half_len()
+			{
+				old
+			} else {
+				cell
+			};
+
+			// Otherwise, the root grows from the first collider.
+			if self.within_max(cell.half_len()) {
+				self.cell = cell;
+				self.root = self.new_branch();
+			}
+		}
+
+		for id in ids.into_iter().chain(overflow) {
+			// If it can't be placed, it's in the overflow list.
+			let _ = self.insert_id(id);
+		}
+	}
+
+	/// Move a collider if `bounds` aren't its stored bounds, or fail if
+	/// `id` isn't in the tree, or the new bounds are invalid (then it stays
+	/// where it was).  Returns true if it moved.
+	pub(crate) fn update(&mut self, id: Id, bounds: C::Bounds)
+		-> Result<bool, OctreeError>
+	{
+		let stored = self.stored_bounds(id)
+			.ok_or(OctreeError::StaleId)?;
+
+		if (0..C::AXES).all(|axis| bounds.min_on(axis)
+			== stored.min_on(axis)
+			&& bounds.max_on(axis) == stored.max_on(axis))
+		{
+			return Ok(false);
+		}
+
+		self.check_bounds(bounds)?;
+		self.check_nodes()?;
+		self.remove_id(id)?;
+		self.stored[usize::from(id)] = Some(bounds);
+		self.insert_id(id)?;
+
+		Ok(true)
+	}
+
+	/// Remove an Id from the tree.  Returns the node if it's empty now,
+	/// to be removed, or an error if the Id isn't where it should be.
+	fn remove_inside(&mut self, id: Id, node_id: Id, cell: C)
+		-> Result<Option<Id>, OctreeError>
+	{
+		// Calculate bounds for this id.
+		let bounds = self.stored(id);
+		// Get node_id as usize
+		let node_id: usize = node_id.into();
+
+		// Bounds must collide with cell, and be in a branch.
+		if !self.loose_cell(cell).collide(bounds)
+			|| !self.nodes[node_id].is_branch()
+		{
+			return Err(OctreeError::Corrupt);
+		}
+
+		// Could be found on a lower level.
+		if let Some(ch) = self.which_child(cell, bounds) {
+			let j = self.nodes[node_id].child[ch];
+
+			if j.is_some() {
+				// Yes, there is a branch here, where the Id is!
+				// Remove it from inside this branch.
+				let cell = cell.child(ch);
+
+				if let Some(rm)
+					= self.remove_inside(id, j, cell)?
+				{ // Remove empty branch
+					// Child branch should be the one
+					// removed
+					assert_eq!(j, rm);
+					// Add to garbage.
+					self.free_node(rm);
+					// Remove child branch.
+					self.nodes[node_id].child[ch] =
+						Id::none();
+				}
+
+				// If the node is empty, mark for removal.
+				if self.nodes[node_id].is_empty() {
+					Ok(Some(node_id.into()))
+				} else {
+					Ok(None) // nothing to be removed.
+				}
+			} else {
+				// No, we don't have to descend - it's here!
+				self.remove_from_branch(id, node_id)
+			}
+		} else {
+			// No, we can't descend - it's here!
+			self.remove_from_branch(id, node_id)
+		}
+	}
+
+	/// Remove from branch, including any links that may exist.
+	fn remove_from_branch(&mut self, id: Id, node_id: usize)
+		-> Result<Option<Id>, OctreeError>
+	{
+		// Remove the collider
+		if self.nodes[node_id].remove_collider(id)
+			.is_some() // Found and removed
+		{
+			// If the node is empty, mark for removal.
+			if self.nodes[node_id].is_empty() {
+				return Ok(Some(node_id.into()));
+			} else {
+				return Ok(None);
+			}
+		}
+
+		// Couldn't Find it: Search Link Node
+		let link = self.nodes[node_id].link()
+			.ok_or(OctreeError::Corrupt)?;
+		let rm = self.remove_from_branch(id, link)?;
+
+		// If link leaf is now empty, remove.
+		if let Some(rm) = rm {
+			// Returned location should match LINK node location.
+			assert_eq!(rm, self.nodes[node_id].child[LINK]);
+			// Add to garbage.
+			self.free_node(rm);
+			// Splice the rest of the chain in place of the leaf.
+			self.nodes[node_id].child[LINK]
+				= self.nodes[link].child[LINK];
+		}
+
+		// If the node is empty now, mark for removal.
+		if self.nodes[node_id].is_empty() {
+			Ok(Some(node_id.into()))
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Rebuild the tree densely, without any garbage.  Colliders keep their
+	/// order, but move down to fill the gaps left by removed ones; returns
+	/// `(old, new)` for each changed `Id`.
+	pub(crate) fn compact(&mut self) -> Vec<(Id, Id)> {
+		// New collider `Id`s.
+		let mut remap = vec![Id::none(); self.stored.len()];
+		let mut moved = vec![];
+		let mut stored = Arena::with_capacity(self.len());
+
+		for (i, id) in remap.iter_mut().enumerate() {
+			if self.stored[i].is_some() {
+				stored.push(self.stored[i]);
+				*id = Id(stored.len() as u32);
+
+				if *id != Id::from(i) {
+					moved.push((Id::from(i), *id));
+				}
+			}
+		}
+
+		// New node `Id`s, breadth-first from the root.
+		let mut order = vec![];
+		let mut new_ids = vec![Id::none(); self.nodes.len()];
+
+		if self.root.is_some() {
+			order.push(usize::from(self.root));
+			new_ids[usize::from(self.root)] = Id(1);
+		}
+
+		let mut i = 0;
+		while i < order.len() {
+			let node = &self.nodes[order[i]];
+			let branches = if node.is_branch() {
+				B
+			} else {
+				0
+			};
+
+			for slot in (0..branches).chain(LINK..=LINK) {
+				let j = node.child[slot];
+				if j.is_some() {
+					let new = Id(order.len() as u32 + 1);
+
+					order.push(j.into());
+					new_ids[usize::from(j)] = new;
+				}
+			}
+			i += 1;
+		}
+
+		// Copy the nodes in order, with the new `Id`s.
+		let mut nodes = Arena::with_capacity(order.len());
+
+		for old in order {
+			let mut node = self.nodes[old].clone();
+			// Leaves have a sentinel instead of child branches.
+			let (branches, first) = if node.is_branch() {
+				(B, 0)
+			} else {
+				(0, 1)
+			};
+
+			for slot in first..=LINK {
+				let j = node.child[slot];
+				if j.is_none() {
+					continue;
+				}
+
+				let is_node = slot < branches || slot == LINK;
+
+				node.child[slot] = if is_node {
+					new_ids[usize::from(j)]
+				} else {
+					remap[usize::from(j)]
+				};
+			}
+
+			nodes.push(node);
+		}
+
+		for id in &mut self.overflow {
+			*id = remap[usize::from(*id)];
+		}
+
+		self.stored = stored;
+		self.collider_garbage = vec![];
+		self.is_garbage = vec![false; nodes.len()];
+		self.nodes = nodes;
+		self.garbage = vec![];
+		if self.root.is_some() {
+			self.root = Id(1);
+		}
+
+		moved
+	}
+
+	/// Reserve room for at least `additional` more colliders, and a node
+	/// for each of them.
+	pub(crate) fn reserve(&mut self, additional: usize) {
+		let colliders = additional
+			.saturating_sub(self.collider_garbage.len());
+		let nodes = additional.saturating_sub(self.garbage.len());
+
+		self.stored.reserve(colliders);
+		self.nodes.reserve(nodes);
+		self.is_garbage.reserve(nodes);
+	}
+
+	/// Give back as much unused memory as possible.
+	pub(crate) fn shrink_to_fit(&mut self) {
+		self.stored.shrink_to_fit();
+		self.collider_garbage.shrink_to_fit();
+		self.nodes.shrink_to_fit();
+		self.garbage.shrink_to_fit();
+		self.is_garbage.shrink_to_fit();
+	}
+
+	/// Get the stored bounds of a collider, or `None` if `id` isn't in the
+	/// tree.
+	pub(crate) fn stored_bounds(&self, id: Id) -> Option<C::Bounds> {
+		if id.is_none() || usize::from(id) >= self.stored.len() {
+			return None;
+		}
+
+		self.stored[usize::from(id)]
+	}
+
+	/// Get the stored bounds of a collider that must be in the tree.
+	pub(crate) fn stored(&self, id: Id) -> C::Bounds {
+		self.stored[usize::from(id)].expect("no such collider")
+	}
+
+	/// Get the number of colliders in the tree.
+	pub(crate) fn len(&self) -> usize {
+		self.n_colliders as usize
+	}
+
+	/// Check if there are no colliders in the tree.
+	pub(crate) fn is_empty(&self) -> bool {
+		self.n_colliders == 0
+	}
+
+	/// Get each pair of colliders whose stored bounds collide, with the
+	/// lesser `Id` first.
+	pub(crate) fn collision_pairs(&self) -> Vec<(Id, Id)> {
+		self.query(|_| true, |_| true).into_iter()
+			.flat_map(|id| self.pairs_with(id))
+			.collect()
+	}
+
+	/// Get the pairs of `id` with each collider it collides with that has
+	/// a greater `Id`, so that each pair is only found once.
+	pub(crate) fn pairs_with(&self, id: Id) -> Vec<(Id, Id)> {
+		let bounds = self.stored(id);
+
+		self.query(|cell| cell.collide(bounds),
+			|other| bounds.collide(other))
+			.into_iter()
+			.filter(|other| id.0 < other.0)
+			.map(|other| (id, other))
+			.collect()
+	}
+
+	/// Walk the nodes whose enlarged cells pass `node_test`, collecting the
+	/// colliders (and those in the overflow list) whose stored bounds pass
+	/// `collider_test`.
+	pub(crate) fn query<F, G>(&self, node_test: F, collider_test: G)
+		-> Vec<Id>
+		where F: Fn(C) -> bool, G: Fn(C::Bounds) -> bool
+	{
+		let mut found = if self.root.is_none() {
+			vec![]
+		} else {
+			self.query_from(vec![(self.root, self.cell)],
+				&node_test, &collider_test)
+		};
+
+		found.extend(self.overflow.iter()
+			.filter(|id| collider_test(self.stored(**id))));
+		found
+	}
+
+	/// Walk the nodes under those in `stack` whose enlarged cells pass
+	/// `node_test`, collecting the colliders whose stored bounds pass
+	/// `collider_test`.
+	pub(crate) fn query_from<F, G>(&self, stack: Vec<(Id, C)>, node_test: F,
+		collider_test: G) -> Vec<Id>
+		where F: Fn(C) -> bool, G: Fn(C::Bounds) -> bool
+	{
+		walk(&self.nodes, self.looseness, stack, node_test,
+			|id| collider_test(self.stored(id)))
+	}
+}
+
+/// Walk the nodes under those in `stack` whose enlarged cells pass
+/// `node_test`, collecting the colliders that pass `collider_test`.
+pub(crate) fn walk<N, C, S, F, G, const B: usize>(nodes: &N, looseness: S,
+	mut stack: Vec<(Id, C)>, node_test: F, collider_test: G) -> Vec<Id>
+	where N: ops::Index<usize, Output = node::Node<B>>, C: Cell<S>,
+		S: Scalar, F: Fn(C) -> bool, G: Fn(Id) -> bool
+{
+	let mut found = vec![];
+
+	while let Some((node_id, cell)) = stack.pop() {
+		if !node_test(cell.loose(looseness)) {
+			continue;
+		}
+
+		let node_id: usize = node_id.into();
+		node_colliders(nodes, node_id, &collider_test, &mut found);
+
+		// Child branches.
+		let node = &nodes[node_id];
+		for ch in 0..B {
+			let j = node.child[ch];
+			if j.is_some() {
+				stack.push((j, cell.child(ch)));
+			}
+		}
+	}
+
+	found
+}
+
+/// Collect the colliders in a branch that pass `collider_test`.
+pub(crate) fn node_colliders<N, G, const B: usize>(nodes: &N,
+	node_id: usize, collider_test: &G, found: &mut Vec<Id>)
+	where N: ops::Index<usize, Output = node::Node<B>>, G: Fn(Id) -> bool
+{
+	let node = &nodes[node_id];
+
+	// Colliders in this branch.
+	for id in &node.child[B..=14] {
+		if id.is_some() && collider_test(*id) {
+			found.push(*id);
+		}
+	}
+
+	// Colliders in linked leaves.
+	let mut link = node.link();
+	while let Some(l) = link {
+		for id in &nodes[l].child[1..=14] {
+			if id.is_some() && collider_test(*id) {
+				found.push(*id);
+			}
+		}
+		link = nodes[l].link();
+	}
+}
+
+impl<C, S, const B: usize> fmt::Display for Tree<C, S, B>
+	where C: Cell<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.root.is_none() {
+			writeln!(f, "No Root")?;
+		} else {
+			let root: usize = self.root.into();
+			writeln!(f, "Root {}:{:?}", root, self.cell)?;
+		}
+
+		if !self.overflow.is_empty() {
+			writeln!(f, "Overflow: {:?}", self.overflow)?;
+		}
+
+		for i in 0..self.nodes.len() {
+			if !self.is_garbage[i] {
+				writeln!(f, "{}: {}", i, self.nodes[i])?;
+				write!(f, "{}: ", i)?;
+				for j in B..=14 {
+					let index = self.nodes[i].child[j];
+					if index.is_some() {
+						let b = self.stored(index);
+						write!(f, "{:?},", b)?;
+					}
+				}
+				writeln!(f)?;
+			}
+		}
+
+		write!(f, "")
+	}
+}
+
+impl<C, S, const B: usize> fmt::Debug for Tree<C, S, B>
+	where C: Cell<S>, S: Scalar
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.root.is_none() {
+			writeln!(f, "No Root")?;
+		} else {
+			let root: usize = self.root.into();
+			writeln!(f, "root {}", root)?;
+		}
+
+		if !self.overflow.is_empty() {
+			writeln!(f, "overflow {:?}", self.overflow)?;
+		}
+
+		for i in 0..self.nodes.len() {
+			if !self.is_garbage[i] {
+				writeln!(f, "{}: {:?}", i, self.nodes[i])?;
+			}
+		}
+
+		write!(f, "")
+	}
+}
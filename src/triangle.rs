@@ -0,0 +1,274 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use *;
+
+/// Single-precision triangle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Triangle {
+	/// The first corner.
+	pub a: Vector,
+	/// The second corner (counter-clockwise from `a` on the front).
+	pub b: Vector,
+	/// The third corner.
+	pub c: Vector,
+}
+
+impl Triangle {
+	/// Create a new triangle from 3 points.
+	pub fn new(a: Vector, b: Vector, c: Vector) -> Triangle {
+		Triangle { a, b, c }
+	}
+
+	/// Get the unit normal (counter-clockwise winding is front facing).
+	///
+	/// # Panics
+	///
+	/// Panics if the triangle is degenerate (has no area).
+	pub fn normal(&self) -> Vector {
+		(self.b - self.a).cross(self.c - self.a).normalize()
+	}
+
+	/// Get the area of the triangle.
+	pub fn area(&self) -> f32 {
+		(self.b - self.a).cross(self.c - self.a).length() / 2.0
+	}
+
+	/// Get the center (centroid) of the triangle.
+	pub fn center(&self) -> Vector {
+		(self.a + self.b + self.c) / 3.0
+	}
+
+	/// Get the barycentric coordinates `(u, v, w)` of `p` projected onto
+	/// the triangle, such that `p = a * u + b * v + c * w`.  The result
+	/// has NaNs if the triangle is degenerate.
+	pub fn barycentric(&self, p: Vector) -> (f32, f32, f32) {
+		let v0 = self.b - self.a;
+		let v1 = self.c - self.a;
+		let v2 = p - self.a;
+		let d00 = v0.dot(v0);
+		let d01 = v0.dot(v1);
+		let d11 = v1.dot(v1);
+		let d20 = v2.dot(v0);
+		let d21 = v2.dot(v1);
+		let denom = d00 * d11 - d01 * d01;
+		let v = (d11 * d20 - d01 * d21) / denom;
+		let w = (d00 * d21 - d01 * d20) / denom;
+
+		(1.0 - v - w, v, w)
+	}
+
+	/// Get the point on (or in) the triangle closest to `p`.
+	pub fn closest_point(&self, p: Vector) -> Vector {
+		let (a, b, c) = (self.a, self.b, self.c);
+		let ab = b - a;
+		let ac = c - a;
+
+		// Vertex region outside A.
+		let ap = p - a;
+		let d1 = ab.dot(ap);
+		let d2 = ac.dot(ap);
+		if d1 <= 0.0 && d2 <= 0.0 {
+			return a;
+		}
+
+		// Vertex region outside B.
+		let bp = p - b;
+		let d3 = ab.dot(bp);
+		let d4 = ac.dot(bp);
+		if d3 >= 0.0 && d4 <= d3 {
+			return b;
+		}
+
+		// Edge region of AB.
+		let vc = d1 * d4 - d3 * d2;
+		if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+			return a + ab * (d1 / (d1 - d3));
+		}
+
+		// Vertex region outside C.
+		let cp = p - c;
+		let d5 = ab.dot(cp);
+		let d6 = ac.dot(cp);
+		if d6 >= 0.0 && d5 <= d6 {
+			return c;
+		}
+
+		// Edge region of AC.
+		let vb = d5 * d2 - d1 * d6;
+		if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+			return a + ac * (d2 / (d2 - d6));
+		}
+
+		// Edge region of BC.
+		let va = d3 * d6 - d5 * d4;
+		if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+			return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+		}
+
+		// Inside the face.
+		let denom = 1.0 / (va + vb + vc);
+		a + ab * (vb * denom) + ac * (vc * denom)
+	}
+
+	/// Check if `Triangle` collides with `BSphere`.
+	pub fn collide_bsphere(&self, sphere: BSphere) -> bool {
+		sphere.collide_point(self.closest_point(sphere.center))
+	}
+
+	/// Check if `Triangle` collides with `BBox` (Akenine-Möller separating
+	/// axis test).
+	pub fn collide_bbox(&self, bbox: BBox) -> bool {
+		let center = bbox.center();
+		let e = (bbox.max - bbox.min) / 2.0;
+
+		// Move the box to the origin.
+		let v = [self.a - center, self.b - center, self.c - center];
+		let f = [v[1] - v[0], v[2] - v[1], v[0] - v[2]];
+
+		// Project triangle onto `axis`, and check against box radius `r`.
+		let separated = |axis: Vector| {
+			let p0 = v[0].dot(axis);
+			let p1 = v[1].dot(axis);
+			let p2 = v[2].dot(axis);
+			let r = e.x * axis.x.abs() + e.y * axis.y.abs()
+				+ e.z * axis.z.abs();
+
+			p0.min(p1).min(p2) > r || p0.max(p1).max(p2) < -r
+		};
+
+		// 9 axes from the cross products of box and triangle edges.
+		let units = [
+			Vector::new(1.0, 0.0, 0.0),
+			Vector::new(0.0, 1.0, 0.0),
+			Vector::new(0.0, 0.0, 1.0),
+		];
+		for u in units.iter() {
+			for edge in f.iter() {
+				if separated(u.cross(*edge)) {
+					return false;
+				}
+			}
+		}
+
+		// 3 box face normals.
+		for u in units.iter() {
+			if separated(*u) {
+				return false;
+			}
+		}
+
+		// Triangle face normal.
+		!separated(f[0].cross(f[1]))
+	}
+
+	/// Check if `Triangle` collides with `BCube`.
+	pub fn collide_bcube(&self, bcube: BCube) -> bool {
+		self.collide_bbox(bcube.to_bbox())
+	}
+
+	/// Split the triangle by a `Plane`.  Returns the triangles in front of
+	/// the plane, and those behind it.  A triangle lying on the plane is
+	/// in front if it faces the same way as the plane.
+	pub fn split(&self, plane: &Plane) -> (Vec<Triangle>, Vec<Triangle>) {
+		let (front, back) = Polygon::from(*self).clip(plane);
+
+		(front.triangles(), back.triangles())
+	}
+
+	/// Cast a ray from `origin` along `direction` (Möller–Trumbore, both
+	/// sides).  Returns `t` for the hit at `origin + direction * t`, or
+	/// `None` if it misses.
+	pub fn intersect_ray(&self, origin: Vector, direction: Vector)
+		-> Option<f32>
+	{
+		let e1 = self.b - self.a;
+		let e2 = self.c - self.a;
+		let p = direction.cross(e2);
+		let det = e1.dot(p);
+
+		// Parallel to the triangle.
+		if det.abs() <= f32::EPSILON {
+			return None;
+		}
+
+		let inv_det = 1.0 / det;
+		let s = origin - self.a;
+		let u = s.dot(p) * inv_det;
+		if !(0.0..=1.0).contains(&u) {
+			return None;
+		}
+
+		let q = s.cross(e1);
+		let v = direction.dot(q) * inv_det;
+		if v < 0.0 || u + v > 1.0 {
+			return None;
+		}
+
+		let t = e2.dot(q) * inv_det;
+		if t >= 0.0 {
+			Some(t)
+		} else {
+			None
+		}
+	}
+
+	/// Get the `BBox` that contains this `Triangle`.
+	pub fn bbox(&self) -> BBox {
+		let min = Vector::new(
+			self.a.x.min(self.b.x).min(self.c.x),
+			self.a.y.min(self.b.y).min(self.c.y),
+			self.a.z.min(self.b.z).min(self.c.z),
+		);
+		let max = Vector::new(
+			self.a.x.max(self.b.x).max(self.c.x),
+			self.a.y.max(self.b.y).max(self.c.y),
+			self.a.z.max(self.b.z).max(self.c.z),
+		);
+
+		BBox::new(min, max)
+	}
+}
+
+impl Collider for Triangle {
+	fn bbox(&self) -> BBox {
+		Triangle::bbox(self)
+	}
+}
+
+#[test]
+fn test_triangle() {
+	let t = Triangle::new(vector!(0.0, 0.0, 0.0), vector!(4.0, 0.0, 0.0),
+		vector!(0.0, 4.0, 0.0));
+
+	assert_eq!(t.normal(), vector!(0.0, 0.0, 1.0));
+	assert_eq!(t.area(), 8.0);
+	assert_eq!(t.barycentric(vector!(2.0, 0.0, 5.0)), (0.5, 0.5, 0.0));
+	assert_eq!(t.closest_point(vector!(1.0, 1.0, 3.0)),
+		vector!(1.0, 1.0, 0.0));
+	assert_eq!(t.closest_point(vector!(-1.0, -1.0, 0.0)),
+		vector!(0.0, 0.0, 0.0));
+	assert_eq!(t.closest_point(vector!(3.0, 3.0, 0.0)),
+		vector!(2.0, 2.0, 0.0));
+
+	assert!(t.collide_bbox(BBox::new(vector!(1.0, 1.0, -1.0),
+		vector!(2.0, 2.0, 1.0))));
+	assert!(!t.collide_bbox(BBox::new(vector!(1.0, 1.0, 0.5),
+		vector!(2.0, 2.0, 1.0))));
+	assert!(!t.collide_bbox(BBox::new(vector!(2.5, 2.5, -1.0),
+		vector!(3.0, 3.0, 1.0))));
+
+	assert_eq!(t.intersect_ray(vector!(1.0, 1.0, 5.0),
+		vector!(0.0, 0.0, -1.0)), Some(5.0));
+	assert_eq!(t.intersect_ray(vector!(3.0, 3.0, 5.0),
+		vector!(0.0, 0.0, -1.0)), None);
+
+	let (front, back) = t.split(&Plane::new(vector!(1.0, 0.0, 0.0), 1.0));
+	assert_eq!(front.len(), 1);
+	assert_eq!(back.len(), 2);
+	let area: f32 = front.iter().chain(back.iter()).map(|t| t.area()).sum();
+	assert!((area - t.area()).abs() < 0.0001);
+}
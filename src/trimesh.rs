@@ -0,0 +1,173 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::fmt;
+use *;
+
+/// A triangle mesh collider, with triangles sorted into an `Octree`.
+pub struct TriMesh {
+	triangles: Octree<Triangle>,
+	bbox: BBox,
+}
+
+impl fmt::Debug for TriMesh {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "TriMesh({} triangles, {:?})", self.len(), self.bbox)
+	}
+}
+
+impl TriMesh {
+	/// Create a new `TriMesh` from a list of triangles.
+	pub fn new(triangles: &[Triangle]) -> TriMesh {
+		let mut octree = Octree::new();
+		let mut min = vector!(f32::INFINITY);
+		let mut max = vector!(f32::NEG_INFINITY);
+
+		for triangle in triangles {
+			let bbox = triangle.bbox();
+
+			min = Vector::new(min.x.min(bbox.min.x),
+				min.y.min(bbox.min.y), min.z.min(bbox.min.z));
+			max = Vector::new(max.x.max(bbox.max.x),
+				max.y.max(bbox.max.y), max.z.max(bbox.max.z));
+
+			octree.add(*triangle);
+		}
+
+		// An empty mesh is a point at the origin.
+		let bbox = if triangles.is_empty() {
+			BBox::new(Vector::zero(), Vector::zero())
+		} else {
+			BBox::new(min, max)
+		};
+
+		TriMesh { triangles: octree, bbox }
+	}
+
+	/// Get the number of triangles in the mesh.
+	pub fn len(&self) -> usize {
+		self.triangles.len()
+	}
+
+	/// Check if the mesh has no triangles.
+	pub fn is_empty(&self) -> bool {
+		self.triangles.is_empty()
+	}
+
+	/// Get the octree of triangles.
+	pub fn octree(&self) -> &Octree<Triangle> {
+		&self.triangles
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// nearest hit at `origin + direction * t` with the triangle that was
+	/// hit, or `None` if it misses.
+	pub fn raycast(&self, origin: Vector, direction: Vector)
+		-> Option<(f32, Triangle)>
+	{
+		let mut hit: Option<(f32, Triangle)> = None;
+
+		for id in self.triangles.query_ray(origin, direction) {
+			let triangle = self.triangles[id];
+
+			if let Some(t) = triangle.intersect_ray(origin, direction) {
+				if hit.map(|(near, _)| t < near).unwrap_or(true) {
+					hit = Some((t, triangle));
+				}
+			}
+		}
+
+		hit
+	}
+
+	/// Cast a ray from `origin` along `direction`.  Returns `t` for the
+	/// nearest hit at `origin + direction * t`, or `None` if it misses.
+	pub fn intersect_ray(&self, origin: Vector, direction: Vector)
+		-> Option<f32>
+	{
+		self.raycast(origin, direction).map(|(t, _)| t)
+	}
+
+	/// Get all of the triangles that collide with `BSphere`.
+	pub fn query_bsphere(&self, sphere: BSphere) -> Vec<Triangle> {
+		self.triangles.query_bbox(sphere.bbox()).into_iter()
+			.map(|id| self.triangles[id])
+			.filter(|triangle| triangle.collide_bsphere(sphere))
+			.collect()
+	}
+
+	/// Get all of the triangles that collide with `BBox`.
+	pub fn query_bbox(&self, bbox: BBox) -> Vec<Triangle> {
+		self.triangles.query_bbox(bbox).into_iter()
+			.map(|id| self.triangles[id])
+			.filter(|triangle| triangle.collide_bbox(bbox))
+			.collect()
+	}
+
+	/// Check if `TriMesh` collides with `BSphere`.
+	pub fn collide_bsphere(&self, sphere: BSphere) -> bool {
+		self.triangles.query_bbox(sphere.bbox()).into_iter()
+			.any(|id| self.triangles[id].collide_bsphere(sphere))
+	}
+
+	/// Check if `TriMesh` collides with `BBox`.
+	pub fn collide_bbox(&self, bbox: BBox) -> bool {
+		self.triangles.query_bbox(bbox).into_iter()
+			.any(|id| self.triangles[id].collide_bbox(bbox))
+	}
+
+	/// Get the `BBox` that contains this `TriMesh`.
+	pub fn bbox(&self) -> BBox {
+		self.bbox
+	}
+}
+
+impl Collider for TriMesh {
+	fn bbox(&self) -> BBox {
+		self.bbox
+	}
+}
+
+#[test]
+fn test_trimesh() {
+	// A 10x10 grid of quads on the XZ plane.
+	let mut triangles = vec![];
+	for x in 0..10 {
+		for z in 0..10 {
+			let (x, z) = (x as f32, z as f32);
+			let a = vector!(x, 0.0, z);
+			let b = vector!(x + 1.0, 0.0, z);
+			let c = vector!(x + 1.0, 0.0, z + 1.0);
+			let d = vector!(x, 0.0, z + 1.0);
+			triangles.push(Triangle::new(a, c, b));
+			triangles.push(Triangle::new(a, d, c));
+		}
+	}
+	let mesh = TriMesh::new(&triangles);
+
+	assert_eq!(mesh.len(), 200);
+	assert_eq!(mesh.bbox().min, vector!(0.0, 0.0, 0.0));
+	assert_eq!(mesh.bbox().max, vector!(10.0, 0.0, 10.0));
+
+	let (t, tri) = mesh.raycast(vector!(4.25, 3.0, 7.5),
+		vector!(0.0, -1.0, 0.0)).unwrap();
+	assert_eq!(t, 3.0);
+	assert!(tri.collide_bbox(BBox::new(vector!(4.25, -0.1, 7.5),
+		vector!(4.25, 0.1, 7.5))));
+	assert!(mesh.intersect_ray(vector!(11.0, 3.0, 5.0),
+		vector!(0.0, -1.0, 0.0)).is_none());
+
+	assert!(mesh.collide_bsphere(BSphere::new(vector!(5.0, 0.5, 5.0), 0.6)));
+	assert!(!mesh.collide_bsphere(BSphere::new(vector!(5.0, 0.5, 5.0), 0.4)));
+	// Sphere around a shared vertex touches all 6 triangles that share it.
+	assert_eq!(mesh.query_bsphere(BSphere::new(vector!(5.0, 0.0, 5.0), 0.1))
+		.len(), 6);
+
+	assert!(mesh.collide_bbox(BBox::new(vector!(2.0, -1.0, 2.0),
+		vector!(2.5, 1.0, 2.5))));
+	assert!(!mesh.collide_bbox(BBox::new(vector!(2.0, 0.1, 2.0),
+		vector!(2.5, 1.0, 2.5))));
+}
@@ -4,35 +4,41 @@
 // Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
 // https://www.boost.org/LICENSE_1_0.txt)
 
-use cgmath;
-use std::{fmt, mem, ops};
+use std::{fmt, iter, ops};
+use *;
 
-/// Single-precision 3D vector.
+/// 3D vector, generic over the `Scalar` type.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[allow(missing_docs)]
 #[repr(C)]
-pub struct Vector {
-	pub x: f32,
-	pub y: f32,
-	pub z: f32,
+pub struct Vec3<S> {
+	pub x: S,
+	pub y: S,
+	pub z: S,
 }
 
-impl From<f32> for Vector {
-	fn from(arg: f32) -> Self {
+/// Single-precision 3D vector.
+pub type Vector = Vec3<f32>;
+
+/// Double-precision 3D vector.
+pub type DVector = Vec3<f64>;
+
+impl<S: Scalar> From<S> for Vec3<S> {
+	fn from(arg: S) -> Self {
 		Self::new(arg, arg, arg)
 	}
 }
 
-impl fmt::Display for Vector {
+impl<S: Scalar> fmt::Display for Vec3<S> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(f, "{:?}", (self.x, self.y, self.z))
 	}
 }
 
-impl Vector {
+impl<S: Scalar> Vec3<S> {
 	/// Full constructor.
-	pub fn new(x: f32, y: f32, z: f32) -> Self {
-		Vector { x, y, z }
+	pub const fn new(x: S, y: S, z: S) -> Self {
+		Vec3 { x, y, z }
 	}
 
 	/// Zero constructor.
@@ -40,43 +46,52 @@ impl Vector {
 		Default::default()
 	}
 
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Vec3<T> {
+		Vec3::new(self.x.cast(), self.y.cast(), self.z.cast())
+	}
+
+	/// Add a `w` component.
+	pub fn extend(self, w: S) -> Vec4<S> {
+		Vec4::new(self.x, self.y, self.z, w)
+	}
+
+	/// Drop the `z` component.
+	pub fn truncate(self) -> Vec2<S> {
+		Vec2::new(self.x, self.y)
+	}
+
 	/// Returns the cross product of two vectors.
 	pub fn cross(self, rhs: Self) -> Self {
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let b: &cgmath::Vector3<f32> = rhs.as_ref().into();
-		let v: [f32; 3] = a.cross(*b).into();
-		v.into()
+		Vec3::new(
+			self.y * rhs.z - self.z * rhs.y,
+			self.z * rhs.x - self.x * rhs.z,
+			self.x * rhs.y - self.y * rhs.x,
+		)
 	}
 
-	/// Returns the acute angle between two vectors.
-	///
-	/// # Panics
-	///
-	/// Panics if `self` is the zero vector.
-	pub fn angle(self, rhs: Self) -> f32 {
-		(self.dot(rhs) / self.length()).acos()
+	/// Returns the angle between two vectors, from 0 to π radians.  The
+	/// result is NaN if either vector is zero.
+	pub fn angle(self, rhs: Self) -> S {
+		let cos = self.dot(rhs) / (self.length() * rhs.length());
+
+		// Rounding can push parallel vectors just outside -1 to 1.
+		cos.max(-S::one()).min(S::one()).acos()
 	}
 
 	/// Returns the dot product of two vectors.
-	pub fn dot(self, rhs: Vector) -> f32 {
-		use cgmath::InnerSpace;
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let b: &cgmath::Vector3<f32> = rhs.as_ref().into();
-		a.dot(*b)
+	pub fn dot(self, rhs: Self) -> S {
+		self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
 	}
 
 	/// Returns the length (magnitude) of the vector.
-	pub fn length(self) -> f32 {
-		use cgmath::InnerSpace;
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		a.magnitude()
+	pub fn length(self) -> S {
+		self.squared_length().sqrt()
 	}
 
 	/// Returns the squared length of the vector.
-	pub fn squared_length(self) -> f32 {
-		use cgmath::InnerSpace;
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		a.magnitude2()
+	pub fn squared_length(self) -> S {
+		self.dot(self)
 	}
 
 	/// Scales the vector to unit length.
@@ -84,42 +99,127 @@ impl Vector {
 	/// ## Panics
 	///
 	/// Panics if the vector is zero.
-	pub fn normalize(self) -> Vector {
-		use cgmath::InnerSpace;
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let v: [f32; 3] = a.normalize().into();
-		v.into()
+	pub fn normalize(self) -> Self {
+		let length = self.length();
+
+		assert!(length != S::zero());
+
+		self / length
+	}
+
+	/// Scales the vector to unit length, or returns `None` if it's too
+	/// close to zero (or not finite) to have a direction.
+	pub fn try_normalize(self) -> Option<Self> {
+		let length = self.length();
+
+		if length > S::epsilon() && length.is_finite() {
+			Some(self / length)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the distance between two points.
+	pub fn distance(self, rhs: Self) -> S {
+		(rhs - self).length()
+	}
+
+	/// Returns the component-wise minimum of two vectors.
+	pub fn min(self, rhs: Self) -> Self {
+		Vec3::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+	}
+
+	/// Returns the component-wise maximum of two vectors.
+	pub fn max(self, rhs: Self) -> Self {
+		Vec3::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+	}
+
+	/// Returns the component-wise absolute value.
+	pub fn abs(self) -> Self {
+		Vec3::new(self.x.abs(), self.y.abs(), self.z.abs())
+	}
+
+	/// Rounds each component down.
+	pub fn floor(self) -> Self {
+		Vec3::new(self.x.floor(), self.y.floor(), self.z.floor())
+	}
+
+	/// Rounds each component up.
+	pub fn ceil(self) -> Self {
+		Vec3::new(self.x.ceil(), self.y.ceil(), self.z.ceil())
+	}
+
+	/// Linearly interpolate from `self` (`t` is 0) to `rhs` (`t` is 1).
+	pub fn lerp(self, rhs: Self, t: S) -> Self {
+		self + (rhs - self) * t
+	}
+
+	/// Returns the part of the vector that's parallel to `onto`.  The
+	/// result is NaN if `onto` is zero.
+	pub fn project_on(self, onto: Self) -> Self {
+		onto * (self.dot(onto) / onto.squared_length())
+	}
+
+	/// Returns the part of the vector that's perpendicular to `from`.  The
+	/// result is NaN if `from` is zero.
+	pub fn reject_from(self, from: Self) -> Self {
+		self - self.project_on(from)
+	}
+
+	/// Reflect off of a surface with unit `normal`.
+	pub fn reflect(self, normal: Self) -> Self {
+		self - normal * (S::from_f64(2.0) * self.dot(normal))
+	}
+
+	/// Refract unit vector `self` through a surface with unit `normal`
+	/// (facing against `self`), where `eta` is the ratio of refractive
+	/// indices (from / to).  Returns `None` for total internal reflection.
+	pub fn refract(self, normal: Self, eta: S) -> Option<Self> {
+		let cos = self.dot(normal);
+		let k = S::one() - eta * eta * (S::one() - cos * cos);
+
+		if k < S::zero() {
+			None
+		} else {
+			Some(self * eta - normal * (eta * cos + k.sqrt()))
+		}
+	}
+
+	/// Check that no component is infinite or NaN.
+	pub fn is_finite(self) -> bool {
+		self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+	}
+
+	/// Check that each component is within `epsilon` of `rhs`.
+	pub fn approx_eq(self, rhs: Self, epsilon: S) -> bool {
+		(self.x - rhs.x).abs() <= epsilon
+			&& (self.y - rhs.y).abs() <= epsilon
+			&& (self.z - rhs.z).abs() <= epsilon
 	}
 }
 
-impl ops::Add<Vector> for Vector {
-	type Output = Vector;
-	fn add(self, rhs: Vector) -> Self::Output {
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let b: &cgmath::Vector3<f32> = rhs.as_ref().into();
-		let v: [f32; 3] = (a + b).into();
-		v.into()
+impl<S: Scalar> ops::Add<Vec3<S>> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn add(self, rhs: Vec3<S>) -> Self::Output {
+		Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
 	}
 }
 
-impl ops::AddAssign<Vector> for Vector {
-	fn add_assign(&mut self, rhs: Vector) {
+impl<S: Scalar> ops::AddAssign<Vec3<S>> for Vec3<S> {
+	fn add_assign(&mut self, rhs: Vec3<S>) {
 		*self = *self + rhs;
 	}
 }
 
-impl ops::Sub<Vector> for Vector {
-	type Output = Vector;
-	fn sub(self, rhs: Vector) -> Self::Output {
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let b: &cgmath::Vector3<f32> = rhs.as_ref().into();
-		let v: [f32; 3] = (a - b).into();
-		v.into()
+impl<S: Scalar> ops::Sub<Vec3<S>> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn sub(self, rhs: Vec3<S>) -> Self::Output {
+		Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
 	}
 }
 
-impl ops::SubAssign<Vector> for Vector {
-	fn sub_assign(&mut self, rhs: Vector) {
+impl<S: Scalar> ops::SubAssign<Vec3<S>> for Vec3<S> {
+	fn sub_assign(&mut self, rhs: Vec3<S>) {
 		*self = *self - rhs;
 	}
 }
@@ -127,70 +227,194 @@ impl ops::SubAssign<Vector> for Vector {
 impl ops::Mul<Vector> for f32 {
 	type Output = Vector;
 	fn mul(self, arg: Vector) -> Self::Output {
-		let a: &cgmath::Vector3<f32> = arg.as_ref().into();
-		let v: [f32; 3] = (self * a).into();
-		v.into()
+		arg * self
 	}
 }
 
-impl ops::Mul<f32> for Vector {
-	type Output = Vector;
-	fn mul(self, arg: f32) -> Self::Output {
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let v: [f32; 3] = (arg * a).into();
-		v.into()
+impl ops::Mul<DVector> for f64 {
+	type Output = DVector;
+	fn mul(self, arg: DVector) -> Self::Output {
+		arg * self
+	}
+}
+
+impl<S: Scalar> ops::Mul<S> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn mul(self, arg: S) -> Self::Output {
+		Vec3::new(self.x * arg, self.y * arg, self.z * arg)
 	}
 }
 
-impl ops::MulAssign<f32> for Vector {
-	fn mul_assign(&mut self, rhs: f32) {
+impl<S: Scalar> ops::MulAssign<S> for Vec3<S> {
+	fn mul_assign(&mut self, rhs: S) {
 		*self = *self * rhs;
 	}
 }
 
-impl ops::Div<f32> for Vector {
-	type Output = Vector;
-	fn div(self, arg: f32) -> Self::Output {
-		let a: &cgmath::Vector3<f32> = self.as_ref().into();
-		let v: [f32; 3] = (a / arg).into();
-		v.into()
+impl<S: Scalar> ops::Mul<Vec3<S>> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn mul(self, rhs: Vec3<S>) -> Self::Output {
+		Vec3::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+	}
+}
+
+impl<S: Scalar> ops::MulAssign<Vec3<S>> for Vec3<S> {
+	fn mul_assign(&mut self, rhs: Vec3<S>) {
+		*self = *self * rhs;
+	}
+}
+
+impl<S: Scalar> ops::Div<Vec3<S>> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn div(self, rhs: Vec3<S>) -> Self::Output {
+		Vec3::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z)
 	}
 }
 
-impl ops::DivAssign<f32> for Vector {
-	fn div_assign(&mut self, rhs: f32) {
+impl<S: Scalar> ops::DivAssign<Vec3<S>> for Vec3<S> {
+	fn div_assign(&mut self, rhs: Vec3<S>) {
 		*self = *self / rhs;
 	}
 }
 
-impl ops::Neg for Vector {
-	type Output = Vector;
+impl<S: Scalar> ops::Div<S> for Vec3<S> {
+	type Output = Vec3<S>;
+	fn div(self, arg: S) -> Self::Output {
+		Vec3::new(self.x / arg, self.y / arg, self.z / arg)
+	}
+}
 
-	fn neg(self) -> Vector {
-		Vector { x: -self.x, y: -self.y, z: -self.z }
+impl<S: Scalar> ops::DivAssign<S> for Vec3<S> {
+	fn div_assign(&mut self, rhs: S) {
+		*self = *self / rhs;
 	}
 }
 
-impl AsRef<[f32; 3]> for Vector {
-	fn as_ref(&self) -> &[f32; 3] {
-		unsafe {
-			mem::transmute(self)
+impl<S: Scalar> ops::Neg for Vec3<S> {
+	type Output = Vec3<S>;
+
+	fn neg(self) -> Vec3<S> {
+		Vec3 { x: -self.x, y: -self.y, z: -self.z }
+	}
+}
+
+impl<S> ops::Index<usize> for Vec3<S> {
+	type Output = S;
+
+	fn index(&self, index: usize) -> &S {
+		match index {
+			0 => &self.x,
+			1 => &self.y,
+			2 => &self.z,
+			a => panic!("index must be 0-2, not {}", a),
 		}
 	}
 }
 
-impl From<[f32; 3]> for Vector {
-	fn from(array: [f32; 3]) -> Self {
-		unsafe {
-			mem::transmute(array)
+impl<S> ops::IndexMut<usize> for Vec3<S> {
+	fn index_mut(&mut self, index: usize) -> &mut S {
+		match index {
+			0 => &mut self.x,
+			1 => &mut self.y,
+			2 => &mut self.z,
+			a => panic!("index must be 0-2, not {}", a),
 		}
 	}
 }
 
-impl Into<[f32; 3]> for Vector {
-	fn into(self) -> [f32; 3] {
+impl<S: Scalar> iter::Sum for Vec3<S> {
+	fn sum<I: Iterator<Item = Vec3<S>>>(iter: I) -> Self {
+		iter.fold(Vec3::zero(), |a, b| a + b)
+	}
+}
+
+impl<'a, S: Scalar> iter::Sum<&'a Vec3<S>> for Vec3<S> {
+	fn sum<I: Iterator<Item = &'a Vec3<S>>>(iter: I) -> Self {
+		iter.fold(Vec3::zero(), |a, b| a + *b)
+	}
+}
+
+impl<S> AsRef<[S; 3]> for Vec3<S> {
+	fn as_ref(&self) -> &[S; 3] {
+		// Safe because `Vec3` is `#[repr(C)]` with 3 fields of `S`.
 		unsafe {
-			mem::transmute(self)
+			&*(self as *const Vec3<S> as *const [S; 3])
 		}
 	}
 }
+
+impl<S: Copy> From<[S; 3]> for Vec3<S> {
+	fn from(array: [S; 3]) -> Self {
+		Vec3 { x: array[0], y: array[1], z: array[2] }
+	}
+}
+
+impl<S> From<Vec3<S>> for [S; 3] {
+	fn from(vector: Vec3<S>) -> [S; 3] {
+		[vector.x, vector.y, vector.z]
+	}
+}
+
+impl From<Vector> for DVector {
+	fn from(vector: Vector) -> DVector {
+		vector.cast()
+	}
+}
+
+#[test]
+fn test_vector_precision() {
+	let far = DVector::new(1.0e9, 0.0, 0.0) + DVector::new(0.25, 0.5, 0.0);
+	assert_eq!(far.x, 1_000_000_000.25);
+	assert_eq!((far - DVector::new(1.0e9, 0.0, 0.0)).x, 0.25);
+
+	let single: Vector = far.cast();
+	assert_eq!(single.x, 1.0e9);
+	assert_eq!(DVector::from(vector!(0.1, 0.2, 0.3)).cast::<f32>(),
+		vector!(0.1, 0.2, 0.3));
+	assert_eq!(2.0 * DVector::new(1.0, 2.0, 3.0), DVector::new(2.0, 4.0,
+		6.0));
+}
+
+#[test]
+fn test_vector_ops() {
+	let a = vector!(1.0, -2.0, 3.0);
+	let b = vector!(2.0, 4.0, -1.0);
+
+	assert_eq!(a * b, vector!(2.0, -8.0, -3.0));
+	assert_eq!(a / b, vector!(0.5, -0.5, -3.0));
+	assert_eq!((a[0], a[1], a[2]), (1.0, -2.0, 3.0));
+	assert_eq!(a.min(b), vector!(1.0, -2.0, -1.0));
+	assert_eq!(a.max(b), vector!(2.0, 4.0, 3.0));
+	assert_eq!(a.abs(), vector!(1.0, 2.0, 3.0));
+	assert_eq!(vector!(1.5, -1.5, 0.0).floor(), vector!(1.0, -2.0, 0.0));
+	assert_eq!(vector!(1.5, -1.5, 0.0).ceil(), vector!(2.0, -1.0, 0.0));
+	assert_eq!(a.lerp(b, 0.5), vector!(1.5, 1.0, 1.0));
+	assert_eq!(vector!(0.0).distance(vector!(3.0, 4.0, 0.0)), 5.0);
+	assert_eq!([a, b, a].iter().sum::<Vector>(), vector!(4.0, 0.0, 5.0));
+
+	// Angle uses both lengths.
+	let angle = vector!(2.0, 0.0, 0.0).angle(vector!(0.0, 3.0, 0.0));
+	assert!((angle - ::std::f32::consts::FRAC_PI_2).abs() < 0.0001);
+	assert_eq!(vector!(5.0, 0.0, 0.0).angle(vector!(3.0, 0.0, 0.0)), 0.0);
+
+	let v = vector!(3.0, 4.0, 0.0);
+	let x = vector!(2.0, 0.0, 0.0);
+	assert_eq!(v.project_on(x), vector!(3.0, 0.0, 0.0));
+	assert_eq!(v.reject_from(x), vector!(0.0, 4.0, 0.0));
+	assert_eq!(vector!(1.0, -1.0, 0.0).reflect(vector!(0.0, 1.0, 0.0)),
+		vector!(1.0, 1.0, 0.0));
+
+	// Straight through with no bend, and total internal reflection.
+	let down = vector!(0.0, -1.0, 0.0);
+	let up = vector!(0.0, 1.0, 0.0);
+	assert_eq!(down.refract(up, 1.5), Some(down));
+	let grazing = vector!(1.0, -0.1, 0.0).normalize();
+	assert!(grazing.refract(up, 1.5).is_none());
+	assert!(grazing.refract(up, 1.0).unwrap().approx_eq(grazing, 0.0001));
+
+	assert!(vector!(0.0).try_normalize().is_none());
+	assert_eq!(x.try_normalize(), Some(vector!(1.0, 0.0, 0.0)));
+	assert!(!vector!(1.0, f32::NAN, 0.0).is_finite());
+	assert!(a.approx_eq(a + vector!(0.0001), 0.001));
+	assert!(!a.approx_eq(a + vector!(0.01), 0.001));
+}
@@ -0,0 +1,383 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::{fmt, iter, ops};
+use *;
+
+/// 2D vector, generic over the `Scalar` type.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Vec2<S> {
+	pub x: S,
+	pub y: S,
+}
+
+/// Single-precision 2D vector.
+pub type Vector2 = Vec2<f32>;
+
+/// Double-precision 2D vector.
+pub type DVector2 = Vec2<f64>;
+
+impl<S: Scalar> From<S> for Vec2<S> {
+	fn from(arg: S) -> Self {
+		Self::new(arg, arg)
+	}
+}
+
+impl<S: Scalar> fmt::Display for Vec2<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?}", (self.x, self.y))
+	}
+}
+
+impl<S: Scalar> Vec2<S> {
+	/// Full constructor.
+	pub const fn new(x: S, y: S) -> Self {
+		Vec2 { x, y }
+	}
+
+	/// Zero constructor.
+	pub fn zero() -> Self {
+		Default::default()
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Vec2<T> {
+		Vec2::new(self.x.cast(), self.y.cast())
+	}
+
+	/// Add a `z` component.
+	pub fn extend(self, z: S) -> Vec3<S> {
+		Vec3::new(self.x, self.y, z)
+	}
+
+	/// Returns the 2D cross product (the Z of the 3D cross product), which
+	/// is positive if `rhs` is counter-clockwise from `self`.
+	pub fn cross(self, rhs: Self) -> S {
+		self.x * rhs.y - self.y * rhs.x
+	}
+
+	/// Returns the angle between two vectors, from 0 to π radians.  The
+	/// result is NaN if either vector is zero.
+	pub fn angle(self, rhs: Self) -> S {
+		let cos = self.dot(rhs) / (self.length() * rhs.length());
+
+		// Rounding can push parallel vectors just outside -1 to 1.
+		cos.max(-S::one()).min(S::one()).acos()
+	}
+
+	/// Returns the dot product of two vectors.
+	pub fn dot(self, rhs: Self) -> S {
+		self.x * rhs.x + self.y * rhs.y
+	}
+
+	/// Returns the length (magnitude) of the vector.
+	pub fn length(self) -> S {
+		self.squared_length().sqrt()
+	}
+
+	/// Returns the squared length of the vector.
+	pub fn squared_length(self) -> S {
+		self.dot(self)
+	}
+
+	/// Scales the vector to unit length.
+	///
+	/// ## Panics
+	///
+	/// Panics if the vector is zero.
+	pub fn normalize(self) -> Self {
+		let length = self.length();
+
+		assert!(length != S::zero());
+
+		self / length
+	}
+
+	/// Scales the vector to unit length, or returns `None` if it's too
+	/// close to zero (or not finite) to have a direction.
+	pub fn try_normalize(self) -> Option<Self> {
+		let length = self.length();
+
+		if length > S::epsilon() && length.is_finite() {
+			Some(self / length)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the distance between two points.
+	pub fn distance(self, rhs: Self) -> S {
+		(rhs - self).length()
+	}
+
+	/// Returns the component-wise minimum of two vectors.
+	pub fn min(self, rhs: Self) -> Self {
+		Vec2::new(self.x.min(rhs.x), self.y.min(rhs.y))
+	}
+
+	/// Returns the component-wise maximum of two vectors.
+	pub fn max(self, rhs: Self) -> Self {
+		Vec2::new(self.x.max(rhs.x), self.y.max(rhs.y))
+	}
+
+	/// Returns the component-wise absolute value.
+	pub fn abs(self) -> Self {
+		Vec2::new(self.x.abs(), self.y.abs())
+	}
+
+	/// Rounds each component down.
+	pub fn floor(self) -> Self {
+		Vec2::new(self.x.floor(), self.y.floor())
+	}
+
+	/// Rounds each component up.
+	pub fn ceil(self) -> Self {
+		Vec2::new(self.x.ceil(), self.y.ceil())
+	}
+
+	/// Linearly interpolate from `self` (`t` is 0) to `rhs` (`t` is 1).
+	pub fn lerp(self, rhs: Self, t: S) -> Self {
+		self + (rhs - self) * t
+	}
+
+	/// Returns the part of the vector that's parallel to `onto`.  The
+	/// result is NaN if `onto` is zero.
+	pub fn project_on(self, onto: Self) -> Self {
+		onto * (self.dot(onto) / onto.squared_length())
+	}
+
+	/// Returns the part of the vector that's perpendicular to `from`.  The
+	/// result is NaN if `from` is zero.
+	pub fn reject_from(self, from: Self) -> Self {
+		self - self.project_on(from)
+	}
+
+	/// Reflect off of a surface with unit `normal`.
+	pub fn reflect(self, normal: Self) -> Self {
+		self - normal * (S::from_f64(2.0) * self.dot(normal))
+	}
+
+	/// Refract unit vector `self` through a surface with unit `normal`
+	/// (facing against `self`), where `eta` is the ratio of refractive
+	/// indices (from / to).  Returns `None` for total internal reflection.
+	pub fn refract(self, normal: Self, eta: S) -> Option<Self> {
+		let cos = self.dot(normal);
+		let k = S::one() - eta * eta * (S::one() - cos * cos);
+
+		if k < S::zero() {
+			None
+		} else {
+			Some(self * eta - normal * (eta * cos + k.sqrt()))
+		}
+	}
+
+	/// Check that no component is infinite or NaN.
+	pub fn is_finite(self) -> bool {
+		self.x.is_finite() && self.y.is_finite()
+	}
+
+	/// Check that each component is within `epsilon` of `rhs`.
+	pub fn approx_eq(self, rhs: Self, epsilon: S) -> bool {
+		(self.x - rhs.x).abs() <= epsilon
+			&& (self.y - rhs.y).abs() <= epsilon
+	}
+}
+
+impl<S: Scalar> ops::Add<Vec2<S>> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn add(self, rhs: Vec2<S>) -> Self::Output {
+		Vec2::new(self.x + rhs.x, self.y + rhs.y)
+	}
+}
+
+impl<S: Scalar> ops::AddAssign<Vec2<S>> for Vec2<S> {
+	fn add_assign(&mut self, rhs: Vec2<S>) {
+		*self = *self + rhs;
+	}
+}
+
+impl<S: Scalar> ops::Sub<Vec2<S>> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn sub(self, rhs: Vec2<S>) -> Self::Output {
+		Vec2::new(self.x - rhs.x, self.y - rhs.y)
+	}
+}
+
+impl<S: Scalar> ops::SubAssign<Vec2<S>> for Vec2<S> {
+	fn sub_assign(&mut self, rhs: Vec2<S>) {
+		*self = *self - rhs;
+	}
+}
+
+impl ops::Mul<Vector2> for f32 {
+	type Output = Vector2;
+	fn mul(self, arg: Vector2) -> Self::Output {
+		arg * self
+	}
+}
+
+impl ops::Mul<DVector2> for f64 {
+	type Output = DVector2;
+	fn mul(self, arg: DVector2) -> Self::Output {
+		arg * self
+	}
+}
+
+impl<S: Scalar> ops::Mul<S> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn mul(self, arg: S) -> Self::Output {
+		Vec2::new(self.x * arg, self.y * arg)
+	}
+}
+
+impl<S: Scalar> ops::MulAssign<S> for Vec2<S> {
+	fn mul_assign(&mut self, rhs: S) {
+		*self = *self * rhs;
+	}
+}
+
+impl<S: Scalar> ops::Mul<Vec2<S>> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn mul(self, rhs: Vec2<S>) -> Self::Output {
+		Vec2::new(self.x * rhs.x, self.y * rhs.y)
+	}
+}
+
+impl<S: Scalar> ops::MulAssign<Vec2<S>> for Vec2<S> {
+	fn mul_assign(&mut self, rhs: Vec2<S>) {
+		*self = *self * rhs;
+	}
+}
+
+impl<S: Scalar> ops::Div<Vec2<S>> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn div(self, rhs: Vec2<S>) -> Self::Output {
+		Vec2::new(self.x / rhs.x, self.y / rhs.y)
+	}
+}
+
+impl<S: Scalar> ops::DivAssign<Vec2<S>> for Vec2<S> {
+	fn div_assign(&mut self, rhs: Vec2<S>) {
+		*self = *self / rhs;
+	}
+}
+
+impl<S: Scalar> ops::Div<S> for Vec2<S> {
+	type Output = Vec2<S>;
+	fn div(self, arg: S) -> Self::Output {
+		Vec2::new(self.x / arg, self.y / arg)
+	}
+}
+
+impl<S: Scalar> ops::DivAssign<S> for Vec2<S> {
+	fn div_assign(&mut self, rhs: S) {
+		*self = *self / rhs;
+	}
+}
+
+impl<S: Scalar> ops::Neg for Vec2<S> {
+	type Output = Vec2<S>;
+
+	fn neg(self) -> Vec2<S> {
+		Vec2 { x: -self.x, y: -self.y }
+	}
+}
+
+impl<S> ops::Index<usize> for Vec2<S> {
+	type Output = S;
+
+	fn index(&self, index: usize) -> &S {
+		match index {
+			0 => &self.x,
+			1 => &self.y,
+			a => panic!("index must be 0-1, not {}", a),
+		}
+	}
+}
+
+impl<S> ops::IndexMut<usize> for Vec2<S> {
+	fn index_mut(&mut self, index: usize) -> &mut S {
+		match index {
+			0 => &mut self.x,
+			1 => &mut self.y,
+			a => panic!("index must be 0-1, not {}", a),
+		}
+	}
+}
+
+impl<S: Scalar> iter::Sum for Vec2<S> {
+	fn sum<I: Iterator<Item = Vec2<S>>>(iter: I) -> Self {
+		iter.fold(Vec2::zero(), |a, b| a + b)
+	}
+}
+
+impl<'a, S: Scalar> iter::Sum<&'a Vec2<S>> for Vec2<S> {
+	fn sum<I: Iterator<Item = &'a Vec2<S>>>(iter: I) -> Self {
+		iter.fold(Vec2::zero(), |a, b| a + *b)
+	}
+}
+
+impl<S> AsRef<[S; 2]> for Vec2<S> {
+	fn as_ref(&self) -> &[S; 2] {
+		// Safe because `Vec2` is `#[repr(C)]` with 2 fields of `S`.
+		unsafe {
+			&*(self as *const Vec2<S> as *const [S; 2])
+		}
+	}
+}
+
+impl<S: Copy> From<[S; 2]> for Vec2<S> {
+	fn from(array: [S; 2]) -> Self {
+		Vec2 { x: array[0], y: array[1] }
+	}
+}
+
+impl<S> From<Vec2<S>> for [S; 2] {
+	fn from(vector: Vec2<S>) -> [S; 2] {
+		[vector.x, vector.y]
+	}
+}
+
+impl From<Vector2> for DVector2 {
+	fn from(vector: Vector2) -> DVector2 {
+		vector.cast()
+	}
+}
+
+#[test]
+fn test_vector2_ops() {
+	let a = Vector2::new(1.0, -2.0);
+	let b = Vector2::new(2.0, 4.0);
+
+	assert_eq!(a * b, Vector2::new(2.0, -8.0));
+	assert_eq!(a / b, Vector2::new(0.5, -0.5));
+	assert_eq!((a[0], a[1]), (1.0, -2.0));
+	assert_eq!(a.min(b), Vector2::new(1.0, -2.0));
+	assert_eq!(a.max(b), Vector2::new(2.0, 4.0));
+	assert_eq!(a.abs(), Vector2::new(1.0, 2.0));
+	assert_eq!(Vector2::new(1.5, -1.5).floor(), Vector2::new(1.0, -2.0));
+	assert_eq!(Vector2::new(1.5, -1.5).ceil(), Vector2::new(2.0, -1.0));
+	assert_eq!(a.lerp(b, 0.5), Vector2::new(1.5, 1.0));
+	assert_eq!(Vector2::zero().distance(Vector2::new(3.0, 4.0)), 5.0);
+	assert_eq!([a, b, a].iter().sum::<Vector2>(), Vector2::new(4.0, 0.0));
+
+	let v = Vector2::new(3.0, 4.0);
+	let x = Vector2::new(2.0, 0.0);
+	let up = Vector2::new(0.0, 1.0);
+	assert_eq!(v.project_on(x), Vector2::new(3.0, 0.0));
+	assert_eq!(v.reject_from(x), Vector2::new(0.0, 4.0));
+	assert_eq!(Vector2::new(1.0, -1.0).reflect(up), Vector2::new(1.0, 1.0));
+	assert_eq!((-up).refract(up, 1.5), Some(-up));
+	assert!(Vector2::new(1.0, -0.1).normalize().refract(up, 1.5).is_none());
+
+	assert!(Vector2::zero().try_normalize().is_none());
+	assert_eq!(x.try_normalize(), Some(Vector2::new(1.0, 0.0)));
+	assert!(!Vector2::new(1.0, f32::NAN).is_finite());
+	assert!(a.approx_eq(a + Vector2::from(0.0001), 0.001));
+	assert!(!a.approx_eq(a + Vector2::from(0.01), 0.001));
+}
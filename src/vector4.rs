@@ -0,0 +1,424 @@
+// Copyright Jeron A. Lau 2017-2018.
+// Copyright Douglas Lau 2017
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+use std::{fmt, iter, ops};
+use *;
+
+/// 4D (homogeneous) vector, generic over the `Scalar` type.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Vec4<S> {
+	pub x: S,
+	pub y: S,
+	pub z: S,
+	pub w: S,
+}
+
+/// Single-precision 4D vector.
+pub type Vector4 = Vec4<f32>;
+
+/// Double-precision 4D vector.
+pub type DVector4 = Vec4<f64>;
+
+impl<S: Scalar> From<S> for Vec4<S> {
+	fn from(arg: S) -> Self {
+		Self::new(arg, arg, arg, arg)
+	}
+}
+
+impl<S: Scalar> fmt::Display for Vec4<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?}", (self.x, self.y, self.z, self.w))
+	}
+}
+
+impl<S: Scalar> Vec4<S> {
+	/// Full constructor.
+	pub const fn new(x: S, y: S, z: S, w: S) -> Self {
+		Vec4 { x, y, z, w }
+	}
+
+	/// Zero constructor.
+	pub fn zero() -> Self {
+		Default::default()
+	}
+
+	/// A point at `p` (`w` is 1), which is moved by translations.
+	pub fn point(p: Vec3<S>) -> Self {
+		p.extend(S::one())
+	}
+
+	/// A direction `v` (`w` is 0), which isn't moved by translations.
+	pub fn direction(v: Vec3<S>) -> Self {
+		v.extend(S::zero())
+	}
+
+	/// Convert to another precision, rounding if it is less precise.
+	pub fn cast<T: Scalar>(self) -> Vec4<T> {
+		Vec4::new(self.x.cast(), self.y.cast(), self.z.cast(),
+			self.w.cast())
+	}
+
+	/// Drop `w`, without dividing by it.
+	pub fn truncate(self) -> Vec3<S> {
+		Vec3::new(self.x, self.y, self.z)
+	}
+
+	/// Convert from homogeneous coordinates by dividing by `w` (the
+	/// perspective divide).  Directions (`w` is 0) come out infinite.
+	pub fn to_cartesian(self) -> Vec3<S> {
+		self.truncate() / self.w
+	}
+
+	/// Returns the dot product of two vectors.
+	pub fn dot(self, rhs: Self) -> S {
+		self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+	}
+
+	/// Returns the length (magnitude) of the vector.
+	pub fn length(self) -> S {
+		self.squared_length().sqrt()
+	}
+
+	/// Returns the squared length of the vector.
+	pub fn squared_length(self) -> S {
+		self.dot(self)
+	}
+
+	/// Scales the vector to unit length.
+	///
+	/// ## Panics
+	///
+	/// Panics if the vector is zero.
+	pub fn normalize(self) -> Self {
+		let length = self.length();
+
+		assert!(length != S::zero());
+
+		self / length
+	}
+
+	/// Scales the vector to unit length, or returns `None` if it's too
+	/// close to zero (or not finite) to have a direction.
+	pub fn try_normalize(self) -> Option<Self> {
+		let length = self.length();
+
+		if length > S::epsilon() && length.is_finite() {
+			Some(self / length)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the distance between two points.
+	pub fn distance(self, rhs: Self) -> S {
+		(rhs - self).length()
+	}
+
+	/// Returns the component-wise minimum of two vectors.
+	pub fn min(self, rhs: Self) -> Self {
+		Vec4::new(self.x.min(rhs.x), self.y.min(rhs.y),
+			self.z.min(rhs.z), self.w.min(rhs.w))
+	}
+
+	/// Returns the component-wise maximum of two vectors.
+	pub fn max(self, rhs: Self) -> Self {
+		Vec4::new(self.x.max(rhs.x), self.y.max(rhs.y),
+			self.z.max(rhs.z), self.w.max(rhs.w))
+	}
+
+	/// Returns the component-wise absolute value.
+	pub fn abs(self) -> Self {
+		Vec4::new(self.x.abs(), self.y.abs(), self.z.abs(), self.w.abs())
+	}
+
+	/// Rounds each component down.
+	pub fn floor(self) -> Self {
+		Vec4::new(self.x.floor(), self.y.floor(), self.z.floor(),
+			self.w.floor())
+	}
+
+	/// Rounds each component up.
+	pub fn ceil(self) -> Self {
+		Vec4::new(self.x.ceil(), self.y.ceil(), self.z.ceil(),
+			self.w.ceil())
+	}
+
+	/// Linearly interpolate from `self` (`t` is 0) to `rhs` (`t` is 1).
+	pub fn lerp(self, rhs: Self, t: S) -> Self {
+		self + (rhs - self) * t
+	}
+
+	/// Returns the part of the vector that's parallel to `onto`.  The
+	/// result is NaN if `onto` is zero.
+	pub fn project_on(self, onto: Self) -> Self {
+		onto * (self.dot(onto) / onto.squared_length())
+	}
+
+	/// Returns the part of the vector that's perpendicular to `from`.  The
+	/// result is NaN if `from` is zero.
+	pub fn reject_from(self, from: Self) -> Self {
+		self - self.project_on(from)
+	}
+
+	/// Reflect off of a surface with unit `normal`.
+	pub fn reflect(self, normal: Self) -> Self {
+		self - normal * (S::from_f64(2.0) * self.dot(normal))
+	}
+
+	/// Refract unit vector `self` through a surface with unit `normal`
+	/// (facing against `self`), where `eta` is the ratio of refractive
+	/// indices (from / to).  Returns `None` for total internal reflection.
+	pub fn refract(self, normal: Self, eta: S) -> Option<Self> {
+		let cos = self.dot(normal);
+		let k = S::one() - eta * eta * (S::one() - cos * cos);
+
+		if k < S::zero() {
+			None
+		} else {
+			Some(self * eta - normal * (eta * cos + k.sqrt()))
+		}
+	}
+
+	/// Check that no component is infinite or NaN.
+	pub fn is_finite(self) -> bool {
+		self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+			&& self.w.is_finite()
+	}
+
+	/// Check that each component is within `epsilon` of `rhs`.
+	pub fn approx_eq(self, rhs: Self, epsilon: S) -> bool {
+		(self.x - rhs.x).abs() <= epsilon
+			&& (self.y - rhs.y).abs() <= epsilon
+			&& (self.z - rhs.z).abs() <= epsilon
+			&& (self.w - rhs.w).abs() <= epsilon
+	}
+}
+
+impl<S: Scalar> ops::Add<Vec4<S>> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn add(self, rhs: Vec4<S>) -> Self::Output {
+		Vec4::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z,
+			self.w + rhs.w)
+	}
+}
+
+impl<S: Scalar> ops::AddAssign<Vec4<S>> for Vec4<S> {
+	fn add_assign(&mut self, rhs: Vec4<S>) {
+		*self = *self + rhs;
+	}
+}
+
+impl<S: Scalar> ops::Sub<Vec4<S>> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn sub(self, rhs: Vec4<S>) -> Self::Output {
+		Vec4::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z,
+			self.w - rhs.w)
+	}
+}
+
+impl<S: Scalar> ops::SubAssign<Vec4<S>> for Vec4<S> {
+	fn sub_assign(&mut self, rhs: Vec4<S>) {
+		*self = *self - rhs;
+	}
+}
+
+impl ops::Mul<Vector4> for f32 {
+	type Output = Vector4;
+	fn mul(self, arg: Vector4) -> Self::Output {
+		arg * self
+	}
+}
+
+impl ops::Mul<DVector4> for f64 {
+	type Output = DVector4;
+	fn mul(self, arg: DVector4) -> Self::Output {
+		arg * self
+	}
+}
+
+impl<S: Scalar> ops::Mul<S> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn mul(self, arg: S) -> Self::Output {
+		Vec4::new(self.x * arg, self.y * arg, self.z * arg, self.w * arg)
+	}
+}
+
+impl<S: Scalar> ops::MulAssign<S> for Vec4<S> {
+	fn mul_assign(&mut self, rhs: S) {
+		*self = *self * rhs;
+	}
+}
+
+impl<S: Scalar> ops::Mul<Vec4<S>> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn mul(self, rhs: Vec4<S>) -> Self::Output {
+		Vec4::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z,
+			self.w * rhs.w)
+	}
+}
+
+impl<S: Scalar> ops::MulAssign<Vec4<S>> for Vec4<S> {
+	fn mul_assign(&mut self, rhs: Vec4<S>) {
+		*self = *self * rhs;
+	}
+}
+
+impl<S: Scalar> ops::Div<Vec4<S>> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn div(self, rhs: Vec4<S>) -> Self::Output {
+		Vec4::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z,
+			self.w / rhs.w)
+	}
+}
+
+impl<S: Scalar> ops::DivAssign<Vec4<S>> for Vec4<S> {
+	fn div_assign(&mut self, rhs: Vec4<S>) {
+		*self = *self / rhs;
+	}
+}
+
+impl<S: Scalar> ops::Div<S> for Vec4<S> {
+	type Output = Vec4<S>;
+	fn div(self, arg: S) -> Self::Output {
+		Vec4::new(self.x / arg, self.y / arg, self.z / arg, self.w / arg)
+	}
+}
+
+impl<S: Scalar> ops::DivAssign<S> for Vec4<S> {
+	fn div_assign(&mut self, rhs: S) {
+		*self = *self / rhs;
+	}
+}
+
+impl<S: Scalar> ops::Neg for Vec4<S> {
+	type Output = Vec4<S>;
+
+	fn neg(self) -> Vec4<S> {
+		Vec4 { x: -self.x, y: -self.y, z: -self.z, w: -self.w }
+	}
+}
+
+impl<S> ops::Index<usize> for Vec4<S> {
+	type Output = S;
+
+	fn index(&self, index: usize) -> &S {
+		match index {
+			0 => &self.x,
+			1 => &self.y,
+			2 => &self.z,
+			3 => &self.w,
+			a => panic!("index must be 0-3, not {}", a),
+		}
+	}
+}
+
+impl<S> ops::IndexMut<usize> for Vec4<S> {
+	fn index_mut(&mut self, index: usize) -> &mut S {
+		match index {
+			0 => &mut self.x,
+			1 => &mut self.y,
+			2 => &mut self.z,
+			3 => &mut self.w,
+			a => panic!("index must be 0-3, not {}", a),
+		}
+	}
+}
+
+impl<S: Scalar> iter::Sum for Vec4<S> {
+	fn sum<I: Iterator<Item = Vec4<S>>>(iter: I) -> Self {
+		iter.fold(Vec4::zero(), |a, b| a + b)
+	}
+}
+
+impl<'a, S: Scalar> iter::Sum<&'a Vec4<S>> for Vec4<S> {
+	fn sum<I: Iterator<Item = &'a Vec4<S>>>(iter: I) -> Self {
+		iter.fold(Vec4::zero(), |a, b| a + *b)
+	}
+}
+
+impl<S> AsRef<[S; 4]> for Vec4<S> {
+	fn as_ref(&self) -> &[S; 4] {
+		// Safe because `Vec4` is `#[repr(C)]` with 4 fields of `S`.
+		unsafe {
+			&*(self as *const Vec4<S> as *const [S; 4])
+		}
+	}
+}
+
+impl<S: Copy> From<[S; 4]> for Vec4<S> {
+	fn from(array: [S; 4]) -> Self {
+		Vec4 { x: array[0], y: array[1], z: array[2], w: array[3] }
+	}
+}
+
+impl<S> From<Vec4<S>> for [S; 4] {
+	fn from(vector: Vec4<S>) -> [S; 4] {
+		[vector.x, vector.y, vector.z, vector.w]
+	}
+}
+
+impl From<Vector4> for DVector4 {
+	fn from(vector: Vector4) -> DVector4 {
+		vector.cast()
+	}
+}
+
+#[test]
+fn test_vector4_homogeneous() {
+	let m = Matrix::default().t(vector!(1.0, 2.0, 3.0));
+	let p = vector!(1.0, 1.0, 1.0);
+
+	// Points move with translation, directions don't.
+	assert_eq!(m.transform_point(p), vector!(2.0, 3.0, 4.0));
+	assert_eq!(m.transform_vector(p), p);
+	assert_eq!(m * Vector4::point(p), Vector4::new(2.0, 3.0, 4.0, 1.0));
+	assert_eq!(m * (p, 1.0), vector!(2.0, 3.0, 4.0));
+
+	// The far plane of a perspective projection maps to 1.0 after the
+	// divide, whatever the distance from the center.
+	let proj = Matrix::finite_perspective_projection(1.0, 1.0, 1.0, 100.0);
+	let far = proj.project_point(vector!(10.0, -5.0, -100.0));
+	assert!((far.z - 1.0).abs() < 0.0001);
+	let clip = proj * Vector4::point(vector!(0.0, 0.0, -50.0));
+	assert_eq!(clip.w, 50.0);
+	assert_eq!(clip.to_cartesian(), clip.truncate() / 50.0);
+}
+
+#[test]
+fn test_vector4_ops() {
+	let a = Vector4::new(1.0, -2.0, 3.0, 0.5);
+	let b = Vector4::new(2.0, 4.0, -1.0, 1.0);
+
+	assert_eq!(a * b, Vector4::new(2.0, -8.0, -3.0, 0.5));
+	assert_eq!(a / b, Vector4::new(0.5, -0.5, -3.0, 0.5));
+	assert_eq!((a[0], a[1], a[2], a[3]), (1.0, -2.0, 3.0, 0.5));
+	assert_eq!(a.min(b), Vector4::new(1.0, -2.0, -1.0, 0.5));
+	assert_eq!(a.max(b), Vector4::new(2.0, 4.0, 3.0, 1.0));
+	assert_eq!(a.abs(), Vector4::new(1.0, 2.0, 3.0, 0.5));
+	let c = Vector4::new(1.5, -1.5, 0.0, 2.5);
+	assert_eq!(c.floor(), Vector4::new(1.0, -2.0, 0.0, 2.0));
+	assert_eq!(c.ceil(), Vector4::new(2.0, -1.0, 0.0, 3.0));
+	assert_eq!(a.lerp(b, 0.5), Vector4::new(1.5, 1.0, 1.0, 0.75));
+	let d = Vector4::new(1.0, 2.0, 2.0, 4.0);
+	assert_eq!(Vector4::zero().distance(d), 5.0);
+	assert_eq!([a, b, a].iter().sum::<Vector4>(),
+		Vector4::new(4.0, 0.0, 5.0, 2.0));
+
+	let x = Vector4::new(2.0, 0.0, 0.0, 0.0);
+	assert_eq!(d.project_on(x), Vector4::new(1.0, 0.0, 0.0, 0.0));
+	assert_eq!(d.reject_from(x), Vector4::new(0.0, 2.0, 2.0, 4.0));
+	let w = Vector4::new(0.0, 0.0, 0.0, 1.0);
+	assert_eq!(d.reflect(w), Vector4::new(1.0, 2.0, 2.0, -4.0));
+	assert_eq!((-w).refract(w, 1.5), Some(-w));
+
+	assert!(Vector4::zero().try_normalize().is_none());
+	assert_eq!(x.try_normalize(), Some(Vector4::new(1.0, 0.0, 0.0, 0.0)));
+	assert!(!Vector4::new(1.0, 0.0, 0.0, f32::NAN).is_finite());
+	assert!(a.approx_eq(a + Vector4::from(0.0001), 0.001));
+	assert!(!a.approx_eq(a + Vector4::from(0.01), 0.001));
+}